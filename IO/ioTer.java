@@ -1,8 +1,15 @@
 public class ioTer {
+    static {
+        System.loadLibrary("native_io");
+    }
+
     public static native void prints(String msg);
     public static native void printca(char[] msg);
 	public static native void printd(double a);
 	public static native void printi(int a);
 	public static native int scani();
 	public static native double scand();
+	public static native String scans();
+	public static native String readLine();
+	public static native int[] scanai(int count);
 }
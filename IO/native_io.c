@@ -1,34 +1,92 @@
 // native_io.c
+#define _POSIX_C_SOURCE 200809L
+#include <jni.h>
 #include <stdio.h>
-#include <stdarg.h>
+#include <stdlib.h>
+#include <string.h>
 
-// Simplified JNI: no real JNIEnv, just raw pointers for now
-//JNIEXPORT void JNICALL Java_ioTer_prints(const char* msg) {
-void Java_ioTer_prints(const char* msg) {
-    printf("%s\n", msg);
+JNIEXPORT void JNICALL Java_ioTer_prints(JNIEnv* env, jclass clazz, jstring msg) {
+    const char* chars = (*env)->GetStringUTFChars(env, msg, NULL);
+    if (chars == NULL) {
+        return;
+    }
+    printf("%s\n", chars);
+    (*env)->ReleaseStringUTFChars(env, msg, chars);
 }
 
-void Java_ioTer_printca(const char* msg) {
-    printf("%s", msg);
+JNIEXPORT void JNICALL Java_ioTer_printca(JNIEnv* env, jclass clazz, jcharArray msg) {
+    jsize length = (*env)->GetArrayLength(env, msg);
+    jchar* chars = (*env)->GetCharArrayElements(env, msg, NULL);
+    if (chars == NULL) {
+        return;
+    }
+    for (jsize i = 0; i < length; i++) {
+        putchar(chars[i] < 0x80 ? chars[i] : '?');
+    }
+    (*env)->ReleaseCharArrayElements(env, msg, chars, JNI_ABORT);
 }
-void Java_ioTer_printd(double number) {
-    printf("%lf\n", number); 
+
+JNIEXPORT void JNICALL Java_ioTer_printd(JNIEnv* env, jclass clazz, jdouble number) {
+    printf("%lf\n", number);
 }
 
-void Java_ioTer_printi(int number) {
+JNIEXPORT void JNICALL Java_ioTer_printi(JNIEnv* env, jclass clazz, jint number) {
     printf("%d\n", number);
 }
 
-int Java_ioTer_scani() {
-	int value;
+JNIEXPORT jint JNICALL Java_ioTer_scani(JNIEnv* env, jclass clazz) {
+    jint value;
     scanf("%d", &value);
-	return value;
+    return value;
 }
 
-double Java_ioTer_scand() {
-	double value;
+JNIEXPORT jdouble JNICALL Java_ioTer_scand(JNIEnv* env, jclass clazz) {
+    jdouble value;
     scanf("%lf", &value);
-	return value;
+    return value;
+}
+// the next whitespace separated word of stdin, null at end of input
+JNIEXPORT jstring JNICALL Java_ioTer_scans(JNIEnv* env, jclass clazz) {
+    char word[256];
+    if (scanf("%255s", word) != 1) {
+        return NULL;
+    }
+    return (*env)->NewStringUTF(env, word);
+}
+
+// the next line of stdin without its line terminator, null at end of input
+JNIEXPORT jstring JNICALL Java_ioTer_readLine(JNIEnv* env, jclass clazz) {
+    char* line = NULL;
+    size_t capacity = 0;
+    ssize_t length = getline(&line, &capacity, stdin);
+    if (length < 0) {
+        free(line);
+        return NULL;
+    }
+    if (length > 0 && line[length - 1] == '\n') {
+        line[--length] = '\0';
+    }
+    // NewStringUTF copies the characters, the buffer getline allocated is ours to free
+    jstring result = (*env)->NewStringUTF(env, line);
+    free(line);
+    return result;
 }
-// Compile to .so
-// gcc -shared -fPIC -o libnative_io.so native_io.c
+
+// count integers read from stdin
+JNIEXPORT jintArray JNICALL Java_ioTer_scanai(JNIEnv* env, jclass clazz, jint count) {
+    jintArray result = (*env)->NewIntArray(env, count);
+    if (result == NULL) {
+        return NULL;
+    }
+    for (jint i = 0; i < count; i++) {
+        jint value;
+        if (scanf("%d", &value) != 1) {
+            break;
+        }
+        (*env)->SetIntArrayRegion(env, result, i, 1, &value);
+    }
+    return result;
+}
+
+// Compile to .so against the headers of a JDK
+// gcc -shared -fPIC -I$JAVA_HOME/include -I$JAVA_HOME/include/linux -o libnative_io.so native_io.c
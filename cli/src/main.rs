@@ -1,142 +1,217 @@
 use std::fs;
+use std::path::Path;
 
+use options::{parse_options, Command, Options, USAGE};
+use parser::class_file::ClassFile;
 use parser::class_file_reader::ClassFileReader;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::task;
-use vm::class_loader::class_loader::ClassLoader;
-use vm::state::{FILE_NAME, MEMORY_SIZE, MEMORY_SNAP, VIS_BOOL};
+use vm::state::{
+    FILE_NAME, IC_STATS, JIT_TRACE, MEMORY_SIZE, MEMORY_SNAP, STACK_SIZE, SYSTEM_PROPERTIES,
+    VIS_BOOL,
+};
+use vm::class_loader::bundled::require_bundled;
+use vm::class_loader::java_home::JavaHome;
 use vm::vis;
 use vm::vm::VM;
 
+mod options;
+
 #[tokio::main]
 async fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
-
-    if args.len() < 3 {
-        panic!("Invalid args\n usage: <exe> [ parse | run | vis] <class_file>");
-    }
-
-    if let Some(mem_pos) = args.iter().position(|arg| arg == "--mem") {
-        if mem_pos + 1 < args.len() {
-            if let Ok(mem_value) = args[mem_pos + 1].parse::<usize>() {
-                let mut mem_size = MEMORY_SIZE.lock().unwrap();
-                *mem_size = mem_value;
-            } else {
-                panic!("Invalid value for mem. Please provide an integer.");
-            }
-        } else {
-            panic!("--mem flag requires a value.");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let options = match parse_options(&args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
         }
-    }
+        Err(message) => {
+            eprintln!("error: {}", message);
+            eprintln!("run with --help for usage");
+            std::process::exit(2);
+        }
+    };
 
-    if let Some(file_pos) = args.iter().position(|arg| arg == "--file") {
-        if file_pos + 1 < args.len() {
-            if args.contains(&"--vis".to_string()) {
-                let file_value = &args[file_pos + 1];
-                if file_value.ends_with(".json") {
-                    let mut file_name = FILE_NAME.lock().unwrap();
-                    *file_name = file_value.clone();
-                } else {
-                    panic!("--file value must end with .json.");
+    let status = match options.command {
+        Command::Parse => parse(&options.class),
+        Command::Disasm => disasm(&options.class),
+        Command::Run | Command::Vis => {
+            configure(&options);
+            match start(&options).await {
+                Ok((vm, main_class)) => execute(&options, &vm, &main_class).await,
+                Err(message) => {
+                    eprintln!("error: {}", message);
+                    1
                 }
-            } else {
-                panic!("--file flag requires --vis to be present.");
             }
-        } else {
-            panic!("--file flag requires a value.");
         }
+    };
+    std::process::exit(status);
+}
+
+// hands the options over to the VM, which reads them from vm::state
+fn configure(options: &Options) {
+    if let Some(heap_size) = options.heap_size {
+        *MEMORY_SIZE.lock().unwrap() = heap_size;
+    }
+    if let Some(stack_size) = options.stack_size {
+        *STACK_SIZE.lock().unwrap() = stack_size;
+    }
+    if let Some(file) = &options.vis_file {
+        *FILE_NAME.lock().unwrap() = file.clone();
     }
+    *VIS_BOOL.lock().unwrap() = options.command == Command::Vis;
+    *MEMORY_SNAP.lock().unwrap() = options.snap;
+    *IC_STATS.lock().unwrap() = options.ic_stats;
+    // compile and enter/leave events of the baseline JIT (cli built with --features jit)
+    *JIT_TRACE.lock().unwrap() = options.jit_trace;
+    // java.library.path names the directories System.loadLibrary searches, separated by ':'
+    SYSTEM_PROPERTIES
+        .lock()
+        .unwrap()
+        .extend(options.properties.iter().cloned());
+}
 
-    if args.iter().any(|arg| arg == "--snap") {
-        if args.contains(&"--vis".to_string()) {
-            let mut snap = MEMORY_SNAP.lock().unwrap();
-            *snap = true;
-        } else {
-            panic!("--file flag requires --vis to be present.");
+// a VM with the class path set up and the internal name of the main class
+async fn start(options: &Options) -> Result<(VM, String), String> {
+    let (main_class, default_class_path) = if options.class.ends_with(".class") {
+        // the class path root is as many directories up from the file as the class has
+        // packages
+        let class_file = read_class_file(&options.class)?;
+        let main_class = class_file.get_class_name().clone();
+        let mut root = Path::new(&options.class).parent();
+        for _ in main_class.matches('/') {
+            root = root.and_then(Path::parent);
         }
-    }
+        let root = root.map(|root| root.to_string_lossy().to_string());
+        (main_class, vec![root.unwrap_or_default(), String::new()])
+    } else {
+        (options.class.replace('.', "/"), vec![String::new()])
+    };
 
-    match args.get(1).unwrap().as_str() {
-        "--parse" => parse(&args[2].as_str()),
-        "--run" => run(&args[2].as_str()).await,
-        "--vis" => {
-            {
-                let mut vis_flag = VIS_BOOL.lock().unwrap();
-                *vis_flag = true;
-            }
-            vis(&args[2].as_str()).await
+    let class_path = options.class_path.as_ref().unwrap_or(&default_class_path);
+    // java.class.path as the class path ended up, -Djava.class.path still wins
+    let mut entries = Vec::new();
+    for entry in class_path {
+        let entry = if entry.is_empty() { "." } else { entry.as_str() };
+        if !entries.contains(&entry) {
+            entries.push(entry);
         }
-        cmd => panic!("command {} not implemented yet.", cmd),
     }
+    SYSTEM_PROPERTIES
+        .lock()
+        .unwrap()
+        .insert(0, ("java.class.path".to_string(), entries.join(":")));
+
+    let java_home = if options.bundled_runtime {
+        require_bundled().map_err(|e| e.to_string())?;
+        None
+    } else {
+        let java_home = JavaHome::find(options.java_home.as_deref().map(Path::new))
+            .map_err(|e| e.to_string())?;
+        Some(java_home)
+    };
+    let mem_size = *MEMORY_SIZE.lock().unwrap();
+    let properties = SYSTEM_PROPERTIES.lock().unwrap().clone();
+    let mut vm = VM::with_config(mem_size, java_home, properties)
+        .await
+        .map_err(|e| format!("the VM failed to start: {:?}", e))?;
+    for entry in class_path {
+        let added = if entry.ends_with(".jar") {
+            vm.class_loader.add_jar_entry(entry.clone())
+        } else {
+            vm.class_loader.add_directory_entry(entry.clone())
+        };
+        added.map_err(|e| format!("cannot use class path entry '{}': {}", entry, e))?;
+    }
+    Ok((vm, main_class))
 }
 
-async fn vis(class: &str) {
-    let mem_size = MEMORY_SIZE.lock().unwrap();
-    let mut vm = VM::new(*mem_size).await;
-    let main_class = add_prepare(&class, &mut vm);
-    let class_name = main_class.clone();
-    let _ = vm.class_loader.add_directory_entry("".to_string());
-    /*
-        let producer = tokio::spawn(async {
-            vm.invoke_main(&class_name).await;
-        });
-
-        let consumer = tokio::spawn(async {
-            vis::consumer_thread().await;
-        });
-
-        let _ = tokio::try_join!(producer, consumer);
-    */
-    let _ = vm.invoke_main(&class_name).await;
-    //vis::consumer_thread().await;
-    vis::file_writer().await;
+async fn execute(options: &Options, vm: &VM, main_class: &str) -> i32 {
+    let status = vm.run_main(main_class, &options.program_args).await;
+    if options.command == Command::Vis {
+        //vis::consumer_thread().await;
+        vis::file_writer().await;
+    }
+    print_inline_cache_stats(vm);
+    status
 }
 
-async fn run(class: &str) {
-    //   let mut vm = VM::new();
-    // TODO provide args as string array
-    // let start_args = vec![Value::Int(0)];
-    //  vm.start(main_class, start_args);
-    //let class = class_manager.get_or_resolve_class(main_class).unwrap();
-    //    println!("{class}");
-    let mem_size = MEMORY_SIZE.lock().unwrap();
-    let mut vm = VM::new(*mem_size).await;
-    let main_class = add_prepare(&class, &mut vm);
-    let _ = vm.class_loader.add_directory_entry("".to_string());
-    //let _ = vm.class_loader.add_directory_entry("../Temp/java/".to_string());
-    let damn = vm.invoke_main(&main_class).await;
-    //println!("{:?}",damn);
-
-    //dbg!(class);
+fn print_inline_cache_stats(vm: &VM) {
+    if !*IC_STATS.lock().unwrap() {
+        return;
+    }
+    println!("inline cache statistics:");
+    for site in vm.inline_cache_stats() {
+        println!(
+            "  {}.{} @{} {:?} [{}] hits={} misses={} megamorphic={} hit rate={:.1}%",
+            site.class_name,
+            site.method,
+            site.pc,
+            site.state,
+            site.receivers.join(", "),
+            site.hits,
+            site.misses,
+            site.megamorphic_lookups,
+            site.hit_rate() * 100.0
+        );
+    }
 }
 
-fn add_prepare(unprepared: &str, vm: &mut VM) -> String {
-    let trimmed_unprepared = if unprepared.ends_with(".class") {
-        &unprepared[..unprepared.len() - 6]
-    } else {
-        unprepared
-    };
-    if let Some(pos) = trimmed_unprepared.rfind("/") {
-        let main_class = &trimmed_unprepared[pos + 1..];
-        let entry = &trimmed_unprepared[..pos + 1];
-        let _ = vm.class_loader.add_directory_entry(entry.to_string());
-        main_class.to_string()
-    } else {
-        trimmed_unprepared.to_string()
-    }
+fn read_class_file(path: &str) -> Result<ClassFile, String> {
+    let content = fs::read(path).map_err(|e| format!("cannot read '{}': {}", path, e))?;
+    ClassFileReader::new(content)
+        .parse()
+        .map_err(|e| format!("'{}' is not a valid class file: {}", path, e))
 }
 
-fn parse(class: &str) {
+fn parse(class: &str) -> i32 {
+    let class_file = match read_class_file(class) {
+        Ok(class_file) => class_file,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return 1;
+        }
+    };
     println!("parsing class file {}", class);
-
-    //TODO: add size bound
-    //read whole file to memory as Vec<u8>
-    let content = fs::read(class).unwrap();
-
-    let reader = ClassFileReader::new(content);
-    let class_file = reader.parse();
-    dbg!(class_file.unwrap());
-
+    dbg!(class_file);
     println!("Parsing completed");
+    0
+}
+
+// the methods of a class file and their instructions, by bytecode address
+fn disasm(class: &str) -> i32 {
+    let class_file = match read_class_file(class) {
+        Ok(class_file) => class_file,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return 1;
+        }
+    };
+    let cp = &class_file.constant_pool;
+    println!("class {}", class_file.get_class_name());
+    if let Some(super_class) =
+        cp.get_underlying_string_from_constant_class_info_index(class_file.super_class)
+    {
+        println!("  extends {}", super_class);
+    }
+    for method in &class_file.methods {
+        let flags = method
+            .access_flags
+            .iter_names()
+            .map(|(name, _)| name.trim_start_matches("ACC_").to_lowercase())
+            .collect::<Vec<_>>();
+        println!();
+        println!(
+            "{}{}{}",
+            flags.iter().map(|flag| format!("{} ", flag)).collect::<String>(),
+            method.get_name(cp),
+            method.get_des(cp)
+        );
+        if let Some(code) = method.get_code_attribute() {
+            for instruction in &code.code {
+                println!("  {:>5}: {:?}", instruction.0, instruction.1);
+            }
+        }
+    }
+    0
 }
@@ -0,0 +1,320 @@
+// Command line parsing. Anything wrong with the arguments comes back as a message for the
+// user, main prints it with a hint to --help and exits with status 2.
+
+pub const USAGE: &str = "\
+usage: cli <command> [options] <class> [args...]
+
+commands:
+  run      runs the main method of a class
+  vis      runs it and records the execution for the visualizer
+  parse    prints the parsed structure of a class file
+  disasm   prints the methods of a class file with their bytecode
+
+<class> is a .class file or, for run and vis, a class name such as pkg.Main found on the
+class path. Everything after it is passed to main.
+
+options:
+  -cp, --class-path <path>  directories and .jar files separated by ':', by default the
+                            directory of the class file and the current directory
+  -D<name>=<value>          sets a system property
+  --java-home <dir>         the JDK or JRE to boot from, by default JAVA_HOME, the java
+                            on the PATH or the newest JDK installed
+  --bundled-runtime         boots from the small class library built into the VM
+                            instead of a JDK
+  -Xss<size>                the deepest a thread's stack gets, in frames (default 1024)
+  --mem <size>              the number of heap slots (default 4096)
+  --ic-stats                prints inline cache statistics after the run
+  --jit-trace               traces the baseline JIT, in builds with the jit feature
+  --file <name>.json        where vis writes its recording (default dump.json)
+  --snap                    includes heap snapshots in the vis recording
+  -h, --help                prints this message
+
+sizes take a k, m or g suffix for multiples of 1024.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Vis,
+    Parse,
+    Disasm,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    // None when -cp is not given
+    pub class_path: Option<Vec<String>>,
+    pub java_home: Option<String>,
+    pub bundled_runtime: bool,
+    pub properties: Vec<(String, String)>,
+    pub stack_size: Option<usize>,
+    pub heap_size: Option<usize>,
+    pub ic_stats: bool,
+    pub jit_trace: bool,
+    pub vis_file: Option<String>,
+    pub snap: bool,
+    pub class: String,
+    pub program_args: Vec<String>,
+}
+
+// the options in args, which start with the command, or None when help was asked for
+pub fn parse_options(args: &[String]) -> Result<Option<Options>, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        None => return Err("missing command".to_string()),
+        Some("-h" | "--help" | "help") => return Ok(None),
+        // the flags the cli took before it had subcommands
+        Some("run" | "--run") => Command::Run,
+        Some("vis" | "--vis") => Command::Vis,
+        Some("parse" | "--parse") => Command::Parse,
+        Some("disasm") => Command::Disasm,
+        Some(command) => return Err(format!("unknown command '{}'", command)),
+    };
+    let mut options = Options {
+        command,
+        class_path: None,
+        java_home: None,
+        bundled_runtime: false,
+        properties: Vec::new(),
+        stack_size: None,
+        heap_size: None,
+        ic_stats: false,
+        jit_trace: false,
+        vis_file: None,
+        snap: false,
+        class: String::new(),
+        program_args: Vec::new(),
+    };
+
+    // options sit between the command and the class, everything after the class is passed to
+    // main
+    loop {
+        let Some(arg) = args.next() else {
+            return Err("missing class".to_string());
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-cp" | "-classpath" | "--class-path" => {
+                let path = value_of(arg, args.next())?;
+                options.class_path = Some(
+                    path.split(':')
+                        .filter(|entry| !entry.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                );
+            }
+            "--java-home" => options.java_home = Some(value_of(arg, args.next())?.to_string()),
+            "--bundled-runtime" => options.bundled_runtime = true,
+            "--mem" => options.heap_size = Some(size(arg, value_of(arg, args.next())?)?),
+            "--ic-stats" => options.ic_stats = true,
+            "--jit-trace" => options.jit_trace = true,
+            "--file" => {
+                let file = value_of(arg, args.next())?;
+                if !file.ends_with(".json") {
+                    return Err(format!("--file needs a .json file, got '{}'", file));
+                }
+                options.vis_file = Some(file.to_string());
+            }
+            "--snap" => options.snap = true,
+            _ if arg.starts_with("-D") => {
+                let property = &arg[2..];
+                let (name, value) = property.split_once('=').unwrap_or((property, ""));
+                if name.is_empty() {
+                    return Err(format!("'{}' does not name a property", arg));
+                }
+                options.properties.push((name.to_string(), value.to_string()));
+            }
+            _ if arg.starts_with("-Xss") => {
+                options.stack_size = Some(size("-Xss", &arg[4..])?);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            class => {
+                options.class = class.to_string();
+                break;
+            }
+        }
+    }
+    options.program_args = args.cloned().collect();
+
+    if options.command != Command::Vis {
+        if options.vis_file.is_some() {
+            return Err("--file only applies to vis".to_string());
+        }
+        if options.snap {
+            return Err("--snap only applies to vis".to_string());
+        }
+    }
+    if options.bundled_runtime && options.java_home.is_some() {
+        return Err("--java-home and --bundled-runtime exclude each other".to_string());
+    }
+    if matches!(options.command, Command::Parse | Command::Disasm)
+        && !options.class.ends_with(".class")
+    {
+        return Err(format!("'{}' is not a .class file", options.class));
+    }
+    Ok(Some(options))
+}
+
+fn value_of<'a>(option: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(format!("{} needs a value", option)),
+    }
+}
+
+// a positive count such as 4096, 4k or 1m
+fn size(option: &str, value: &str) -> Result<usize, String> {
+    let (digits, unit) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&value[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|count| count.checked_mul(unit))
+        .filter(|size| *size > 0)
+        .ok_or_else(|| format!("{} needs a positive size, got '{}'", option, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_options(&args)
+    }
+
+    fn options(args: &[&str]) -> Options {
+        parse(args).unwrap().unwrap()
+    }
+
+    #[test]
+    fn splits_options_class_and_program_args() {
+        let options = options(&["run", "-cp", "a::b.jar", "--mem", "2k", "Main", "-cp", "x"]);
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(
+            options.class_path,
+            Some(vec!["a".to_string(), "b.jar".to_string()])
+        );
+        assert_eq!(options.heap_size, Some(2048));
+        assert_eq!(options.class, "Main");
+        assert_eq!(options.program_args, ["-cp", "x"]);
+    }
+
+    #[test]
+    fn help_and_missing_arguments() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["run", "-h"]).unwrap().is_none());
+        assert_eq!(parse(&[]).unwrap_err(), "missing command");
+        assert_eq!(
+            parse(&["jump", "Main"]).unwrap_err(),
+            "unknown command 'jump'"
+        );
+        assert_eq!(parse(&["run"]).unwrap_err(), "missing class");
+        assert_eq!(parse(&["run", "--snap"]).unwrap_err(), "missing class");
+        assert_eq!(parse(&["run", "-cp"]).unwrap_err(), "-cp needs a value");
+    }
+
+    #[test]
+    fn unknown_option() {
+        assert_eq!(
+            parse(&["run", "--fast", "Main"]).unwrap_err(),
+            "unknown option '--fast'"
+        );
+    }
+
+    #[test]
+    fn properties() {
+        let options = options(&["run", "-Da=1", "-Db", "-Dc=x=y", "Main"]);
+        assert_eq!(
+            options.properties,
+            [
+                ("a".to_string(), "1".to_string()),
+                // like java, -D without = sets the property to the empty string
+                ("b".to_string(), String::new()),
+                ("c".to_string(), "x=y".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse(&["run", "-D", "Main"]).unwrap_err(),
+            "'-D' does not name a property"
+        );
+        assert_eq!(
+            parse(&["run", "-D=1", "Main"]).unwrap_err(),
+            "'-D=1' does not name a property"
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size("--mem", "4096"), Ok(4096));
+        assert_eq!(size("--mem", "4k"), Ok(4 << 10));
+        assert_eq!(size("--mem", "3M"), Ok(3 << 20));
+        assert_eq!(size("--mem", "1g"), Ok(1 << 30));
+        assert_eq!(options(&["run", "-Xss2k", "Main"]).stack_size, Some(2048));
+        for bad in [
+            "",
+            "0",
+            "0k",
+            "k",
+            "-1",
+            "1x",
+            "1.5m",
+            "99999999999999999999",
+        ] {
+            assert_eq!(
+                size("--mem", bad),
+                Err(format!("--mem needs a positive size, got '{}'", bad))
+            );
+        }
+        // fits in a usize before the suffix is applied but not after
+        let overflow = format!("{}g", usize::MAX >> 20);
+        assert!(size("--mem", &overflow).is_err());
+        assert_eq!(
+            parse(&["run", "-Xss", "Main"]).unwrap_err(),
+            "-Xss needs a positive size, got ''"
+        );
+    }
+
+    #[test]
+    fn vis_only_options() {
+        let options = options(&["vis", "--file", "out.json", "--snap", "Main"]);
+        assert_eq!(options.vis_file.as_deref(), Some("out.json"));
+        assert!(options.snap);
+        assert_eq!(
+            parse(&["run", "--file", "out.json", "Main"]).unwrap_err(),
+            "--file only applies to vis"
+        );
+        assert_eq!(
+            parse(&["run", "--snap", "Main"]).unwrap_err(),
+            "--snap only applies to vis"
+        );
+        assert_eq!(
+            parse(&["vis", "--file", "out.txt", "Main"]).unwrap_err(),
+            "--file needs a .json file, got 'out.txt'"
+        );
+    }
+
+    #[test]
+    fn java_home_excludes_bundled_runtime() {
+        let options = options(&["run", "--java-home", "/jdk", "Main"]);
+        assert_eq!(options.java_home.as_deref(), Some("/jdk"));
+        assert!(!options.bundled_runtime);
+        assert_eq!(
+            parse(&["run", "--java-home", "/jdk", "--bundled-runtime", "Main"]).unwrap_err(),
+            "--java-home and --bundled-runtime exclude each other"
+        );
+    }
+
+    #[test]
+    fn parse_and_disasm_need_a_class_file() {
+        assert_eq!(options(&["disasm", "Main.class"]).command, Command::Disasm);
+        assert_eq!(
+            parse(&["parse", "Main"]).unwrap_err(),
+            "'Main' is not a .class file"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use super::types::U2;
 use bitflags::bitflags;
 
 bitflags! {
-    #[derive(Default, Debug)]
+    #[derive(Default, Debug, Clone, Copy)]
     pub struct MethodFlags: U2{
         const ACC_PUBLIC = 0x0001;
         const ACC_PRIVATE = 0x0002;
@@ -105,6 +105,21 @@ impl Code {
         let Instruction(address, _) = self.code.get(index).unwrap();
         *address
     }
+
+    // the source line of the instruction at index, from the LineNumberTable if javac emitted one
+    pub fn get_line_at_index(&self, index: usize) -> Option<U2> {
+        let address = self.get_address_at_index(index);
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                AttributeInfo::LineNumberTable(LineNumberTable(entries)) => Some(entries),
+                _ => None,
+            })
+            .flatten()
+            .filter(|entry| entry.start_pc as U4 <= address)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -954,10 +954,7 @@ impl ClassFileReader {
                 self.read_u1_with_count(&mut byte_read)?,
                 self.read_u1_with_count(&mut byte_read)?,
             ),
-            0x5f => Operation::Swap(
-                self.read_u1_with_count(&mut byte_read)?,
-                self.read_u1_with_count(&mut byte_read)?,
-            ),
+            0x5f => Operation::Swap,
             /*
             0xaa => {
                 byte_read += LOOKUP_SWITCH_OPERAND_COUNT as U4;
@@ -997,11 +994,17 @@ impl ClassFileReader {
                 Operation::Tableswitch(default_offset, low, high, jump_offsets)
             }
 
-            0xc4 => Operation::Wide(
-                self.read_u1_with_count(&mut byte_read)?,
-                self.read_u1_with_count(&mut byte_read)?,
-                self.read_u1_with_count(&mut byte_read)?,
-            ),
+            0xc4 => {
+                let opcode = self.read_u1_with_count(&mut byte_read)?;
+                let index = self.read_u2_with_count(&mut byte_read)?;
+                // only the iinc form carries a constant
+                let constant = if opcode == 0x84 {
+                    self.read_u2_with_count(&mut byte_read)? as i16
+                } else {
+                    0
+                };
+                Operation::Wide(opcode, index, constant)
+            }
             op_code => {
                 return Err(std::io::Error::new(
                     ErrorKind::Other,
@@ -210,10 +210,11 @@ pub enum Operation {
     Saload,
     Sastore,
     Sipush(u8, u8),
-    Swap(u8, u8),
+    Swap,
     //Tableswitch(Vec<u8>),
     Tableswitch(i32, i32, i32, Vec<i32>),
-    Wide(u8, u8, u8),
+    // opcode, local variable index and, for iinc, the constant
+    Wide(u8, u16, i16),
 }
 
 /*
@@ -0,0 +1,102 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    // the variadic JNI functions, see src/jni/jni_varargs.c
+    cc::Build::new()
+        .file("src/jni/jni_varargs.c")
+        .compile("jni_varargs");
+    println!("cargo:rerun-if-changed=src/jni/jni_varargs.c");
+
+    bundle_class_library();
+}
+
+// Compiles the class library in classlib/ and writes bundled_classes.rs to OUT_DIR, a table of
+// the class files by internal name that src/class_loader/bundled.rs includes. Without a javac
+// that compiles it the table is empty and the VM can only boot from a JDK.
+fn bundle_class_library() {
+    println!("cargo:rerun-if-changed=classlib");
+    println!("cargo:rerun-if-env-changed=JAVAC");
+    println!("cargo:rerun-if-env-changed=JAVA_HOME");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let classes_dir = out_dir.join("classlib");
+    let _ = fs::remove_dir_all(&classes_dir);
+
+    let mut sources = Vec::new();
+    find_files(Path::new("classlib"), "java", &mut sources);
+    sources.sort();
+    // the library is java.base itself, so it is compiled as that module with no system
+    // modules to shadow it
+    let compiled = Command::new(javac())
+        .args(["--system", "none", "-source", "17", "-target", "17", "-nowarn"])
+        .args(["-encoding", "UTF-8", "-XDstringConcat=inline", "-d"])
+        .arg(&classes_dir)
+        .args(&sources)
+        .status();
+    let mut classes = Vec::new();
+    match compiled {
+        Ok(status) if status.success() => find_files(&classes_dir, "class", &mut classes),
+        // a javac older than 17 cannot compile it, the VM goes without it as it does without
+        // any javac
+        Ok(status) => println!(
+            "cargo:warning=javac could not compile classlib/ ({}), the VM is built without its \
+             bundled class library",
+            status
+        ),
+        Err(e) => println!(
+            "cargo:warning=no javac ({}), the VM is built without its bundled class library",
+            e
+        ),
+    }
+    classes.sort();
+
+    let mut table = String::from("pub static BUNDLED_CLASSES: &[(&str, &[u8])] = &[\n");
+    for class in &classes {
+        let name = class
+            .strip_prefix(&classes_dir)
+            .unwrap()
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/");
+        if name == "module-info" {
+            continue;
+        }
+        table.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            name,
+            class.display().to_string()
+        ));
+    }
+    table.push_str("];\n");
+    fs::write(out_dir.join("bundled_classes.rs"), table).unwrap();
+}
+
+// JAVAC, the javac of JAVA_HOME or the one on the PATH
+fn javac() -> PathBuf {
+    if let Some(javac) = env::var_os("JAVAC") {
+        return PathBuf::from(javac);
+    }
+    if let Some(home) = env::var_os("JAVA_HOME") {
+        let javac = Path::new(&home).join("bin").join("javac");
+        if javac.is_file() {
+            return javac;
+        }
+    }
+    PathBuf::from("javac")
+}
+
+fn find_files(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_files(&path, extension, files);
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+package java.io;
+
+// The VM reads fd to find the stream to use, only the standard streams are open.
+public final class FileDescriptor {
+    public static final FileDescriptor in = new FileDescriptor(0);
+    public static final FileDescriptor out = new FileDescriptor(1);
+    public static final FileDescriptor err = new FileDescriptor(2);
+
+    private final int fd;
+
+    private FileDescriptor(int fd) {
+        this.fd = fd;
+    }
+
+    public boolean valid() {
+        return true;
+    }
+}
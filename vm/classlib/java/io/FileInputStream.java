@@ -0,0 +1,28 @@
+package java.io;
+
+// The natives are those of java.base, the VM reads from the descriptor in fd.
+public class FileInputStream extends InputStream {
+    private final FileDescriptor fd;
+
+    public FileInputStream(FileDescriptor fdObj) {
+        fd = fdObj;
+    }
+
+    public int read() throws IOException {
+        return read0();
+    }
+
+    public int read(byte[] b, int off, int len) throws IOException {
+        return readBytes(b, off, len);
+    }
+
+    public int available() throws IOException {
+        return available0();
+    }
+
+    private native int read0() throws IOException;
+
+    private native int readBytes(byte[] b, int off, int len) throws IOException;
+
+    private native int available0() throws IOException;
+}
@@ -0,0 +1,23 @@
+package java.io;
+
+// The natives are those of java.base, the VM writes to the descriptor in fd.
+public class FileOutputStream extends OutputStream {
+    private final FileDescriptor fd;
+
+    public FileOutputStream(FileDescriptor fdObj) {
+        fd = fdObj;
+    }
+
+    public void write(int b) throws IOException {
+        write(b, false);
+    }
+
+    public void write(byte[] b, int off, int len) throws IOException {
+        writeBytes(b, off, len, false);
+    }
+
+    private native void write(int b, boolean append) throws IOException;
+
+    private native void writeBytes(byte[] b, int off, int len, boolean append)
+            throws IOException;
+}
@@ -0,0 +1,142 @@
+package java.io;
+
+// Text goes out as UTF-8 and unbuffered. Like in java.base an IOException is not thrown but
+// remembered for checkError.
+public class PrintStream extends OutputStream {
+    private final OutputStream out;
+    private boolean trouble;
+
+    public PrintStream(OutputStream out) {
+        if (out == null) {
+            throw new NullPointerException("Null output stream");
+        }
+        this.out = out;
+    }
+
+    public void write(int b) {
+        try {
+            out.write(b);
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void write(byte[] buf, int off, int len) {
+        try {
+            out.write(buf, off, len);
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void flush() {
+        try {
+            out.flush();
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public void close() {
+        try {
+            out.close();
+        } catch (IOException e) {
+            trouble = true;
+        }
+    }
+
+    public boolean checkError() {
+        return trouble;
+    }
+
+    private void writeString(String s) {
+        byte[] bytes = s.getBytes();
+        write(bytes, 0, bytes.length);
+    }
+
+    public void print(boolean b) {
+        writeString(String.valueOf(b));
+    }
+
+    public void print(char c) {
+        writeString(String.valueOf(c));
+    }
+
+    public void print(int i) {
+        writeString(String.valueOf(i));
+    }
+
+    public void print(long l) {
+        writeString(String.valueOf(l));
+    }
+
+    public void print(float f) {
+        writeString(String.valueOf(f));
+    }
+
+    public void print(double d) {
+        writeString(String.valueOf(d));
+    }
+
+    public void print(char[] s) {
+        writeString(new String(s));
+    }
+
+    public void print(String s) {
+        writeString(String.valueOf(s));
+    }
+
+    public void print(Object obj) {
+        writeString(String.valueOf(obj));
+    }
+
+    public void println() {
+        writeString("\n");
+    }
+
+    public void println(boolean x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(char x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(int x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(long x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(float x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(double x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(char[] x) {
+        writeString(new String(x) + "\n");
+    }
+
+    public void println(String x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public void println(Object x) {
+        writeString(String.valueOf(x) + "\n");
+    }
+
+    public PrintStream append(CharSequence csq) {
+        print(String.valueOf(csq));
+        return this;
+    }
+
+    public PrintStream append(char c) {
+        print(c);
+        return this;
+    }
+}
@@ -0,0 +1,76 @@
+package java.lang;
+
+public final class Byte extends Number implements Comparable<Byte> {
+    public static final byte MIN_VALUE = -128;
+    public static final byte MAX_VALUE = 127;
+
+    private final byte value;
+
+    public Byte(byte value) {
+        this.value = value;
+    }
+
+    public static Byte valueOf(byte s) {
+        return new Byte(s);
+    }
+
+    public static Byte valueOf(String s) {
+        return new Byte(parseByte(s));
+    }
+
+    public static byte parseByte(String s) {
+        return parseByte(s, 10);
+    }
+
+    public static byte parseByte(String s, int radix) {
+        return (byte) Long.parse(s, radix, MIN_VALUE, MAX_VALUE);
+    }
+
+    public static String toString(byte s) {
+        return Integer.toString(s);
+    }
+
+    public static int hashCode(byte value) {
+        return value;
+    }
+
+    public static int compare(byte x, byte y) {
+        return x - y;
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public byte byteValue() {
+        return value;
+    }
+
+    public int compareTo(Byte anotherByte) {
+        return compare(value, anotherByte.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Byte && value == ((Byte) obj).value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public String toString() {
+        return Integer.toString(value);
+    }
+}
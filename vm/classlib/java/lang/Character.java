@@ -0,0 +1,164 @@
+package java.lang;
+
+// Without the Unicode tables of java.base the classification covers Latin-1, any char beyond
+// it counts as a letter that has no case.
+public final class Character implements java.io.Serializable, Comparable<Character> {
+    public static final char MIN_VALUE = '\u0000';
+    public static final char MAX_VALUE = '\uffff';
+    public static final int MIN_RADIX = 2;
+    public static final int MAX_RADIX = 36;
+    public static final char MIN_HIGH_SURROGATE = '\ud800';
+    public static final char MAX_HIGH_SURROGATE = '\udbff';
+    public static final char MIN_LOW_SURROGATE = '\udc00';
+    public static final char MAX_LOW_SURROGATE = '\udfff';
+
+    private static final Character[] CACHE = new Character[128];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Character((char) i);
+        }
+    }
+
+    private final char value;
+
+    public Character(char value) {
+        this.value = value;
+    }
+
+    public static Character valueOf(char c) {
+        if (c < CACHE.length) {
+            return CACHE[c];
+        }
+        return new Character(c);
+    }
+
+    public static boolean isDigit(char ch) {
+        return ch >= '0' && ch <= '9';
+    }
+
+    public static boolean isLetter(char ch) {
+        if (ch > 0xff) {
+            return !isSurrogate(ch);
+        }
+        return isUpperCase(ch) || isLowerCase(ch);
+    }
+
+    public static boolean isLetterOrDigit(char ch) {
+        return isLetter(ch) || isDigit(ch);
+    }
+
+    public static boolean isAlphabetic(int codePoint) {
+        return codePoint > 0xffff || isLetter((char) codePoint);
+    }
+
+    public static boolean isUpperCase(char ch) {
+        return (ch >= 'A' && ch <= 'Z') || (ch >= '\u00c0' && ch <= '\u00de' && ch != '\u00d7');
+    }
+
+    public static boolean isLowerCase(char ch) {
+        return (ch >= 'a' && ch <= 'z') || (ch >= '\u00df' && ch <= '\u00ff' && ch != '\u00f7')
+                || ch == '\u00aa' || ch == '\u00b5' || ch == '\u00ba';
+    }
+
+    public static boolean isWhitespace(char ch) {
+        return ch == ' ' || (ch >= '\t' && ch <= '\r') || (ch >= '\u001c' && ch <= '\u001f');
+    }
+
+    public static boolean isSpaceChar(char ch) {
+        return ch == ' ' || ch == '\u00a0';
+    }
+
+    public static char toUpperCase(char ch) {
+        if ((ch >= 'a' && ch <= 'z') || (ch >= '\u00e0' && ch <= '\u00fe' && ch != '\u00f7')) {
+            return (char) (ch - 32);
+        }
+        if (ch == '\u00ff') {
+            return '\u0178';
+        }
+        if (ch == '\u00b5') {
+            return '\u039c';
+        }
+        return ch;
+    }
+
+    public static char toLowerCase(char ch) {
+        if (isUpperCase(ch)) {
+            return (char) (ch + 32);
+        }
+        return ch;
+    }
+
+    public static boolean isHighSurrogate(char ch) {
+        return ch >= MIN_HIGH_SURROGATE && ch <= MAX_HIGH_SURROGATE;
+    }
+
+    public static boolean isLowSurrogate(char ch) {
+        return ch >= MIN_LOW_SURROGATE && ch <= MAX_LOW_SURROGATE;
+    }
+
+    public static boolean isSurrogate(char ch) {
+        return ch >= MIN_HIGH_SURROGATE && ch <= MAX_LOW_SURROGATE;
+    }
+
+    // the value of ch as a digit in radix, -1 if it is none
+    public static int digit(char ch, int radix) {
+        if (radix < MIN_RADIX || radix > MAX_RADIX) {
+            return -1;
+        }
+        int value;
+        if (ch >= '0' && ch <= '9') {
+            value = ch - '0';
+        } else if (ch >= 'a' && ch <= 'z') {
+            value = ch - 'a' + 10;
+        } else if (ch >= 'A' && ch <= 'Z') {
+            value = ch - 'A' + 10;
+        } else {
+            return -1;
+        }
+        return value < radix ? value : -1;
+    }
+
+    public static int getNumericValue(char ch) {
+        return digit(ch, MAX_RADIX);
+    }
+
+    public static char forDigit(int digit, int radix) {
+        if (digit < 0 || digit >= radix || radix < MIN_RADIX || radix > MAX_RADIX) {
+            return '\0';
+        }
+        return (char) (digit < 10 ? '0' + digit : 'a' - 10 + digit);
+    }
+
+    public static String toString(char c) {
+        return String.valueOf(c);
+    }
+
+    public static int hashCode(char value) {
+        return value;
+    }
+
+    public static int compare(char x, char y) {
+        return x - y;
+    }
+
+    public char charValue() {
+        return value;
+    }
+
+    public int compareTo(Character anotherCharacter) {
+        return compare(value, anotherCharacter.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Character && value == ((Character) obj).value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public String toString() {
+        return String.valueOf(value);
+    }
+}
@@ -0,0 +1,58 @@
+package java.lang;
+
+// Mirrors are made by the VM, which sets name and componentType.
+public final class Class<T> {
+    private String name;
+    private Class<?> componentType;
+
+    private Class() {}
+
+    public String getName() {
+        return name;
+    }
+
+    public String getSimpleName() {
+        if (isArray()) {
+            return componentType.getSimpleName() + "[]";
+        }
+        String simpleName = name.substring(name.lastIndexOf('.') + 1);
+        return simpleName.substring(simpleName.lastIndexOf('$') + 1);
+    }
+
+    public String toString() {
+        if (isPrimitive()) {
+            return name;
+        }
+        return (isInterface() ? "interface " : "class ") + name;
+    }
+
+    public Class<?> getComponentType() {
+        return componentType;
+    }
+
+    public T cast(Object obj) {
+        if (obj != null && !isInstance(obj)) {
+            throw new ClassCastException("Cannot cast " + obj.getClass().getName() + " to " + name);
+        }
+        return (T) obj;
+    }
+
+    // there is no -ea, assert statements never run
+    public boolean desiredAssertionStatus() {
+        return false;
+    }
+
+    public native boolean isInstance(Object obj);
+
+    public native boolean isAssignableFrom(Class<?> cls);
+
+    public native boolean isInterface();
+
+    public native boolean isArray();
+
+    public native boolean isPrimitive();
+
+    public native Class<? super T> getSuperclass();
+
+    public native int getModifiers();
+}
@@ -0,0 +1,42 @@
+package java.lang;
+
+public abstract class Enum<E extends Enum<E>> implements Comparable<E>, java.io.Serializable {
+    private final String name;
+    private final int ordinal;
+
+    protected Enum(String name, int ordinal) {
+        this.name = name;
+        this.ordinal = ordinal;
+    }
+
+    public final String name() {
+        return name;
+    }
+
+    public final int ordinal() {
+        return ordinal;
+    }
+
+    public String toString() {
+        return name;
+    }
+
+    public final boolean equals(Object other) {
+        return this == other;
+    }
+
+    public final int hashCode() {
+        return super.hashCode();
+    }
+
+    public final int compareTo(E o) {
+        Enum<?> other = o;
+        return ordinal - other.ordinal;
+    }
+
+    // finding the constant needs reflection, which the bundled library has none of
+    public static <T extends Enum<T>> T valueOf(Class<T> enumClass, String name) {
+        throw new UnsupportedOperationException(
+                "Enum.valueOf is not supported by the bundled class library");
+    }
+}
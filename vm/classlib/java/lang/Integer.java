@@ -0,0 +1,180 @@
+package java.lang;
+
+public final class Integer extends Number implements Comparable<Integer> {
+    public static final int MIN_VALUE = 0x80000000;
+    public static final int MAX_VALUE = 0x7fffffff;
+    public static final int SIZE = 32;
+    public static final int BYTES = 4;
+
+    // valueOf hands out the same objects for -128 to 127, as java.base does
+    private static final Integer[] CACHE = new Integer[256];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Integer(i - 128);
+        }
+    }
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        if (i >= -128 && i <= 127) {
+            return CACHE[i + 128];
+        }
+        return new Integer(i);
+    }
+
+    public static Integer valueOf(String s) {
+        return valueOf(parseInt(s, 10));
+    }
+
+    public static Integer valueOf(String s, int radix) {
+        return valueOf(parseInt(s, radix));
+    }
+
+    public static int parseInt(String s) {
+        return parseInt(s, 10);
+    }
+
+    public static int parseInt(String s, int radix) {
+        return (int) Long.parse(s, radix, MIN_VALUE, MAX_VALUE);
+    }
+
+    public static String toString(int i) {
+        return Long.toString(i, 10);
+    }
+
+    public static String toString(int i, int radix) {
+        return Long.toString(i, radix);
+    }
+
+    public static String toHexString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 4);
+    }
+
+    public static String toOctalString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 3);
+    }
+
+    public static String toBinaryString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 1);
+    }
+
+    public static int hashCode(int value) {
+        return value;
+    }
+
+    public static int compare(int x, int y) {
+        return (x < y) ? -1 : ((x == y) ? 0 : 1);
+    }
+
+    public static int signum(int i) {
+        return (i >> 31) | (-i >>> 31);
+    }
+
+    public static int sum(int a, int b) {
+        return a + b;
+    }
+
+    public static int max(int a, int b) {
+        return Math.max(a, b);
+    }
+
+    public static int min(int a, int b) {
+        return Math.min(a, b);
+    }
+
+    public static int bitCount(int i) {
+        int count = 0;
+        while (i != 0) {
+            i &= i - 1;
+            count++;
+        }
+        return count;
+    }
+
+    public static int numberOfLeadingZeros(int i) {
+        if (i == 0) {
+            return 32;
+        }
+        int n = 0;
+        while (i > 0) {
+            i <<= 1;
+            n++;
+        }
+        return n;
+    }
+
+    public static int numberOfTrailingZeros(int i) {
+        if (i == 0) {
+            return 32;
+        }
+        int n = 0;
+        while ((i & 1) == 0) {
+            i >>>= 1;
+            n++;
+        }
+        return n;
+    }
+
+    public static int highestOneBit(int i) {
+        return i & (MIN_VALUE >>> numberOfLeadingZeros(i));
+    }
+
+    public static int lowestOneBit(int i) {
+        return i & -i;
+    }
+
+    public static int reverse(int i) {
+        int reversed = 0;
+        for (int bit = 0; bit < 32; bit++) {
+            reversed = (reversed << 1) | (i & 1);
+            i >>>= 1;
+        }
+        return reversed;
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public byte byteValue() {
+        return (byte) value;
+    }
+
+    public short shortValue() {
+        return (short) value;
+    }
+
+    public int compareTo(Integer anotherInteger) {
+        return compare(value, anotherInteger.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Integer && value == ((Integer) obj).value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
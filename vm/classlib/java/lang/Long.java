@@ -0,0 +1,204 @@
+package java.lang;
+
+public final class Long extends Number implements Comparable<Long> {
+    public static final long MIN_VALUE = 0x8000000000000000L;
+    public static final long MAX_VALUE = 0x7fffffffffffffffL;
+    public static final int SIZE = 64;
+    public static final int BYTES = 8;
+
+    private static final Long[] CACHE = new Long[256];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Long(i - 128);
+        }
+    }
+
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long l) {
+        if (l >= -128 && l <= 127) {
+            return CACHE[(int) l + 128];
+        }
+        return new Long(l);
+    }
+
+    public static Long valueOf(String s) {
+        return valueOf(parseLong(s, 10));
+    }
+
+    public static long parseLong(String s) {
+        return parseLong(s, 10);
+    }
+
+    public static long parseLong(String s, int radix) {
+        return parse(s, radix, MIN_VALUE, MAX_VALUE);
+    }
+
+    // s as a number between min and max, the parsing behind the parse methods of all the
+    // integer types
+    static long parse(String s, int radix, long min, long max) {
+        if (s == null) {
+            throw new NumberFormatException("Cannot parse null string: null");
+        }
+        if (radix < Character.MIN_RADIX || radix > Character.MAX_RADIX) {
+            throw new NumberFormatException("radix " + radix + " out of range");
+        }
+        int length = s.length();
+        int i = 0;
+        boolean negative = false;
+        if (length > 0 && (s.charAt(0) == '-' || s.charAt(0) == '+')) {
+            negative = s.charAt(0) == '-';
+            i++;
+        }
+        if (i == length) {
+            throw NumberFormatException.forInputString(s, radix);
+        }
+        // accumulated negatively, MIN_VALUE has no positive counterpart
+        long limit = negative ? min : -max;
+        long multmin = limit / radix;
+        long result = 0;
+        for (; i < length; i++) {
+            int digit = Character.digit(s.charAt(i), radix);
+            if (digit < 0 || result < multmin) {
+                throw NumberFormatException.forInputString(s, radix);
+            }
+            result *= radix;
+            if (result < limit + digit) {
+                throw NumberFormatException.forInputString(s, radix);
+            }
+            result -= digit;
+        }
+        return negative ? result : -result;
+    }
+
+    public static String toString(long i) {
+        return toString(i, 10);
+    }
+
+    public static String toString(long i, int radix) {
+        if (radix < Character.MIN_RADIX || radix > Character.MAX_RADIX) {
+            radix = 10;
+        }
+        char[] buffer = new char[65];
+        int position = buffer.length;
+        boolean negative = i < 0;
+        // digits of the negative value, MIN_VALUE has no positive counterpart
+        if (!negative) {
+            i = -i;
+        }
+        do {
+            buffer[--position] = Character.forDigit((int) -(i % radix), radix);
+            i /= radix;
+        } while (i != 0);
+        if (negative) {
+            buffer[--position] = '-';
+        }
+        return new String(buffer, position, buffer.length - position);
+    }
+
+    // the digits of i read as unsigned, in base 2 to the shift
+    static String toUnsignedString(long i, int shift) {
+        char[] buffer = new char[64];
+        int position = buffer.length;
+        int mask = (1 << shift) - 1;
+        do {
+            buffer[--position] = Character.forDigit((int) (i & mask), 1 << shift);
+            i >>>= shift;
+        } while (i != 0);
+        return new String(buffer, position, buffer.length - position);
+    }
+
+    public static String toHexString(long i) {
+        return toUnsignedString(i, 4);
+    }
+
+    public static String toOctalString(long i) {
+        return toUnsignedString(i, 3);
+    }
+
+    public static String toBinaryString(long i) {
+        return toUnsignedString(i, 1);
+    }
+
+    public static int hashCode(long value) {
+        return (int) (value ^ (value >>> 32));
+    }
+
+    public static int compare(long x, long y) {
+        return (x < y) ? -1 : ((x == y) ? 0 : 1);
+    }
+
+    public static int signum(long i) {
+        return (int) ((i >> 63) | (-i >>> 63));
+    }
+
+    public static long sum(long a, long b) {
+        return a + b;
+    }
+
+    public static long max(long a, long b) {
+        return Math.max(a, b);
+    }
+
+    public static long min(long a, long b) {
+        return Math.min(a, b);
+    }
+
+    public static int bitCount(long i) {
+        int count = 0;
+        while (i != 0) {
+            i &= i - 1;
+            count++;
+        }
+        return count;
+    }
+
+    public static int numberOfLeadingZeros(long i) {
+        int high = (int) (i >>> 32);
+        return high == 0 ? 32 + Integer.numberOfLeadingZeros((int) i)
+                : Integer.numberOfLeadingZeros(high);
+    }
+
+    public static int numberOfTrailingZeros(long i) {
+        int low = (int) i;
+        return low == 0 ? 32 + Integer.numberOfTrailingZeros((int) (i >>> 32))
+                : Integer.numberOfTrailingZeros(low);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int compareTo(Long anotherLong) {
+        return compare(value, anotherLong.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Long && value == ((Long) obj).value;
+    }
+
+    public int hashCode() {
+        return hashCode(value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
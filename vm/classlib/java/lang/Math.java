@@ -0,0 +1,303 @@
+package java.lang;
+
+public final class Math {
+    public static final double E = 2.718281828459045;
+    public static final double PI = 3.141592653589793;
+
+    // the generator of java.util.Random, seeded from the clock on first use
+    private static long seed;
+
+    private Math() {}
+
+    public static int abs(int a) {
+        return (a < 0) ? -a : a;
+    }
+
+    public static long abs(long a) {
+        return (a < 0) ? -a : a;
+    }
+
+    public static float abs(float a) {
+        return (a <= 0.0f) ? 0.0f - a : a;
+    }
+
+    public static double abs(double a) {
+        return (a <= 0.0d) ? 0.0d - a : a;
+    }
+
+    public static int max(int a, int b) {
+        return (a >= b) ? a : b;
+    }
+
+    public static long max(long a, long b) {
+        return (a >= b) ? a : b;
+    }
+
+    // NaN wins, 0.0 is larger than -0.0
+    public static float max(float a, float b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0f && b == 0.0f) {
+            return Float.floatToRawIntBits(a) == 0 ? a : b;
+        }
+        return (a >= b) ? a : b;
+    }
+
+    public static double max(double a, double b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0d && b == 0.0d) {
+            return Double.doubleToRawLongBits(a) == 0 ? a : b;
+        }
+        return (a >= b) ? a : b;
+    }
+
+    public static int min(int a, int b) {
+        return (a <= b) ? a : b;
+    }
+
+    public static long min(long a, long b) {
+        return (a <= b) ? a : b;
+    }
+
+    // NaN wins, -0.0 is smaller than 0.0
+    public static float min(float a, float b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0f && b == 0.0f) {
+            return Float.floatToRawIntBits(b) == 0 ? a : b;
+        }
+        return (a <= b) ? a : b;
+    }
+
+    public static double min(double a, double b) {
+        if (a != a) {
+            return a;
+        }
+        if (a == 0.0d && b == 0.0d) {
+            return Double.doubleToRawLongBits(b) == 0 ? a : b;
+        }
+        return (a <= b) ? a : b;
+    }
+
+    public static double sin(double a) {
+        return StrictMath.sin(a);
+    }
+
+    public static double cos(double a) {
+        return StrictMath.cos(a);
+    }
+
+    public static double tan(double a) {
+        return StrictMath.tan(a);
+    }
+
+    public static double asin(double a) {
+        return StrictMath.asin(a);
+    }
+
+    public static double acos(double a) {
+        return StrictMath.acos(a);
+    }
+
+    public static double atan(double a) {
+        return StrictMath.atan(a);
+    }
+
+    public static double atan2(double y, double x) {
+        return StrictMath.atan2(y, x);
+    }
+
+    public static double toRadians(double angdeg) {
+        return angdeg * (PI / 180.0);
+    }
+
+    public static double toDegrees(double angrad) {
+        return angrad * (180.0 / PI);
+    }
+
+    public static double exp(double a) {
+        return StrictMath.exp(a);
+    }
+
+    public static double log(double a) {
+        return StrictMath.log(a);
+    }
+
+    public static double log10(double a) {
+        return StrictMath.log10(a);
+    }
+
+    public static double sqrt(double a) {
+        return StrictMath.sqrt(a);
+    }
+
+    public static double cbrt(double a) {
+        return StrictMath.cbrt(a);
+    }
+
+    public static double pow(double a, double b) {
+        return StrictMath.pow(a, b);
+    }
+
+    public static double hypot(double x, double y) {
+        return StrictMath.hypot(x, y);
+    }
+
+    public static double sinh(double x) {
+        return StrictMath.sinh(x);
+    }
+
+    public static double cosh(double x) {
+        return StrictMath.cosh(x);
+    }
+
+    public static double tanh(double x) {
+        return StrictMath.tanh(x);
+    }
+
+    public static double expm1(double x) {
+        return StrictMath.expm1(x);
+    }
+
+    public static double log1p(double x) {
+        return StrictMath.log1p(x);
+    }
+
+    public static double floor(double a) {
+        return StrictMath.floor(a);
+    }
+
+    public static double ceil(double a) {
+        return StrictMath.ceil(a);
+    }
+
+    public static double rint(double a) {
+        return StrictMath.rint(a);
+    }
+
+    public static double IEEEremainder(double f1, double f2) {
+        return StrictMath.IEEEremainder(f1, f2);
+    }
+
+    public static double signum(double d) {
+        return (d == 0.0 || d != d) ? d : (d > 0.0 ? 1.0 : -1.0);
+    }
+
+    public static float signum(float f) {
+        return (f == 0.0f || f != f) ? f : (f > 0.0f ? 1.0f : -1.0f);
+    }
+
+    // rounds half up, NaN becomes 0 and the casts saturate out of range values
+    public static long round(double a) {
+        double floor = floor(a);
+        return (a - floor >= 0.5) ? (long) floor + 1 : (long) floor;
+    }
+
+    public static int round(float a) {
+        double floor = floor(a);
+        return (a - floor >= 0.5) ? (int) floor + 1 : (int) floor;
+    }
+
+    public static double random() {
+        if (seed == 0) {
+            seed = (System.nanoTime() ^ 0x5DEECE66DL) & ((1L << 48) - 1);
+        }
+        return (((long) next(26) << 27) + next(27)) * 0x1.0p-53;
+    }
+
+    private static int next(int bits) {
+        seed = (seed * 0x5DEECE66DL + 0xBL) & ((1L << 48) - 1);
+        return (int) (seed >>> (48 - bits));
+    }
+
+    public static int addExact(int x, int y) {
+        int r = x + y;
+        if (((x ^ r) & (y ^ r)) < 0) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return r;
+    }
+
+    public static long addExact(long x, long y) {
+        long r = x + y;
+        if (((x ^ r) & (y ^ r)) < 0) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int subtractExact(int x, int y) {
+        int r = x - y;
+        if (((x ^ y) & (x ^ r)) < 0) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return r;
+    }
+
+    public static long subtractExact(long x, long y) {
+        long r = x - y;
+        if (((x ^ y) & (x ^ r)) < 0) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int multiplyExact(int x, int y) {
+        long r = (long) x * (long) y;
+        if ((int) r != r) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return (int) r;
+    }
+
+    public static long multiplyExact(long x, long y) {
+        long r = x * y;
+        if (((abs(x) | abs(y)) >>> 31 != 0)
+                && ((y != 0 && r / y != x) || (x == Long.MIN_VALUE && y == -1))) {
+            throw new ArithmeticException("long overflow");
+        }
+        return r;
+    }
+
+    public static int negateExact(int a) {
+        if (a == Integer.MIN_VALUE) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return -a;
+    }
+
+    public static int toIntExact(long value) {
+        if ((int) value != value) {
+            throw new ArithmeticException("integer overflow");
+        }
+        return (int) value;
+    }
+
+    public static int floorDiv(int x, int y) {
+        int q = x / y;
+        if ((x ^ y) < 0 && q * y != x) {
+            q--;
+        }
+        return q;
+    }
+
+    public static long floorDiv(long x, long y) {
+        long q = x / y;
+        if ((x ^ y) < 0 && q * y != x) {
+            q--;
+        }
+        return q;
+    }
+
+    public static int floorMod(int x, int y) {
+        return x - floorDiv(x, y) * y;
+    }
+
+    public static long floorMod(long x, long y) {
+        return x - floorDiv(x, y) * y;
+    }
+}
@@ -0,0 +1,30 @@
+package java.lang;
+
+// The natives are the ones java.base declares, the VM implements them as intrinsics.
+public class Object {
+    public Object() {}
+
+    public final native Class<?> getClass();
+
+    public native int hashCode();
+
+    public boolean equals(Object obj) {
+        return this == obj;
+    }
+
+    protected native Object clone() throws CloneNotSupportedException;
+
+    public String toString() {
+        return getClass().getName() + "@" + Integer.toHexString(hashCode());
+    }
+
+    public final native void notify();
+
+    public final native void notifyAll();
+
+    public final void wait() throws InterruptedException {
+        wait(0L);
+    }
+
+    public final native void wait(long timeoutMillis) throws InterruptedException;
+}
@@ -0,0 +1,76 @@
+package java.lang;
+
+public final class Short extends Number implements Comparable<Short> {
+    public static final short MIN_VALUE = -32768;
+    public static final short MAX_VALUE = 32767;
+
+    private final short value;
+
+    public Short(short value) {
+        this.value = value;
+    }
+
+    public static Short valueOf(short s) {
+        return new Short(s);
+    }
+
+    public static Short valueOf(String s) {
+        return new Short(parseShort(s));
+    }
+
+    public static short parseShort(String s) {
+        return parseShort(s, 10);
+    }
+
+    public static short parseShort(String s, int radix) {
+        return (short) Long.parse(s, radix, MIN_VALUE, MAX_VALUE);
+    }
+
+    public static String toString(short s) {
+        return Integer.toString(s);
+    }
+
+    public static int hashCode(short value) {
+        return value;
+    }
+
+    public static int compare(short x, short y) {
+        return x - y;
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public short shortValue() {
+        return value;
+    }
+
+    public int compareTo(Short anotherShort) {
+        return compare(value, anotherShort.value);
+    }
+
+    public boolean equals(Object obj) {
+        return obj instanceof Short && value == ((Short) obj).value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public String toString() {
+        return Integer.toString(value);
+    }
+}
@@ -0,0 +1,15 @@
+package java.lang;
+
+// The VM calls shutdown once main returns. There are no shutdown hooks in the bundled library,
+// so exiting halts straight away.
+final class Shutdown {
+    private Shutdown() {}
+
+    static void shutdown() {}
+
+    static void exit(int status) {
+        halt0(status);
+    }
+
+    private static native void halt0(int status);
+}
@@ -0,0 +1,53 @@
+package java.lang;
+
+// The VM computes these with the Rust standard library. java.base has pow, exp, cbrt, hypot,
+// floor, ceil and rint in Java, in the bundled library they are natives as well.
+public final class StrictMath {
+    private StrictMath() {}
+
+    public static native double sin(double a);
+
+    public static native double cos(double a);
+
+    public static native double tan(double a);
+
+    public static native double asin(double a);
+
+    public static native double acos(double a);
+
+    public static native double atan(double a);
+
+    public static native double atan2(double y, double x);
+
+    public static native double exp(double a);
+
+    public static native double log(double a);
+
+    public static native double log10(double a);
+
+    public static native double sqrt(double a);
+
+    public static native double cbrt(double a);
+
+    public static native double pow(double a, double b);
+
+    public static native double hypot(double x, double y);
+
+    public static native double sinh(double x);
+
+    public static native double cosh(double x);
+
+    public static native double tanh(double x);
+
+    public static native double expm1(double x);
+
+    public static native double log1p(double x);
+
+    public static native double floor(double a);
+
+    public static native double ceil(double a);
+
+    public static native double rint(double a);
+
+    public static native double IEEEremainder(double f1, double f2);
+}
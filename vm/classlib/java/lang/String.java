@@ -0,0 +1,628 @@
+package java.lang;
+
+import java.util.Iterator;
+
+// The layout is the one of java.base that the VM allocates string literals with: Latin-1 text
+// one byte per char (coder 0), anything else as UTF-16 (coder 1).
+public final class String implements java.io.Serializable, Comparable<String>, CharSequence {
+    static final byte LATIN1 = 0;
+    static final byte UTF16 = 1;
+
+    private final byte[] value;
+    private final byte coder;
+    private int hash;
+
+    public String() {
+        this.value = new byte[0];
+        this.coder = LATIN1;
+    }
+
+    public String(String original) {
+        this.value = original.value;
+        this.coder = original.coder;
+        this.hash = original.hash;
+    }
+
+    public String(char[] value) {
+        this(value, 0, value.length);
+    }
+
+    public String(char[] value, int offset, int count) {
+        checkBoundsOffCount(offset, count, value.length);
+        this.coder = isLatin1(value, offset, count) ? LATIN1 : UTF16;
+        this.value = encode(value, offset, count, this.coder);
+    }
+
+    // bytes are decoded as UTF-8, malformed input becomes U+FFFD
+    public String(byte[] bytes) {
+        this(bytes, 0, bytes.length);
+    }
+
+    public String(byte[] bytes, int offset, int length) {
+        this(decodeUtf8(bytes, offset, length));
+    }
+
+    public String(StringBuilder builder) {
+        this(builder.value, 0, builder.count);
+    }
+
+    private static boolean isLatin1(char[] chars, int offset, int count) {
+        for (int i = offset; i < offset + count; i++) {
+            if (chars[i] > 0xff) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    private static byte[] encode(char[] chars, int offset, int count, byte coder) {
+        if (coder == LATIN1) {
+            byte[] bytes = new byte[count];
+            for (int i = 0; i < count; i++) {
+                bytes[i] = (byte) chars[offset + i];
+            }
+            return bytes;
+        }
+        byte[] bytes = new byte[count << 1];
+        for (int i = 0; i < count; i++) {
+            StringUTF16.putChar(bytes, i, chars[offset + i]);
+        }
+        return bytes;
+    }
+
+    private static StringBuilder decodeUtf8(byte[] bytes, int offset, int length) {
+        checkBoundsOffCount(offset, length, bytes.length);
+        StringBuilder chars = new StringBuilder(length);
+        int i = offset;
+        int end = offset + length;
+        while (i < end) {
+            int b = bytes[i++] & 0xff;
+            int extra;
+            int codePoint;
+            if (b < 0x80) {
+                chars.append((char) b);
+                continue;
+            } else if (b >= 0xc2 && b < 0xe0) {
+                extra = 1;
+                codePoint = b & 0x1f;
+            } else if (b >= 0xe0 && b < 0xf0) {
+                extra = 2;
+                codePoint = b & 0x0f;
+            } else if (b >= 0xf0 && b < 0xf5) {
+                extra = 3;
+                codePoint = b & 0x07;
+            } else {
+                chars.append('\ufffd');
+                continue;
+            }
+            int start = i;
+            while (extra > 0 && i < end && (bytes[i] & 0xc0) == 0x80) {
+                codePoint = (codePoint << 6) | (bytes[i++] & 0x3f);
+                extra--;
+            }
+            if (extra > 0 || codePoint > 0x10ffff || (i - start == 2 && codePoint < 0x800)
+                    || (i - start == 3 && codePoint < 0x10000)
+                    || (codePoint >= 0xd800 && codePoint < 0xe000)) {
+                chars.append('\ufffd');
+            } else if (codePoint >= 0x10000) {
+                codePoint -= 0x10000;
+                chars.append((char) (0xd800 | (codePoint >> 10)));
+                chars.append((char) (0xdc00 | (codePoint & 0x3ff)));
+            } else {
+                chars.append((char) codePoint);
+            }
+        }
+        return chars;
+    }
+
+    static void checkBoundsOffCount(int offset, int count, int length) {
+        if (offset < 0 || count < 0 || offset > length - count) {
+            throw new StringIndexOutOfBoundsException(
+                    "offset " + offset + ", count " + count + ", length " + length);
+        }
+    }
+
+    private static void checkBoundsBeginEnd(int begin, int end, int length) {
+        if (begin < 0 || begin > end || end > length) {
+            throw new StringIndexOutOfBoundsException(
+                    "begin " + begin + ", end " + end + ", length " + length);
+        }
+    }
+
+    // the char at index without a bounds check
+    private char at(int index) {
+        if (coder == LATIN1) {
+            return (char) (value[index] & 0xff);
+        }
+        return StringUTF16.getChar(value, index);
+    }
+
+    public int length() {
+        return value.length >> coder;
+    }
+
+    public boolean isEmpty() {
+        return value.length == 0;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= length()) {
+            throw new StringIndexOutOfBoundsException(
+                    "index " + index + ", length " + length());
+        }
+        return at(index);
+    }
+
+    public void getChars(int srcBegin, int srcEnd, char[] dst, int dstBegin) {
+        checkBoundsBeginEnd(srcBegin, srcEnd, length());
+        checkBoundsOffCount(dstBegin, srcEnd - srcBegin, dst.length);
+        for (int i = srcBegin; i < srcEnd; i++) {
+            dst[dstBegin++] = at(i);
+        }
+    }
+
+    public char[] toCharArray() {
+        char[] chars = new char[length()];
+        getChars(0, chars.length, chars, 0);
+        return chars;
+    }
+
+    // the UTF-8 encoding, unpaired surrogates become '?'
+    public byte[] getBytes() {
+        int length = length();
+        if (coder == LATIN1) {
+            int size = length;
+            for (int i = 0; i < length; i++) {
+                if (value[i] < 0) {
+                    size++;
+                }
+            }
+            if (size == length) {
+                byte[] bytes = new byte[length];
+                System.arraycopy(value, 0, bytes, 0, length);
+                return bytes;
+            }
+        }
+        byte[] bytes = new byte[length * 3];
+        int size = 0;
+        for (int i = 0; i < length; i++) {
+            char c = at(i);
+            if (c < 0x80) {
+                bytes[size++] = (byte) c;
+            } else if (c < 0x800) {
+                bytes[size++] = (byte) (0xc0 | (c >> 6));
+                bytes[size++] = (byte) (0x80 | (c & 0x3f));
+            } else if (Character.isSurrogate(c)) {
+                char low = i + 1 < length ? at(i + 1) : 0;
+                if (Character.isHighSurrogate(c) && Character.isLowSurrogate(low)) {
+                    int codePoint = 0x10000 + ((c - 0xd800) << 10) + (low - 0xdc00);
+                    bytes[size++] = (byte) (0xf0 | (codePoint >> 18));
+                    bytes[size++] = (byte) (0x80 | ((codePoint >> 12) & 0x3f));
+                    bytes[size++] = (byte) (0x80 | ((codePoint >> 6) & 0x3f));
+                    bytes[size++] = (byte) (0x80 | (codePoint & 0x3f));
+                    i++;
+                } else {
+                    bytes[size++] = '?';
+                }
+            } else {
+                bytes[size++] = (byte) (0xe0 | (c >> 12));
+                bytes[size++] = (byte) (0x80 | ((c >> 6) & 0x3f));
+                bytes[size++] = (byte) (0x80 | (c & 0x3f));
+            }
+        }
+        byte[] result = new byte[size];
+        System.arraycopy(bytes, 0, result, 0, size);
+        return result;
+    }
+
+    public boolean equals(Object anObject) {
+        if (this == anObject) {
+            return true;
+        }
+        if (!(anObject instanceof String)) {
+            return false;
+        }
+        String other = (String) anObject;
+        int length = length();
+        if (other.length() != length) {
+            return false;
+        }
+        for (int i = 0; i < length; i++) {
+            if (at(i) != other.at(i)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public boolean contentEquals(CharSequence cs) {
+        int length = length();
+        if (cs.length() != length) {
+            return false;
+        }
+        for (int i = 0; i < length; i++) {
+            if (at(i) != cs.charAt(i)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public boolean equalsIgnoreCase(String anotherString) {
+        return anotherString != null && anotherString.length() == length()
+                && regionMatches(true, 0, anotherString, 0, length());
+    }
+
+    public boolean regionMatches(int toffset, String other, int ooffset, int len) {
+        return regionMatches(false, toffset, other, ooffset, len);
+    }
+
+    public boolean regionMatches(boolean ignoreCase, int toffset, String other, int ooffset,
+            int len) {
+        if (ooffset < 0 || toffset < 0 || toffset > (long) length() - len
+                || ooffset > (long) other.length() - len) {
+            return false;
+        }
+        for (int i = 0; i < len; i++) {
+            char c1 = at(toffset + i);
+            char c2 = other.at(ooffset + i);
+            if (c1 == c2) {
+                continue;
+            }
+            if (!ignoreCase) {
+                return false;
+            }
+            char u1 = Character.toUpperCase(c1);
+            char u2 = Character.toUpperCase(c2);
+            if (u1 != u2 && Character.toLowerCase(u1) != Character.toLowerCase(u2)) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int compareTo(String anotherString) {
+        int length1 = length();
+        int length2 = anotherString.length();
+        int limit = Math.min(length1, length2);
+        for (int i = 0; i < limit; i++) {
+            char c1 = at(i);
+            char c2 = anotherString.at(i);
+            if (c1 != c2) {
+                return c1 - c2;
+            }
+        }
+        return length1 - length2;
+    }
+
+    public int compareToIgnoreCase(String str) {
+        int length1 = length();
+        int length2 = str.length();
+        int limit = Math.min(length1, length2);
+        for (int i = 0; i < limit; i++) {
+            char c1 = Character.toLowerCase(Character.toUpperCase(at(i)));
+            char c2 = Character.toLowerCase(Character.toUpperCase(str.at(i)));
+            if (c1 != c2) {
+                return c1 - c2;
+            }
+        }
+        return length1 - length2;
+    }
+
+    public int hashCode() {
+        int h = hash;
+        if (h == 0) {
+            int length = length();
+            for (int i = 0; i < length; i++) {
+                h = 31 * h + at(i);
+            }
+            hash = h;
+        }
+        return h;
+    }
+
+    public int indexOf(int ch) {
+        return indexOf(ch, 0);
+    }
+
+    public int indexOf(int ch, int fromIndex) {
+        int length = length();
+        for (int i = Math.max(fromIndex, 0); i < length; i++) {
+            if (at(i) == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int indexOf(String str) {
+        return indexOf(str, 0);
+    }
+
+    public int indexOf(String str, int fromIndex) {
+        int last = length() - str.length();
+        for (int i = Math.max(fromIndex, 0); i <= last; i++) {
+            if (regionMatches(i, str, 0, str.length())) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(int ch) {
+        return lastIndexOf(ch, length() - 1);
+    }
+
+    public int lastIndexOf(int ch, int fromIndex) {
+        for (int i = Math.min(fromIndex, length() - 1); i >= 0; i--) {
+            if (at(i) == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(String str) {
+        return lastIndexOf(str, length());
+    }
+
+    public int lastIndexOf(String str, int fromIndex) {
+        for (int i = Math.min(fromIndex, length() - str.length()); i >= 0; i--) {
+            if (regionMatches(i, str, 0, str.length())) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public boolean contains(CharSequence s) {
+        return indexOf(s.toString()) >= 0;
+    }
+
+    public boolean startsWith(String prefix) {
+        return startsWith(prefix, 0);
+    }
+
+    public boolean startsWith(String prefix, int toffset) {
+        return regionMatches(toffset, prefix, 0, prefix.length());
+    }
+
+    public boolean endsWith(String suffix) {
+        return startsWith(suffix, length() - suffix.length());
+    }
+
+    public String substring(int beginIndex) {
+        return substring(beginIndex, length());
+    }
+
+    public String substring(int beginIndex, int endIndex) {
+        int length = length();
+        checkBoundsBeginEnd(beginIndex, endIndex, length);
+        if (beginIndex == 0 && endIndex == length) {
+            return this;
+        }
+        char[] chars = new char[endIndex - beginIndex];
+        getChars(beginIndex, endIndex, chars, 0);
+        return new String(chars);
+    }
+
+    public CharSequence subSequence(int beginIndex, int endIndex) {
+        return substring(beginIndex, endIndex);
+    }
+
+    public String concat(String str) {
+        if (str.isEmpty()) {
+            return this;
+        }
+        return new StringBuilder(this).append(str).toString();
+    }
+
+    public String replace(char oldChar, char newChar) {
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            if (chars[i] == oldChar) {
+                chars[i] = newChar;
+            }
+        }
+        return new String(chars);
+    }
+
+    public String replace(CharSequence target, CharSequence replacement) {
+        String from = target.toString();
+        String to = replacement.toString();
+        StringBuilder result = new StringBuilder();
+        int start = 0;
+        if (from.isEmpty()) {
+            // an empty target matches before every char and at the end
+            for (int i = 0; i < length(); i++) {
+                result.append(to).append(at(i));
+            }
+            return result.append(to).toString();
+        }
+        int index;
+        while ((index = indexOf(from, start)) >= 0) {
+            result.append(this, start, index).append(to);
+            start = index + from.length();
+        }
+        return result.append(this, start, length()).toString();
+    }
+
+    public String toLowerCase() {
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            chars[i] = Character.toLowerCase(chars[i]);
+        }
+        return new String(chars);
+    }
+
+    public String toUpperCase() {
+        char[] chars = toCharArray();
+        for (int i = 0; i < chars.length; i++) {
+            chars[i] = Character.toUpperCase(chars[i]);
+        }
+        return new String(chars);
+    }
+
+    // without the chars up to ' ' at either end
+    public String trim() {
+        int begin = 0;
+        int end = length();
+        while (begin < end && at(begin) <= ' ') {
+            begin++;
+        }
+        while (end > begin && at(end - 1) <= ' ') {
+            end--;
+        }
+        return substring(begin, end);
+    }
+
+    // without the whitespace at either end
+    public String strip() {
+        int begin = 0;
+        int end = length();
+        while (begin < end && Character.isWhitespace(at(begin))) {
+            begin++;
+        }
+        while (end > begin && Character.isWhitespace(at(end - 1))) {
+            end--;
+        }
+        return substring(begin, end);
+    }
+
+    public boolean isBlank() {
+        return strip().isEmpty();
+    }
+
+    public String repeat(int count) {
+        if (count < 0) {
+            throw new IllegalArgumentException("count is negative: " + count);
+        }
+        StringBuilder result = new StringBuilder(length() * count);
+        for (int i = 0; i < count; i++) {
+            result.append(this);
+        }
+        return result.toString();
+    }
+
+    public String[] split(String regex) {
+        return split(regex, 0);
+    }
+
+    // There are no regular expressions, regex has to stand for a literal: a single char other
+    // than one of ".$|()[{^?*+\\", one escaped with a backslash or a string without any of
+    // them. These are the cases java.base splits without compiling a pattern.
+    public String[] split(String regex, int limit) {
+        String separator = regex;
+        if (regex.length() == 2 && regex.charAt(0) == '\\'
+                && !Character.isLetterOrDigit(regex.charAt(1))) {
+            separator = regex.substring(1);
+        } else {
+            for (int i = 0; i < regex.length(); i++) {
+                if (".$|()[{^?*+\\".indexOf(regex.charAt(i)) >= 0) {
+                    throw new UnsupportedOperationException(
+                            "regular expressions are not supported: " + regex);
+                }
+            }
+        }
+        java.util.ArrayList<String> parts = new java.util.ArrayList<>();
+        int length = length();
+        int start = 0;
+        if (separator.isEmpty()) {
+            // the empty string matches between any two chars and at the end
+            while ((limit <= 0 || parts.size() < limit - 1) && start < length) {
+                parts.add(substring(start, start + 1));
+                start++;
+            }
+        } else {
+            int index;
+            while ((limit <= 0 || parts.size() < limit - 1)
+                    && (index = indexOf(separator, start)) >= 0) {
+                parts.add(substring(start, index));
+                start = index + separator.length();
+            }
+        }
+        if (parts.isEmpty()) {
+            return new String[] {this};
+        }
+        parts.add(substring(start));
+        int size = parts.size();
+        if (limit == 0) {
+            while (size > 0 && parts.get(size - 1).isEmpty()) {
+                size--;
+            }
+        }
+        String[] result = new String[size];
+        for (int i = 0; i < size; i++) {
+            result[i] = parts.get(i);
+        }
+        return result;
+    }
+
+    public static String join(CharSequence delimiter, CharSequence... elements) {
+        StringBuilder result = new StringBuilder();
+        for (int i = 0; i < elements.length; i++) {
+            if (i > 0) {
+                result.append(delimiter);
+            }
+            result.append(elements[i]);
+        }
+        return result.toString();
+    }
+
+    public static String join(CharSequence delimiter,
+            Iterable<? extends CharSequence> elements) {
+        StringBuilder result = new StringBuilder();
+        Iterator<? extends CharSequence> iterator = elements.iterator();
+        while (iterator.hasNext()) {
+            result.append(iterator.next());
+            if (iterator.hasNext()) {
+                result.append(delimiter);
+            }
+        }
+        return result.toString();
+    }
+
+    public String toString() {
+        return this;
+    }
+
+    public static String valueOf(Object obj) {
+        return obj == null ? "null" : obj.toString();
+    }
+
+    public static String valueOf(char[] data) {
+        return new String(data);
+    }
+
+    public static String valueOf(char[] data, int offset, int count) {
+        return new String(data, offset, count);
+    }
+
+    public static String copyValueOf(char[] data) {
+        return new String(data);
+    }
+
+    public static String valueOf(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public static String valueOf(char c) {
+        return new String(new char[] {c});
+    }
+
+    public static String valueOf(int i) {
+        return Integer.toString(i);
+    }
+
+    public static String valueOf(long l) {
+        return Long.toString(l);
+    }
+
+    public static String valueOf(float f) {
+        return Float.toString(f);
+    }
+
+    public static String valueOf(double d) {
+        return Double.toString(d);
+    }
+}
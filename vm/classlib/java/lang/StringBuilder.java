@@ -0,0 +1,274 @@
+package java.lang;
+
+// The chars go into a char[] that grows as needed, javac's string concatenation
+// (-XDstringConcat=inline) comes down to the appends here.
+public final class StringBuilder implements java.io.Serializable, CharSequence {
+    char[] value;
+    int count;
+
+    public StringBuilder() {
+        this(16);
+    }
+
+    public StringBuilder(int capacity) {
+        if (capacity < 0) {
+            throw new NegativeArraySizeException(Integer.toString(capacity));
+        }
+        value = new char[capacity];
+    }
+
+    public StringBuilder(String str) {
+        this(str.length() + 16);
+        append(str);
+    }
+
+    public StringBuilder(CharSequence seq) {
+        this(seq.length() + 16);
+        append(seq);
+    }
+
+    private void ensureCapacity(int minimumCapacity) {
+        if (minimumCapacity > value.length) {
+            char[] grown = new char[Math.max(minimumCapacity, (value.length << 1) + 2)];
+            System.arraycopy(value, 0, grown, 0, count);
+            value = grown;
+        }
+    }
+
+    private void checkIndex(int index, int length) {
+        if (index < 0 || index >= length) {
+            throw new StringIndexOutOfBoundsException("index " + index + ",length " + length);
+        }
+    }
+
+    private void checkOffset(int offset) {
+        if (offset < 0 || offset > count) {
+            throw new StringIndexOutOfBoundsException("offset " + offset + ", length " + count);
+        }
+    }
+
+    public int length() {
+        return count;
+    }
+
+    public int capacity() {
+        return value.length;
+    }
+
+    public char charAt(int index) {
+        checkIndex(index, count);
+        return value[index];
+    }
+
+    public void setCharAt(int index, char ch) {
+        checkIndex(index, count);
+        value[index] = ch;
+    }
+
+    public void setLength(int newLength) {
+        if (newLength < 0) {
+            throw new StringIndexOutOfBoundsException(newLength);
+        }
+        ensureCapacity(newLength);
+        for (int i = count; i < newLength; i++) {
+            value[i] = '\0';
+        }
+        count = newLength;
+    }
+
+    public StringBuilder append(Object obj) {
+        return append(String.valueOf(obj));
+    }
+
+    public StringBuilder append(String str) {
+        if (str == null) {
+            str = "null";
+        }
+        int length = str.length();
+        ensureCapacity(count + length);
+        str.getChars(0, length, value, count);
+        count += length;
+        return this;
+    }
+
+    public StringBuilder append(StringBuilder sb) {
+        if (sb == null) {
+            return append("null");
+        }
+        return append(sb.value, 0, sb.count);
+    }
+
+    public StringBuilder append(CharSequence s) {
+        if (s == null) {
+            return append("null");
+        }
+        return append(s, 0, s.length());
+    }
+
+    public StringBuilder append(CharSequence s, int start, int end) {
+        if (s == null) {
+            s = "null";
+        }
+        if (start < 0 || start > end || end > s.length()) {
+            throw new IndexOutOfBoundsException(
+                    "start " + start + ", end " + end + ", length " + s.length());
+        }
+        ensureCapacity(count + end - start);
+        for (int i = start; i < end; i++) {
+            value[count++] = s.charAt(i);
+        }
+        return this;
+    }
+
+    public StringBuilder append(char[] str) {
+        return append(str, 0, str.length);
+    }
+
+    public StringBuilder append(char[] str, int offset, int len) {
+        String.checkBoundsOffCount(offset, len, str.length);
+        ensureCapacity(count + len);
+        System.arraycopy(str, offset, value, count, len);
+        count += len;
+        return this;
+    }
+
+    public StringBuilder append(boolean b) {
+        return append(b ? "true" : "false");
+    }
+
+    public StringBuilder append(char c) {
+        ensureCapacity(count + 1);
+        value[count++] = c;
+        return this;
+    }
+
+    public StringBuilder append(int i) {
+        return append(Integer.toString(i));
+    }
+
+    public StringBuilder append(long lng) {
+        return append(Long.toString(lng));
+    }
+
+    public StringBuilder append(float f) {
+        return append(Float.toString(f));
+    }
+
+    public StringBuilder append(double d) {
+        return append(Double.toString(d));
+    }
+
+    public StringBuilder insert(int offset, String str) {
+        checkOffset(offset);
+        if (str == null) {
+            str = "null";
+        }
+        int length = str.length();
+        ensureCapacity(count + length);
+        System.arraycopy(value, offset, value, offset + length, count - offset);
+        str.getChars(0, length, value, offset);
+        count += length;
+        return this;
+    }
+
+    public StringBuilder insert(int offset, Object obj) {
+        return insert(offset, String.valueOf(obj));
+    }
+
+    public StringBuilder insert(int offset, char c) {
+        return insert(offset, String.valueOf(c));
+    }
+
+    public StringBuilder insert(int offset, boolean b) {
+        return insert(offset, String.valueOf(b));
+    }
+
+    public StringBuilder insert(int offset, int i) {
+        return insert(offset, String.valueOf(i));
+    }
+
+    public StringBuilder insert(int offset, long l) {
+        return insert(offset, String.valueOf(l));
+    }
+
+    public StringBuilder insert(int offset, double d) {
+        return insert(offset, String.valueOf(d));
+    }
+
+    public StringBuilder delete(int start, int end) {
+        if (end > count) {
+            end = count;
+        }
+        if (start < 0 || start > end) {
+            throw new StringIndexOutOfBoundsException(
+                    "start " + start + ", end " + end + ", length " + count);
+        }
+        System.arraycopy(value, end, value, start, count - end);
+        count -= end - start;
+        return this;
+    }
+
+    public StringBuilder deleteCharAt(int index) {
+        checkIndex(index, count);
+        return delete(index, index + 1);
+    }
+
+    public StringBuilder replace(int start, int end, String str) {
+        if (start < 0 || start > count || start > end) {
+            throw new StringIndexOutOfBoundsException(
+                    "start " + start + ", end " + end + ", length " + count);
+        }
+        delete(start, end);
+        return insert(start, str);
+    }
+
+    public StringBuilder reverse() {
+        for (int i = 0, j = count - 1; i < j; i++, j--) {
+            char c = value[i];
+            value[i] = value[j];
+            value[j] = c;
+        }
+        // surrogate pairs keep their order
+        for (int i = 0; i < count - 1; i++) {
+            if (Character.isLowSurrogate(value[i]) && Character.isHighSurrogate(value[i + 1])) {
+                char c = value[i];
+                value[i] = value[i + 1];
+                value[i + 1] = c;
+                i++;
+            }
+        }
+        return this;
+    }
+
+    public int indexOf(String str) {
+        return toString().indexOf(str);
+    }
+
+    public int indexOf(String str, int fromIndex) {
+        return toString().indexOf(str, fromIndex);
+    }
+
+    public int lastIndexOf(String str) {
+        return toString().lastIndexOf(str);
+    }
+
+    public String substring(int start) {
+        return substring(start, count);
+    }
+
+    public String substring(int start, int end) {
+        if (start < 0 || start > end || end > count) {
+            throw new StringIndexOutOfBoundsException(
+                    "start " + start + ", end " + end + ", length " + count);
+        }
+        return new String(value, start, end - start);
+    }
+
+    public CharSequence subSequence(int start, int end) {
+        return substring(start, end);
+    }
+
+    public String toString() {
+        return new String(value, 0, count);
+    }
+}
@@ -0,0 +1,33 @@
+package java.lang;
+
+// chars of a UTF-16 String value, two bytes each in native byte order as the VM writes them
+final class StringUTF16 {
+    private static final int HI_BYTE_SHIFT;
+    private static final int LO_BYTE_SHIFT;
+
+    static {
+        if (isBigEndian()) {
+            HI_BYTE_SHIFT = 8;
+            LO_BYTE_SHIFT = 0;
+        } else {
+            HI_BYTE_SHIFT = 0;
+            LO_BYTE_SHIFT = 8;
+        }
+    }
+
+    private StringUTF16() {}
+
+    static char getChar(byte[] value, int index) {
+        index <<= 1;
+        return (char) (((value[index] & 0xff) << HI_BYTE_SHIFT)
+                | ((value[index + 1] & 0xff) << LO_BYTE_SHIFT));
+    }
+
+    static void putChar(byte[] value, int index, int c) {
+        index <<= 1;
+        value[index] = (byte) (c >> HI_BYTE_SHIFT);
+        value[index + 1] = (byte) (c >> LO_BYTE_SHIFT);
+    }
+
+    private static native boolean isBigEndian();
+}
@@ -0,0 +1,43 @@
+package java.lang;
+
+import java.io.FileDescriptor;
+import java.io.FileInputStream;
+import java.io.FileOutputStream;
+import java.io.InputStream;
+import java.io.PrintStream;
+
+// The standard streams are set up when the VM initializes the class, there is no initPhase1.
+public final class System {
+    public static final InputStream in = new FileInputStream(FileDescriptor.in);
+    public static final PrintStream out = new PrintStream(new FileOutputStream(FileDescriptor.out));
+    public static final PrintStream err = new PrintStream(new FileOutputStream(FileDescriptor.err));
+
+    private System() {}
+
+    public static native void arraycopy(Object src, int srcPos, Object dest, int destPos,
+            int length);
+
+    public static native long currentTimeMillis();
+
+    public static native long nanoTime();
+
+    public static native int identityHashCode(Object x);
+
+    // the properties set with -D or the builder, and the few the VM knows itself
+    public static native String getProperty(String key);
+
+    public static String getProperty(String key, String def) {
+        String value = getProperty(key);
+        return value == null ? def : value;
+    }
+
+    public static String lineSeparator() {
+        return "\n";
+    }
+
+    public static void exit(int status) {
+        Shutdown.exit(status);
+    }
+
+    public static void gc() {}
+}
@@ -0,0 +1,85 @@
+package java.lang;
+
+// There is no stack trace to fill in, printStackTrace prints the exception and its causes.
+public class Throwable implements java.io.Serializable {
+    private String detailMessage;
+    // this until initCause or a constructor sets it
+    private Throwable cause = this;
+    private Throwable[] suppressed = new Throwable[0];
+
+    public Throwable() {}
+
+    public Throwable(String message) {
+        detailMessage = message;
+    }
+
+    public Throwable(String message, Throwable cause) {
+        detailMessage = message;
+        this.cause = cause;
+    }
+
+    public Throwable(Throwable cause) {
+        detailMessage = (cause == null) ? null : cause.toString();
+        this.cause = cause;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public String getLocalizedMessage() {
+        return getMessage();
+    }
+
+    public Throwable getCause() {
+        return (cause == this) ? null : cause;
+    }
+
+    public Throwable initCause(Throwable cause) {
+        if (this.cause != this) {
+            throw new IllegalStateException("Can't overwrite cause with " + cause, this);
+        }
+        if (cause == this) {
+            throw new IllegalArgumentException("Self-causation not permitted", this);
+        }
+        this.cause = cause;
+        return this;
+    }
+
+    public Throwable fillInStackTrace() {
+        return this;
+    }
+
+    public final void addSuppressed(Throwable exception) {
+        if (exception == this) {
+            throw new IllegalArgumentException("Self-suppression not permitted", exception);
+        }
+        if (exception == null) {
+            throw new NullPointerException("Cannot suppress a null exception.");
+        }
+        Throwable[] grown = new Throwable[suppressed.length + 1];
+        System.arraycopy(suppressed, 0, grown, 0, suppressed.length);
+        grown[suppressed.length] = exception;
+        suppressed = grown;
+    }
+
+    public final Throwable[] getSuppressed() {
+        return suppressed.clone();
+    }
+
+    public void printStackTrace() {
+        System.err.println(this);
+        for (Throwable t : suppressed) {
+            System.err.println("\tSuppressed: " + t);
+        }
+        for (Throwable t = getCause(); t != null; t = t.getCause()) {
+            System.err.println("Caused by: " + t);
+        }
+    }
+
+    public String toString() {
+        String message = getLocalizedMessage();
+        String name = getClass().getName();
+        return (message != null) ? (name + ": " + message) : name;
+    }
+}
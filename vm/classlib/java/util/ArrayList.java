@@ -0,0 +1,207 @@
+package java.util;
+
+public class ArrayList<E> extends AbstractCollection<E> implements List<E>, Cloneable,
+        java.io.Serializable {
+    private Object[] elementData;
+    private int size;
+    // changes of the size, iterators fail when it moves under them
+    private int modCount;
+
+    public ArrayList() {
+        this(10);
+    }
+
+    public ArrayList(int initialCapacity) {
+        if (initialCapacity < 0) {
+            throw new IllegalArgumentException("Illegal Capacity: " + initialCapacity);
+        }
+        elementData = new Object[initialCapacity];
+    }
+
+    public ArrayList(Collection<? extends E> c) {
+        elementData = c.toArray();
+        size = elementData.length;
+    }
+
+    private void grow(int minCapacity) {
+        if (minCapacity > elementData.length) {
+            int capacity = elementData.length + (elementData.length >> 1) + 1;
+            Object[] grown = new Object[Math.max(minCapacity, capacity)];
+            System.arraycopy(elementData, 0, grown, 0, size);
+            elementData = grown;
+        }
+    }
+
+    private void checkIndex(int index, int length) {
+        if (index < 0 || index >= length) {
+            throw new IndexOutOfBoundsException(
+                    "Index " + index + " out of bounds for length " + length);
+        }
+    }
+
+    public void ensureCapacity(int minCapacity) {
+        grow(minCapacity);
+    }
+
+    public int size() {
+        return size;
+    }
+
+    public E get(int index) {
+        checkIndex(index, size);
+        return (E) elementData[index];
+    }
+
+    public E set(int index, E element) {
+        E old = get(index);
+        elementData[index] = element;
+        return old;
+    }
+
+    public boolean add(E e) {
+        modCount++;
+        grow(size + 1);
+        elementData[size++] = e;
+        return true;
+    }
+
+    public void add(int index, E element) {
+        if (index < 0 || index > size) {
+            throw new IndexOutOfBoundsException("Index: " + index + ", Size: " + size);
+        }
+        modCount++;
+        grow(size + 1);
+        System.arraycopy(elementData, index, elementData, index + 1, size - index);
+        elementData[index] = element;
+        size++;
+    }
+
+    public E remove(int index) {
+        E old = get(index);
+        modCount++;
+        System.arraycopy(elementData, index + 1, elementData, index, size - index - 1);
+        elementData[--size] = null;
+        return old;
+    }
+
+    public boolean remove(Object o) {
+        int index = indexOf(o);
+        if (index < 0) {
+            return false;
+        }
+        remove(index);
+        return true;
+    }
+
+    public void clear() {
+        modCount++;
+        for (int i = 0; i < size; i++) {
+            elementData[i] = null;
+        }
+        size = 0;
+    }
+
+    public int indexOf(Object o) {
+        for (int i = 0; i < size; i++) {
+            if (Objects.equals(o, elementData[i])) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(Object o) {
+        for (int i = size - 1; i >= 0; i--) {
+            if (Objects.equals(o, elementData[i])) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public boolean contains(Object o) {
+        return indexOf(o) >= 0;
+    }
+
+    public Object[] toArray() {
+        Object[] result = new Object[size];
+        System.arraycopy(elementData, 0, result, 0, size);
+        return result;
+    }
+
+    public Iterator<E> iterator() {
+        return new Itr();
+    }
+
+    public Object clone() {
+        ArrayList<E> copy = new ArrayList<>(size);
+        System.arraycopy(elementData, 0, copy.elementData, 0, size);
+        copy.size = size;
+        return copy;
+    }
+
+    public boolean equals(Object o) {
+        if (o == this) {
+            return true;
+        }
+        if (!(o instanceof List)) {
+            return false;
+        }
+        List<?> other = (List<?>) o;
+        if (other.size() != size) {
+            return false;
+        }
+        Iterator<?> it = other.iterator();
+        for (int i = 0; i < size; i++) {
+            if (!Objects.equals(elementData[i], it.next())) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int hashCode() {
+        int hashCode = 1;
+        for (int i = 0; i < size; i++) {
+            hashCode = 31 * hashCode + Objects.hashCode(elementData[i]);
+        }
+        return hashCode;
+    }
+
+    private class Itr implements Iterator<E> {
+        // the element next returns and the one it returned last, -1 after remove
+        private int cursor;
+        private int lastRet = -1;
+        private int expectedModCount = modCount;
+
+        public boolean hasNext() {
+            return cursor != size;
+        }
+
+        public E next() {
+            checkForComodification();
+            if (cursor >= size) {
+                throw new NoSuchElementException();
+            }
+            lastRet = cursor++;
+            return (E) elementData[lastRet];
+        }
+
+        public void remove() {
+            if (lastRet < 0) {
+                throw new IllegalStateException();
+            }
+            checkForComodification();
+            ArrayList.this.remove(lastRet);
+            cursor = lastRet;
+            lastRet = -1;
+            expectedModCount = modCount;
+        }
+
+        private void checkForComodification() {
+            if (modCount != expectedModCount) {
+                throw new ConcurrentModificationException();
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+package java.util;
+
+// Buckets of linked nodes that double at a load factor of 0.75. Keys hash and spread as in
+// java.base and the buckets keep their order when the table grows, so the iteration order is
+// the one java.base gives for maps of up to a few thousand keys.
+public class HashMap<K, V> implements Map<K, V>, Cloneable, java.io.Serializable {
+    private static final int DEFAULT_CAPACITY = 16;
+
+    private Node<K, V>[] table;
+    private int size;
+    private int modCount;
+
+    static class Node<K, V> implements Map.Entry<K, V> {
+        final int hash;
+        final K key;
+        V value;
+        Node<K, V> next;
+
+        Node(int hash, K key, V value) {
+            this.hash = hash;
+            this.key = key;
+            this.value = value;
+        }
+
+        public final K getKey() {
+            return key;
+        }
+
+        public final V getValue() {
+            return value;
+        }
+
+        public final V setValue(V newValue) {
+            V oldValue = value;
+            value = newValue;
+            return oldValue;
+        }
+
+        public final boolean equals(Object o) {
+            if (o == this) {
+                return true;
+            }
+            if (!(o instanceof Map.Entry)) {
+                return false;
+            }
+            Map.Entry<?, ?> e = (Map.Entry<?, ?>) o;
+            return Objects.equals(key, e.getKey()) && Objects.equals(value, e.getValue());
+        }
+
+        public final int hashCode() {
+            return Objects.hashCode(key) ^ Objects.hashCode(value);
+        }
+
+        public final String toString() {
+            return key + "=" + value;
+        }
+    }
+
+    public HashMap() {
+        this(DEFAULT_CAPACITY);
+    }
+
+    public HashMap(int initialCapacity) {
+        if (initialCapacity < 0) {
+            throw new IllegalArgumentException("Illegal initial capacity: " + initialCapacity);
+        }
+        int capacity = 1;
+        while (capacity < initialCapacity) {
+            capacity <<= 1;
+        }
+        table = newTable(capacity);
+    }
+
+    public HashMap(Map<? extends K, ? extends V> m) {
+        this(DEFAULT_CAPACITY);
+        for (Map.Entry<? extends K, ? extends V> e : m.entrySet()) {
+            put(e.getKey(), e.getValue());
+        }
+    }
+
+    private static <K, V> Node<K, V>[] newTable(int capacity) {
+        return (Node<K, V>[]) new Node[capacity];
+    }
+
+    static int hash(Object key) {
+        int h;
+        return (key == null) ? 0 : (h = key.hashCode()) ^ (h >>> 16);
+    }
+
+    private Node<K, V> getNode(Object key) {
+        int hash = hash(key);
+        for (Node<K, V> e = table[hash & (table.length - 1)]; e != null; e = e.next) {
+            if (e.hash == hash && Objects.equals(key, e.key)) {
+                return e;
+            }
+        }
+        return null;
+    }
+
+    // moves the nodes to a table twice the size, each bucket splits in two keeping its order
+    private void resize() {
+        Node<K, V>[] oldTable = table;
+        Node<K, V>[] newTable = newTable(oldTable.length << 1);
+        for (int i = 0; i < oldTable.length; i++) {
+            Node<K, V> loHead = null, loTail = null, hiHead = null, hiTail = null;
+            for (Node<K, V> e = oldTable[i], next; e != null; e = next) {
+                next = e.next;
+                e.next = null;
+                if ((e.hash & oldTable.length) == 0) {
+                    if (loTail == null) {
+                        loHead = e;
+                    } else {
+                        loTail.next = e;
+                    }
+                    loTail = e;
+                } else {
+                    if (hiTail == null) {
+                        hiHead = e;
+                    } else {
+                        hiTail.next = e;
+                    }
+                    hiTail = e;
+                }
+            }
+            newTable[i] = loHead;
+            newTable[i + oldTable.length] = hiHead;
+        }
+        table = newTable;
+    }
+
+    public int size() {
+        return size;
+    }
+
+    public boolean isEmpty() {
+        return size == 0;
+    }
+
+    public V get(Object key) {
+        Node<K, V> e = getNode(key);
+        return e == null ? null : e.value;
+    }
+
+    public boolean containsKey(Object key) {
+        return getNode(key) != null;
+    }
+
+    public boolean containsValue(Object value) {
+        for (Node<K, V> bucket : table) {
+            for (Node<K, V> e = bucket; e != null; e = e.next) {
+                if (Objects.equals(value, e.value)) {
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    public V put(K key, V value) {
+        int hash = hash(key);
+        int index = hash & (table.length - 1);
+        Node<K, V> last = null;
+        for (Node<K, V> e = table[index]; e != null; e = e.next) {
+            if (e.hash == hash && Objects.equals(key, e.key)) {
+                return e.setValue(value);
+            }
+            last = e;
+        }
+        Node<K, V> node = new Node<>(hash, key, value);
+        if (last == null) {
+            table[index] = node;
+        } else {
+            last.next = node;
+        }
+        modCount++;
+        if (++size > table.length - (table.length >> 2)) {
+            resize();
+        }
+        return null;
+    }
+
+    public void putAll(Map<? extends K, ? extends V> m) {
+        for (Map.Entry<? extends K, ? extends V> e : m.entrySet()) {
+            put(e.getKey(), e.getValue());
+        }
+    }
+
+    public V remove(Object key) {
+        int hash = hash(key);
+        int index = hash & (table.length - 1);
+        Node<K, V> previous = null;
+        for (Node<K, V> e = table[index]; e != null; previous = e, e = e.next) {
+            if (e.hash == hash && Objects.equals(key, e.key)) {
+                if (previous == null) {
+                    table[index] = e.next;
+                } else {
+                    previous.next = e.next;
+                }
+                modCount++;
+                size--;
+                return e.value;
+            }
+        }
+        return null;
+    }
+
+    public void clear() {
+        modCount++;
+        for (int i = 0; i < table.length; i++) {
+            table[i] = null;
+        }
+        size = 0;
+    }
+
+    public Set<K> keySet() {
+        return new KeySet();
+    }
+
+    public Collection<V> values() {
+        return new Values();
+    }
+
+    public Set<Map.Entry<K, V>> entrySet() {
+        return new EntrySet();
+    }
+
+    public Object clone() {
+        return new HashMap<>(this);
+    }
+
+    public boolean equals(Object o) {
+        if (o == this) {
+            return true;
+        }
+        if (!(o instanceof Map)) {
+            return false;
+        }
+        Map<?, ?> m = (Map<?, ?>) o;
+        if (m.size() != size) {
+            return false;
+        }
+        for (Map.Entry<K, V> e : entrySet()) {
+            Object value = m.get(e.getKey());
+            if (!Objects.equals(e.getValue(), value)
+                    || (value == null && !m.containsKey(e.getKey()))) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int hashCode() {
+        int h = 0;
+        for (Map.Entry<K, V> e : entrySet()) {
+            h += e.hashCode();
+        }
+        return h;
+    }
+
+    public String toString() {
+        StringBuilder sb = new StringBuilder();
+        sb.append('{');
+        Iterator<Map.Entry<K, V>> it = entrySet().iterator();
+        while (it.hasNext()) {
+            Map.Entry<K, V> e = it.next();
+            sb.append(e.getKey() == this ? "(this Map)" : e.getKey());
+            sb.append('=');
+            sb.append(e.getValue() == this ? "(this Map)" : e.getValue());
+            if (it.hasNext()) {
+                sb.append(',').append(' ');
+            }
+        }
+        return sb.append('}').toString();
+    }
+
+    // goes through the buckets in order, the views wrap it
+    private class HashIterator {
+        private Node<K, V> next;
+        private Node<K, V> current;
+        private int index;
+        private int expectedModCount = modCount;
+
+        HashIterator() {
+            advance();
+        }
+
+        // the loop does not start the method, the interpreter cannot branch back to
+        // instruction 0
+        private void advance() {
+            Node<K, V>[] buckets = table;
+            while (next == null && index < buckets.length) {
+                next = buckets[index++];
+            }
+        }
+
+        public final boolean hasNext() {
+            return next != null;
+        }
+
+        final Node<K, V> nextNode() {
+            if (modCount != expectedModCount) {
+                throw new ConcurrentModificationException();
+            }
+            Node<K, V> e = next;
+            if (e == null) {
+                throw new NoSuchElementException();
+            }
+            current = e;
+            next = e.next;
+            advance();
+            return e;
+        }
+
+        public final void remove() {
+            if (current == null) {
+                throw new IllegalStateException();
+            }
+            if (modCount != expectedModCount) {
+                throw new ConcurrentModificationException();
+            }
+            HashMap.this.remove(current.key);
+            current = null;
+            expectedModCount = modCount;
+        }
+    }
+
+    private final class KeyIterator extends HashIterator implements Iterator<K> {
+        public K next() {
+            return nextNode().key;
+        }
+    }
+
+    private final class ValueIterator extends HashIterator implements Iterator<V> {
+        public V next() {
+            return nextNode().value;
+        }
+    }
+
+    private final class EntryIterator extends HashIterator implements Iterator<Map.Entry<K, V>> {
+        public Map.Entry<K, V> next() {
+            return nextNode();
+        }
+    }
+
+    private final class KeySet extends AbstractCollection<K> implements Set<K> {
+        public int size() {
+            return size;
+        }
+
+        public Iterator<K> iterator() {
+            return new KeyIterator();
+        }
+
+        public boolean contains(Object o) {
+            return containsKey(o);
+        }
+
+        public boolean remove(Object key) {
+            if (!containsKey(key)) {
+                return false;
+            }
+            HashMap.this.remove(key);
+            return true;
+        }
+
+        public void clear() {
+            HashMap.this.clear();
+        }
+    }
+
+    private final class Values extends AbstractCollection<V> {
+        public int size() {
+            return size;
+        }
+
+        public Iterator<V> iterator() {
+            return new ValueIterator();
+        }
+
+        public boolean contains(Object o) {
+            return containsValue(o);
+        }
+
+        public void clear() {
+            HashMap.this.clear();
+        }
+    }
+
+    private final class EntrySet extends AbstractCollection<Map.Entry<K, V>>
+            implements Set<Map.Entry<K, V>> {
+        public int size() {
+            return size;
+        }
+
+        public Iterator<Map.Entry<K, V>> iterator() {
+            return new EntryIterator();
+        }
+
+        public boolean contains(Object o) {
+            if (!(o instanceof Map.Entry)) {
+                return false;
+            }
+            Map.Entry<?, ?> e = (Map.Entry<?, ?>) o;
+            Node<K, V> candidate = getNode(e.getKey());
+            return candidate != null && candidate.equals(e);
+        }
+
+        public void clear() {
+            HashMap.this.clear();
+        }
+    }
+}
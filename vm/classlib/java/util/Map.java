@@ -0,0 +1,46 @@
+package java.util;
+
+public interface Map<K, V> {
+    int size();
+
+    boolean isEmpty();
+
+    boolean containsKey(Object key);
+
+    boolean containsValue(Object value);
+
+    V get(Object key);
+
+    V put(K key, V value);
+
+    V remove(Object key);
+
+    void clear();
+
+    Set<K> keySet();
+
+    Collection<V> values();
+
+    Set<Map.Entry<K, V>> entrySet();
+
+    default V getOrDefault(Object key, V defaultValue) {
+        V value = get(key);
+        return (value != null || containsKey(key)) ? value : defaultValue;
+    }
+
+    default V putIfAbsent(K key, V value) {
+        V current = get(key);
+        if (current == null) {
+            current = put(key, value);
+        }
+        return current;
+    }
+
+    interface Entry<K, V> {
+        K getKey();
+
+        V getValue();
+
+        V setValue(V value);
+    }
+}
@@ -0,0 +1,7 @@
+// The class library bundled with the VM, a small stand-in for java.base for machines without a
+// JDK. vm/build.rs compiles it and the VM boots from it with --bundled-runtime.
+module java.base {
+    exports java.io;
+    exports java.lang;
+    exports java.util;
+}
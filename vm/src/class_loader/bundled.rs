@@ -0,0 +1,28 @@
+// The class library in vm/classlib that build.rs compiles into the VM, a boot class path for
+// machines without a JDK. It covers java.lang basics, the standard streams, ArrayList and
+// HashMap, see the README for what it leaves out.
+use super::class_loading_error::ClassLoadingError;
+use super::classpath_entry::MemoryEntry;
+
+include!(concat!(env!("OUT_DIR"), "/bundled_classes.rs"));
+
+// an error when the VM was built without a javac that compiles the library
+pub fn require_bundled() -> Result<(), ClassLoadingError> {
+    if BUNDLED_CLASSES.is_empty() {
+        return Err(ClassLoadingError::NoJavaRuntime(
+            "the VM was built without its bundled class library, which needs javac 17 or later"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// the library as a class path entry
+pub fn bundled_entry() -> Result<MemoryEntry, ClassLoadingError> {
+    require_bundled()?;
+    let classes = BUNDLED_CLASSES
+        .iter()
+        .map(|(name, bytes)| (name.to_string(), bytes.to_vec()))
+        .collect();
+    Ok(MemoryEntry { classes })
+}
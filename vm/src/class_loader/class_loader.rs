@@ -1,12 +1,20 @@
+use super::bundled::bundled_entry;
 use super::class_loading_error::ClassLoadingError;
 use super::classpath_entry::*;
-use super::loaded_class::LoadedClass;
+use super::jimage::JImageEntry;
+use super::loaded_class::{InitState, LoadedClass, NameDes};
 use crate::vm::VM;
-use parser::access_flag::ClassFlags;
+use parser::access_flag::{ClassFlags, MethodFlags};
+use parser::class_file::ClassFile;
 use parser::class_file_reader::ClassFileReader;
+use parser::constant_pool::ConstantPool;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+pub const PRIMITIVE_TYPES: [&str; 9] = [
+    "boolean", "byte", "char", "short", "int", "long", "float", "double", "void",
+];
+
 pub struct ClassLoader {
     loaded_classes: Arc<Mutex<HashMap<String, Arc<LoadedClass>>>>,
     class_path_entries: Vec<Box<dyn ClassPathEntry>>,
@@ -32,6 +40,40 @@ impl ClassLoader {
         Ok(())
     }
 
+    pub fn add_jimage_entry(&mut self, path: String) -> Result<(), ClassLoadingError> {
+        let jimage_entry = JImageEntry::new(path)?;
+        self.class_path_entries.push(Box::new(jimage_entry));
+        Ok(())
+    }
+
+    // class files by internal class name, pkg/Main
+    pub fn add_memory_entry(
+        &mut self,
+        classes: HashMap<String, Vec<u8>>,
+    ) -> Result<(), ClassLoadingError> {
+        self.class_path_entries
+            .push(Box::new(MemoryEntry { classes }));
+        Ok(())
+    }
+
+    // a closure asked for the class file of each internal class name the entries before it
+    // do not have
+    pub fn add_callback_entry(
+        &mut self,
+        provider: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) -> Result<(), ClassLoadingError> {
+        self.class_path_entries.push(Box::new(CallbackEntry {
+            provider: Box::new(provider),
+        }));
+        Ok(())
+    }
+
+    // the class library bundled with the VM
+    pub fn add_bundled_entry(&mut self) -> Result<(), ClassLoadingError> {
+        self.class_path_entries.push(Box::new(bundled_entry()?));
+        Ok(())
+    }
+
     pub async fn load_class(
         &self,
         class_name: &str,
@@ -40,12 +82,15 @@ impl ClassLoader {
         if let Some(loaded_class) = self.find_loaded_class(class_name) {
             return Ok(loaded_class);
         }
+        if class_name.starts_with('[') {
+            return self.load_array_class(class_name, vm).await;
+        }
+        if PRIMITIVE_TYPES.contains(&class_name) {
+            return Ok(self.load_primitive_class(class_name));
+        }
 
         let class_data = self.load_class_data(class_name).await?;
-
-        self.verify_class_format(&class_data)?;
-
-        let parsed_class = ClassFileReader::new(class_data).parse().unwrap();
+        let parsed_class = self.parse_class(class_name, class_data)?;
 
         let superclass = if class_name != "java/lang/Object" {
             match parsed_class.get_super_class_name() {
@@ -89,12 +134,24 @@ impl ClassLoader {
             }
         }
 
+        // methods the VM implements in Rust run as natives whatever their class file says
+        let mut methods = parsed_class.methods;
+        for method in methods.iter_mut() {
+            let name_des = NameDes {
+                name: method.get_name(&parsed_class.constant_pool).to_string(),
+                des: method.get_des(&parsed_class.constant_pool).to_string(),
+            };
+            if vm.intrinsics.replaces(class_name, &name_des) {
+                method.access_flags |= MethodFlags::ACC_NATIVE;
+            }
+        }
+
         let loaded_class = Arc::new(LoadedClass::new(
             class_name.to_string(),
             superclass,
             interfaces,
             parsed_class.fields,
-            parsed_class.methods,
+            methods,
             Arc::new(parsed_class.constant_pool),
             parsed_class.access_flags,
         ));
@@ -103,14 +160,97 @@ impl ClassLoader {
             .lock()
             .unwrap()
             .insert(class_name.to_string(), Arc::clone(&loaded_class));
-        let fut = Box::pin(LoadedClass::initialize(loaded_class.clone(), vm));
-        fut.await.unwrap();
         //println!("{class_name} loaded");
 
         Ok(loaded_class)
     }
 
-    fn find_loaded_class(&self, class_name: &str) -> Option<Arc<LoadedClass>> {
+    async fn load_array_class(
+        &self,
+        class_name: &str,
+        vm: &VM,
+    ) -> Result<Arc<LoadedClass>, ClassLoadingError> {
+        let component = &class_name[1..];
+        let component_name = if component.starts_with('[') {
+            Some(component)
+        } else {
+            component
+                .strip_prefix('L')
+                .and_then(|name| name.strip_suffix(';'))
+        };
+        let component_class = match component_name {
+            Some(component_name) => {
+                let fut = Box::pin(self.load_class(component_name, vm));
+                Some(fut.await?)
+            }
+            None if component.len() == 1 && "ZBCSIJFD".contains(component) => {
+                let fut = Box::pin(self.load_class(primitive_name(component), vm));
+                Some(fut.await?)
+            }
+            None => {
+                return Err(ClassLoadingError::NoClassDefFoundError(format!(
+                    "Invalid array class {}",
+                    class_name
+                )))
+            }
+        };
+
+        let object_class = Box::pin(self.load_class("java/lang/Object", vm)).await?;
+        let mut interfaces = Vec::new();
+        for interface_name in ["java/lang/Cloneable", "java/io/Serializable"] {
+            let fut = Box::pin(self.load_class(interface_name, vm));
+            interfaces.push(fut.await?);
+        }
+        let array_class = Arc::new(LoadedClass::new_array_class(
+            class_name.to_string(),
+            object_class,
+            interfaces,
+            component_class,
+        ));
+        let array_class = Arc::clone(
+            self.loaded_classes
+                .lock()
+                .unwrap()
+                .entry(class_name.to_string())
+                .or_insert(array_class),
+        );
+        let fut = Box::pin(LoadedClass::initialize(array_class.clone(), vm));
+        fut.await
+            .map_err(|e| ClassLoadingError::NoClassDefFoundError(format!("{:?}", e)))?;
+        Ok(array_class)
+    }
+
+    // the classes behind int.class & co, they only exist to back Class mirrors
+    fn load_primitive_class(&self, class_name: &str) -> Arc<LoadedClass> {
+        let primitive_class = LoadedClass::new(
+            class_name.to_string(),
+            None,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Arc::new(ConstantPool::default()),
+            ClassFlags::ACC_PUBLIC | ClassFlags::ACC_FINAL | ClassFlags::ACC_ABSTRACT,
+        );
+        *primitive_class.init_state.lock().unwrap() = InitState::Initialized;
+        Arc::clone(
+            self.loaded_classes
+                .lock()
+                .unwrap()
+                .entry(class_name.to_string())
+                .or_insert(Arc::new(primitive_class)),
+        )
+    }
+
+    pub fn loaded_classes(&self) -> Vec<Arc<LoadedClass>> {
+        self.loaded_classes
+            .lock()
+            .unwrap()
+            .values()
+            .map(Arc::clone)
+            .collect()
+    }
+
+    pub fn find_loaded_class(&self, class_name: &str) -> Option<Arc<LoadedClass>> {
         self.loaded_classes
             .lock()
             .unwrap()
@@ -131,6 +271,34 @@ impl ClassLoader {
         )))
     }
 
+    // the class file an entry returned for class_name, which has to declare that class
+    fn parse_class(
+        &self,
+        class_name: &str,
+        class_data: Vec<u8>,
+    ) -> Result<ClassFile, ClassLoadingError> {
+        self.verify_class_format(&class_data)?;
+        let parsed_class = ClassFileReader::new(class_data).parse().map_err(|e| {
+            ClassLoadingError::ClassFormatError(format!("{}: {}", class_name, e))
+        })?;
+        let this_class = parsed_class
+            .constant_pool
+            .get_underlying_string_from_constant_class_info_index(parsed_class.this_class)
+            .ok_or_else(|| {
+                ClassLoadingError::ClassFormatError(format!(
+                    "{}: this_class is not a class constant",
+                    class_name
+                ))
+            })?;
+        if this_class != class_name {
+            return Err(ClassLoadingError::NoClassDefFoundError(format!(
+                "{} (wrong name: {})",
+                class_name, this_class
+            )));
+        }
+        Ok(parsed_class)
+    }
+
     fn verify_class_format(&self, class_data: &[u8]) -> Result<(), ClassLoadingError> {
         if class_data.len() < 4 || &class_data[0..4] != &[0xCA, 0xFE, 0xBA, 0xBE] {
             return Err(ClassLoadingError::ClassFormatError(
@@ -147,3 +315,116 @@ impl ClassLoader {
         Ok(())
     }
 }
+
+// name of the primitive class for a base type descriptor
+pub fn primitive_name(descriptor: &str) -> &'static str {
+    match descriptor {
+        "Z" => "boolean",
+        "B" => "byte",
+        "C" => "char",
+        "S" => "short",
+        "I" => "int",
+        "J" => "long",
+        "F" => "float",
+        "D" => "double",
+        _ => "void",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a class file declaring only its name, major_version tells copies of it apart
+    fn class_file(class_name: &str, major_version: u16) -> Vec<u8> {
+        let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0];
+        bytes.extend(major_version.to_be_bytes());
+        // constant pool: #1 Utf8 class_name, #2 Class #1
+        bytes.extend([0, 3, 1]);
+        bytes.extend((class_name.len() as u16).to_be_bytes());
+        bytes.extend(class_name.as_bytes());
+        bytes.extend([7, 0, 1]);
+        // public super, this_class #2, no superclass, interfaces, fields, methods or attributes
+        bytes.extend([0, 0x21, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        bytes
+    }
+
+    fn memory_entry(classes: &[(&str, Vec<u8>)]) -> HashMap<String, Vec<u8>> {
+        classes
+            .iter()
+            .map(|(name, bytes)| (name.to_string(), bytes.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn earlier_entries_shadow_later_ones() {
+        let mut loader = ClassLoader::new();
+        loader
+            .add_memory_entry(memory_entry(&[("pkg/A", class_file("pkg/A", 61))]))
+            .unwrap();
+        loader
+            .add_callback_entry(|name| Some(class_file(name, 60)))
+            .unwrap();
+        assert_eq!(
+            loader.load_class_data("pkg/A").await.unwrap(),
+            class_file("pkg/A", 61)
+        );
+
+        let mut loader = ClassLoader::new();
+        loader
+            .add_callback_entry(|name| Some(class_file(name, 60)))
+            .unwrap();
+        loader
+            .add_memory_entry(memory_entry(&[("pkg/A", class_file("pkg/A", 61))]))
+            .unwrap();
+        assert_eq!(
+            loader.load_class_data("pkg/A").await.unwrap(),
+            class_file("pkg/A", 60)
+        );
+    }
+
+    #[tokio::test]
+    async fn classes_an_entry_lacks_come_from_the_next() {
+        let mut loader = ClassLoader::new();
+        loader
+            .add_memory_entry(memory_entry(&[("A", class_file("A", 61))]))
+            .unwrap();
+        loader
+            .add_callback_entry(|name| (name == "B").then(|| class_file(name, 60)))
+            .unwrap();
+        loader
+            .add_memory_entry(memory_entry(&[("C", class_file("C", 59))]))
+            .unwrap();
+        assert_eq!(loader.load_class_data("B").await.unwrap(), class_file("B", 60));
+        assert_eq!(loader.load_class_data("C").await.unwrap(), class_file("C", 59));
+        assert!(matches!(
+            loader.load_class_data("D").await,
+            Err(ClassLoadingError::ClassNotFoundException(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_class_files_are_format_errors() {
+        let loader = ClassLoader::new();
+        let mut truncated = class_file("A", 61);
+        truncated.truncate(12);
+        assert!(matches!(
+            loader.parse_class("A", truncated),
+            Err(ClassLoadingError::ClassFormatError(_))
+        ));
+        assert!(matches!(
+            loader.parse_class("A", b"not a class".to_vec()),
+            Err(ClassLoadingError::ClassFormatError(_))
+        ));
+    }
+
+    #[test]
+    fn class_files_must_declare_the_requested_class() {
+        let loader = ClassLoader::new();
+        assert!(loader.parse_class("pkg/A", class_file("pkg/A", 61)).is_ok());
+        assert!(matches!(
+            loader.parse_class("pkg/A", class_file("pkg/B", 61)),
+            Err(ClassLoadingError::NoClassDefFoundError(message)) if message.contains("wrong name")
+        ));
+    }
+}
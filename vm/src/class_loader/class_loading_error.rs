@@ -29,6 +29,7 @@ pub enum ClassLoadingError {
     ParseError(String),
     InvalidJarFile(String),
     NetworkError(String),
+    NoJavaRuntime(String),
 
     Other(String),
 }
@@ -75,6 +76,9 @@ impl fmt::Display for ClassLoadingError {
             ClassLoadingError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             ClassLoadingError::InvalidJarFile(msg) => write!(f, "Invalid JAR file: {}", msg),
             ClassLoadingError::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            ClassLoadingError::NoJavaRuntime(msg) => {
+                write!(f, "No Java runtime to boot from: {}", msg)
+            }
 
             ClassLoadingError::Other(msg) => write!(f, "Other error: {}", msg),
         }
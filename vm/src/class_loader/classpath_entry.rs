@@ -1,5 +1,6 @@
 use super::class_loading_error::ClassLoadingError;
 use async_trait::async_trait;
+use std::collections::HashMap;
 use std::fs;
 use std::io::Read;
 use std::path::Path;
@@ -26,21 +27,27 @@ impl ClassPathEntry for DirectoryEntry {
     }
 }
 
-// JAR file class loading
+// JAR file class loading, jmod files are zips too
 pub struct JarEntry {
-    path: String,
     archive: Arc<Mutex<ZipArchive<fs::File>>>,
+    // where the class files sit in the archive, classes/ in a jmod
+    prefix: &'static str,
 }
 
+// the header in front of the zip of a jmod file
+const JMOD_MAGIC: [u8; 4] = [b'J', b'M', 1, 0];
+
 impl JarEntry {
     pub fn new(path: String) -> Result<Self, ClassLoadingError> {
-        let file = fs::File::open(&path).map_err(|e| ClassLoadingError::IoError(e))?;
+        let mut file = fs::File::open(&path).map_err(|e| ClassLoadingError::IoError(e))?;
+        let mut magic = [0; 4];
+        let is_jmod = file.read_exact(&mut magic).is_ok() && magic == JMOD_MAGIC;
         let archive =
             ZipArchive::new(file).map_err(|e| ClassLoadingError::InvalidJarFile(e.to_string()))?;
 
         Ok(JarEntry {
-            path,
             archive: Arc::new(Mutex::new(archive)),
+            prefix: if is_jmod { "classes/" } else { "" },
         })
     }
 }
@@ -48,11 +55,7 @@ impl JarEntry {
 #[async_trait]
 impl ClassPathEntry for JarEntry {
     async fn read_class(&self, class_name: &str) -> Result<Vec<u8>, ClassLoadingError> {
-        let class_file_path = if self.path.as_str() == base {
-            "classes/".to_string() + class_name + ".class"
-        } else {
-            class_name.to_string() + ".class"
-        };
+        let class_file_path = format!("{}{}.class", self.prefix, class_name);
         let mut archive = self.archive.lock().unwrap();
 
         let mut entry = archive
@@ -67,4 +70,34 @@ impl ClassPathEntry for JarEntry {
         Ok(buffer)
     }
 }
-const base: &str = "/usr/lib/jvm/java-24-openjdk/jmods/java.base.jmod";
+
+// Class files held in memory by internal class name
+pub struct MemoryEntry {
+    pub classes: HashMap<String, Vec<u8>>,
+}
+
+#[async_trait]
+impl ClassPathEntry for MemoryEntry {
+    async fn read_class(&self, class_name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        self.classes
+            .get(class_name)
+            .cloned()
+            .ok_or_else(|| ClassLoadingError::ClassNotFoundException(class_name.to_string()))
+    }
+}
+
+// Returns the class file for an internal class name, None when the host has no such class
+pub type ClassProvider = Box<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+// Class files the host hands out on request, for class files generated on the fly
+pub struct CallbackEntry {
+    pub provider: ClassProvider,
+}
+
+#[async_trait]
+impl ClassPathEntry for CallbackEntry {
+    async fn read_class(&self, class_name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        (self.provider)(class_name)
+            .ok_or_else(|| ClassLoadingError::ClassNotFoundException(class_name.to_string()))
+    }
+}
@@ -0,0 +1,118 @@
+// The Java runtime the VM boots from. Its lib/modules jimage, or the jmods/java.base.jmod of a
+// JDK without one, is the boot class path and its release file tells which JDK the intrinsics
+// are dealing with.
+use super::class_loading_error::ClassLoadingError;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the oldest JDK whose java.base the intrinsics know how to start
+pub const MIN_FEATURE_VERSION: u32 = 17;
+
+#[derive(Debug, Clone)]
+pub struct JavaHome {
+    pub path: PathBuf,
+    // the file java.base comes from, lib/modules or jmods/java.base.jmod
+    pub java_base: PathBuf,
+    // 24 for JAVA_VERSION="24.0.1"
+    pub feature_version: u32,
+}
+
+impl JavaHome {
+    // the runtime installed at path, or why the VM cannot boot from it
+    pub fn at(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        if !path.is_dir() {
+            return Err("not a directory".to_string());
+        }
+        let java_base = [path.join("lib").join("modules"), path.join("jmods").join("java.base.jmod")]
+            .into_iter()
+            .find(|java_base| java_base.is_file())
+            .ok_or_else(|| "no lib/modules or jmods/java.base.jmod, it is not a JDK".to_string())?;
+        let release = fs::read_to_string(path.join("release"))
+            .map_err(|_| "no release file to tell its version".to_string())?;
+        let feature_version = release
+            .lines()
+            .find_map(|line| {
+                let version = line.strip_prefix("JAVA_VERSION=")?.trim_matches('"');
+                version.split(['.', '_', '-', '+']).next()?.parse::<u32>().ok()
+            })
+            .ok_or_else(|| "no JAVA_VERSION in its release file".to_string())?;
+        if feature_version < MIN_FEATURE_VERSION {
+            return Err(format!(
+                "JDK {} is older than {}",
+                feature_version, MIN_FEATURE_VERSION
+            ));
+        }
+        Ok(JavaHome {
+            path,
+            java_base,
+            feature_version,
+        })
+    }
+
+    // the runtime at explicit when one is given, then the one JAVA_HOME names, otherwise the
+    // first usable one of the java on the PATH and the newest JDK in the usual install
+    // locations
+    pub fn find(explicit: Option<&Path>) -> Result<Self, ClassLoadingError> {
+        let java_home = env::var_os("JAVA_HOME").filter(|home| !home.is_empty());
+        if let Some(path) = explicit.or(java_home.as_deref().map(Path::new)) {
+            return JavaHome::at(path).map_err(|reason| {
+                ClassLoadingError::NoJavaRuntime(format!("{}: {}", path.display(), reason))
+            });
+        }
+        let mut tried = Vec::new();
+        for candidate in candidates() {
+            match JavaHome::at(&candidate) {
+                Ok(java_home) => return Ok(java_home),
+                Err(reason) => tried.push(format!("{}: {}", candidate.display(), reason)),
+            }
+        }
+        let mut message = format!(
+            "set JAVA_HOME to a JDK or JRE {} or later",
+            MIN_FEATURE_VERSION
+        );
+        for attempt in tried {
+            message.push_str("\n  tried ");
+            message.push_str(&attempt);
+        }
+        Err(ClassLoadingError::NoJavaRuntime(message))
+    }
+}
+
+// where to look for a runtime, in order
+fn candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    // bin/java of the runtime, through whatever links the package manager put in between
+    if let Some(path) = env::var_os("PATH") {
+        let java = env::split_paths(&path)
+            .map(|dir| dir.join("java"))
+            .find(|java| java.is_file())
+            .and_then(|java| fs::canonicalize(java).ok());
+        if let Some(home) = java.as_deref().and_then(Path::parent).and_then(Path::parent) {
+            candidates.push(home.to_path_buf());
+        }
+    }
+    let mut installed = Vec::new();
+    for (dir, home) in [
+        ("/usr/lib/jvm", None),
+        ("/usr/java", None),
+        ("/opt/java", None),
+        ("/Library/Java/JavaVirtualMachines", Some("Contents/Home")),
+    ] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = match home {
+                Some(home) => entry.path().join(home),
+                None => entry.path(),
+            };
+            let version = JavaHome::at(&path).map_or(0, |java_home| java_home.feature_version);
+            installed.push((version, path));
+        }
+    }
+    installed.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    candidates.extend(installed.into_iter().map(|(_, path)| path));
+    candidates
+}
@@ -0,0 +1,743 @@
+// Class loading from a jimage, the lib/modules file of a JDK or JRE. The file starts with an
+// index, read into memory when the entry is made:
+//
+//     header       magic, version, flags, resource count, table length, locations size and
+//                  strings size, each a u4
+//     redirect     table length i4, a perfect hash of resource names into the table
+//     offsets      table length u4, where each location starts in the locations
+//     locations    attribute streams naming a resource and where its bytes are
+//     strings      NUL terminated UTF-8
+//
+// The resources follow the index and are read from the file when a class is asked for. All
+// numbers are in the byte order of the platform that wrote the image, the magic tells which.
+use super::class_loading_error::ClassLoadingError;
+use super::classpath_entry::ClassPathEntry;
+use async_trait::async_trait;
+use flate2::read::ZlibDecoder;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+
+const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+const MAJOR_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+// location attribute kinds, END closes the stream
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+// the header jlink puts in front of each compressed resource: magic u4, compressed size u8,
+// uncompressed size u8, decompressor name u4, decompressor config u4 and is terminal u1
+const COMPRESSED_MAGIC: u32 = 0xCAFE_FAFA;
+const COMPRESSED_HEADER_SIZE: usize = 29;
+
+// constant pool tags of the class files the compact-cp plugin rewrote
+const CONSTANT_UTF8: u8 = 1;
+const CONSTANT_LONG: u8 = 5;
+const CONSTANT_DOUBLE: u8 = 6;
+const EXTERNALIZED_STRING: u8 = 23;
+const EXTERNALIZED_STRING_DESCRIPTOR: u8 = 25;
+
+pub struct JImageEntry {
+    file: Mutex<fs::File>,
+    big_endian: bool,
+    index: Vec<u8>,
+    table_length: usize,
+    redirect_start: usize,
+    offsets_start: usize,
+    locations_start: usize,
+    strings_start: usize,
+    // the module of each package looked up so far
+    modules: Mutex<HashMap<String, Option<String>>>,
+}
+
+// true if the file at path is a jimage
+pub fn is_jimage(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && (u32::from_le_bytes(magic) == IMAGE_MAGIC || u32::from_be_bytes(magic) == IMAGE_MAGIC)
+}
+
+fn invalid(path: &str, reason: &str) -> ClassLoadingError {
+    ClassLoadingError::ParseError(format!("{} is not a usable jimage: {}", path, reason))
+}
+
+impl JImageEntry {
+    pub fn new(path: String) -> Result<Self, ClassLoadingError> {
+        let mut file = fs::File::open(&path).map_err(ClassLoadingError::IoError)?;
+        let mut header = [0; HEADER_SIZE];
+        file.read_exact(&mut header)
+            .map_err(|_| invalid(&path, "too short"))?;
+        let magic = [header[0], header[1], header[2], header[3]];
+        let big_endian = if u32::from_le_bytes(magic) == IMAGE_MAGIC {
+            false
+        } else if u32::from_be_bytes(magic) == IMAGE_MAGIC {
+            true
+        } else {
+            return Err(invalid(&path, "no jimage magic"));
+        };
+        let field = |i: usize| read_u4(&header, i * 4, big_endian) as usize;
+        // the major version is in the high half of the version field
+        if field(1) >> 16 != MAJOR_VERSION as usize {
+            return Err(invalid(&path, "unsupported version"));
+        }
+        let table_length = field(4);
+        let redirect_start = HEADER_SIZE;
+        let offsets_start = redirect_start + table_length * 4;
+        let locations_start = offsets_start + table_length * 4;
+        let strings_start = locations_start + field(5);
+        let index_size = strings_start + field(6);
+
+        let mut index = header.to_vec();
+        index.resize(index_size, 0);
+        file.read_exact(&mut index[HEADER_SIZE..])
+            .map_err(|_| invalid(&path, "truncated index"))?;
+        Ok(JImageEntry {
+            file: Mutex::new(file),
+            big_endian,
+            index,
+            table_length,
+            redirect_start,
+            offsets_start,
+            locations_start,
+            strings_start,
+            modules: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // the NUL terminated string at offset of the strings table
+    fn string(&self, offset: u64) -> &[u8] {
+        let start = (self.strings_start + offset as usize).min(self.index.len());
+        let bytes = &self.index[start..];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        &bytes[..end]
+    }
+
+    // the attributes of the location at offset of the locations table, by kind. Each starts
+    // with a byte holding the kind in its top five bits and the length of the big endian
+    // value that follows, less one, in the low three.
+    fn location(&self, offset: usize) -> [u64; ATTRIBUTE_COUNT] {
+        let mut attributes = [0; ATTRIBUTE_COUNT];
+        let mut i = self.locations_start + offset;
+        while i < self.strings_start {
+            let byte = self.index[i];
+            let kind = (byte >> 3) as usize;
+            if kind == ATTRIBUTE_END || kind >= ATTRIBUTE_COUNT {
+                break;
+            }
+            let length = (byte & 7) as usize + 1;
+            let value = self.index[i + 1..(i + 1 + length).min(self.strings_start)]
+                .iter()
+                .fold(0u64, |value, &b| (value << 8) | b as u64);
+            attributes[kind] = value;
+            i += 1 + length;
+        }
+        attributes
+    }
+
+    // /module/parent/base.extension, leaving out the parts a location does not have
+    fn location_name(&self, attributes: &[u64; ATTRIBUTE_COUNT]) -> Vec<u8> {
+        let mut name = Vec::new();
+        let module = self.string(attributes[ATTRIBUTE_MODULE]);
+        if !module.is_empty() {
+            name.push(b'/');
+            name.extend_from_slice(module);
+            name.push(b'/');
+        }
+        let parent = self.string(attributes[ATTRIBUTE_PARENT]);
+        if !parent.is_empty() {
+            name.extend_from_slice(parent);
+            name.push(b'/');
+        }
+        name.extend_from_slice(self.string(attributes[ATTRIBUTE_BASE]));
+        let extension = self.string(attributes[ATTRIBUTE_EXTENSION]);
+        if !extension.is_empty() {
+            name.push(b'.');
+            name.extend_from_slice(extension);
+        }
+        name
+    }
+
+    // the location of the resource called name. The redirect slot of its hash is 0 when no
+    // resource hashes there, negative when one does and holds the index, and otherwise the
+    // seed of a second hash that tells the colliding names apart.
+    fn find(&self, name: &str) -> Option<[u64; ATTRIBUTE_COUNT]> {
+        if self.table_length == 0 {
+            return None;
+        }
+        let slot = hash_code(name, HASH_MULTIPLIER) as usize % self.table_length;
+        let redirect = read_u4(&self.index, self.redirect_start + slot * 4, self.big_endian) as i32;
+        let index = match redirect {
+            0 => return None,
+            redirect if redirect < 0 => (-1 - redirect) as usize,
+            seed => hash_code(name, seed as u32) as usize % self.table_length,
+        };
+        if index >= self.table_length {
+            return None;
+        }
+        let offset = read_u4(&self.index, self.offsets_start + index * 4, self.big_endian);
+        let attributes = self.location(offset as usize);
+        // a name that is not in the image still hashes to some location
+        (self.location_name(&attributes) == name.as_bytes()).then_some(attributes)
+    }
+
+    fn resource(&self, attributes: &[u64; ATTRIBUTE_COUNT]) -> Result<Vec<u8>, ClassLoadingError> {
+        let compressed = attributes[ATTRIBUTE_COMPRESSED] as usize;
+        let size = if compressed != 0 {
+            compressed
+        } else {
+            attributes[ATTRIBUTE_UNCOMPRESSED] as usize
+        };
+        let mut content = vec![0; size];
+        {
+            let mut file = self.file.lock().unwrap();
+            let start = self.index.len() as u64 + attributes[ATTRIBUTE_OFFSET];
+            file.seek(SeekFrom::Start(start))
+                .and_then(|_| file.read_exact(&mut content))
+                .map_err(ClassLoadingError::IoError)?;
+        }
+        // jlink may have compressed a resource more than once, each pass adds a header
+        while content.len() >= COMPRESSED_HEADER_SIZE
+            && read_u4(&content, 0, self.big_endian) == COMPRESSED_MAGIC
+        {
+            let name_offset = read_u4(&content, 20, self.big_endian);
+            let body = &content[COMPRESSED_HEADER_SIZE..];
+            content = match self.string(name_offset as u64) {
+                b"zip" => {
+                    let mut inflated = Vec::new();
+                    ZlibDecoder::new(body)
+                        .read_to_end(&mut inflated)
+                        .map_err(ClassLoadingError::IoError)?;
+                    inflated
+                }
+                b"compact-cp" => self.expand_shared_strings(body)?,
+                other => {
+                    return Err(ClassLoadingError::ParseError(format!(
+                        "unsupported jimage decompressor {}",
+                        String::from_utf8_lossy(other)
+                    )))
+                }
+            };
+        }
+        Ok(content)
+    }
+
+    // undoes the compact-cp plugin, which moved the Utf8 constants of a class file into the
+    // strings table and split descriptors into package and class names stored there
+    fn expand_shared_strings(&self, class: &[u8]) -> Result<Vec<u8>, ClassLoadingError> {
+        let truncated = || ClassLoadingError::ParseError("truncated compact-cp class".to_string());
+        let mut input = ByteReader { bytes: class, at: 0 };
+        let mut out = Vec::with_capacity(class.len() * 2);
+        // magic, minor and major version
+        out.extend_from_slice(input.take(8).ok_or_else(truncated)?);
+        let count = input.take(2).ok_or_else(truncated)?;
+        out.extend_from_slice(count);
+        let count = u16::from_be_bytes([count[0], count[1]]);
+        let mut i = 1;
+        while i < count {
+            let tag = input.take(1).ok_or_else(truncated)?[0];
+            match tag {
+                CONSTANT_UTF8 => {
+                    let length = input.take(2).ok_or_else(truncated)?;
+                    let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+                    let utf8 = input.take(length).ok_or_else(truncated)?;
+                    push_utf8(&mut out, utf8)?;
+                }
+                EXTERNALIZED_STRING => {
+                    let index = input.compressed_int().ok_or_else(truncated)?;
+                    push_utf8(&mut out, self.string(index as u64))?;
+                }
+                EXTERNALIZED_STRING_DESCRIPTOR => {
+                    let descriptor = self.shared_descriptor(&mut input).ok_or_else(truncated)?;
+                    push_utf8(&mut out, &descriptor)?;
+                }
+                tag => {
+                    let size = constant_size(tag).ok_or_else(|| {
+                        ClassLoadingError::ParseError(format!("constant pool tag {}", tag))
+                    })?;
+                    out.push(tag);
+                    out.extend_from_slice(input.take(size).ok_or_else(truncated)?);
+                    // the two slot constants
+                    if tag == CONSTANT_LONG || tag == CONSTANT_DOUBLE {
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+        }
+        out.extend_from_slice(&class[input.at..]);
+        Ok(out)
+    }
+
+    // a descriptor whose L types were replaced by a package and a class name index each
+    fn shared_descriptor(&self, input: &mut ByteReader) -> Option<Vec<u8>> {
+        let skeleton = self.string(input.compressed_int()? as u64);
+        let length = input.compressed_int()? as usize;
+        let mut indexes = ByteReader {
+            bytes: input.take(length)?,
+            at: 0,
+        };
+        let mut descriptor = Vec::new();
+        for &c in skeleton {
+            descriptor.push(c);
+            if c == b'L' {
+                let package = self.string(indexes.compressed_int()? as u64);
+                if !package.is_empty() {
+                    descriptor.extend_from_slice(package);
+                    descriptor.push(b'/');
+                }
+                descriptor.extend_from_slice(self.string(indexes.compressed_int()? as u64));
+            }
+        }
+        Some(descriptor)
+    }
+
+    // the module holding package, from the /packages/<dotted package> resource which lists
+    // the modules that have the package as pairs of an is empty flag and a module name
+    fn module_of(&self, package: &str) -> Result<Option<String>, ClassLoadingError> {
+        if let Some(module) = self.modules.lock().unwrap().get(package) {
+            return Ok(module.clone());
+        }
+        let mut module = None;
+        if let Some(location) = self.find(&format!("/packages/{}", package.replace('/', "."))) {
+            let content = self.resource(&location)?;
+            let pairs = content
+                .chunks_exact(8)
+                .map(|pair| (read_u4(pair, 0, self.big_endian), read_u4(pair, 4, self.big_endian)))
+                .collect::<Vec<_>>();
+            module = pairs
+                .iter()
+                .find(|(is_empty, _)| *is_empty == 0)
+                .or(pairs.first())
+                .map(|(_, name)| String::from_utf8_lossy(self.string(*name as u64)).into_owned());
+        }
+        self.modules
+            .lock()
+            .unwrap()
+            .insert(package.to_string(), module.clone());
+        Ok(module)
+    }
+}
+
+#[async_trait]
+impl ClassPathEntry for JImageEntry {
+    async fn read_class(&self, class_name: &str) -> Result<Vec<u8>, ClassLoadingError> {
+        let not_found = || ClassLoadingError::ClassNotFoundException(class_name.to_string());
+        let (package, _) = class_name.rsplit_once('/').ok_or_else(not_found)?;
+        let module = self.module_of(package)?.ok_or_else(not_found)?;
+        let location = self
+            .find(&format!("/{}/{}.class", module, class_name))
+            .ok_or_else(not_found)?;
+        self.resource(&location)
+    }
+}
+
+fn read_u4(bytes: &[u8], at: usize, big_endian: bool) -> u32 {
+    let value = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+    if big_endian {
+        u32::from_be_bytes(value)
+    } else {
+        u32::from_le_bytes(value)
+    }
+}
+
+// the hash the image writer placed names with, FNV-1a style over the UTF-8 bytes
+fn hash_code(name: &str, seed: u32) -> u32 {
+    name.bytes()
+        .fold(seed, |hash, b| hash.wrapping_mul(HASH_MULTIPLIER) ^ b as u32)
+        & 0x7FFF_FFFF
+}
+
+// the bytes after the tag of a constant pool entry
+fn constant_size(tag: u8) -> Option<usize> {
+    match tag {
+        // Class, String, MethodType, Module, Package
+        7 | 8 | 16 | 19 | 20 => Some(2),
+        // MethodHandle
+        15 => Some(3),
+        // Integer, Float, Fieldref, Methodref, InterfaceMethodref, NameAndType, Dynamic,
+        // InvokeDynamic
+        3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => Some(4),
+        // Long, Double
+        5 | 6 => Some(8),
+        _ => None,
+    }
+}
+
+fn push_utf8(out: &mut Vec<u8>, utf8: &[u8]) -> Result<(), ClassLoadingError> {
+    let length = u16::try_from(utf8.len())
+        .map_err(|_| ClassLoadingError::ParseError("Utf8 constant too long".to_string()))?;
+    out.push(CONSTANT_UTF8);
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(utf8);
+    Ok(())
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.at..self.at + length)?;
+        self.at += length;
+        Some(bytes)
+    }
+
+    // an int as CompressIndexes writes it: a first byte with the top bit set holds the length
+    // in its next two bits and the high bits of the value in the low five, the rest are big
+    // endian; otherwise it is a plain four byte int
+    fn compressed_int(&mut self) -> Option<u32> {
+        let first = self.take(1)?[0];
+        let (length, mut value) = if first & 0x80 != 0 {
+            (((first >> 5) & 3) as usize, (first & 0x1F) as u32)
+        } else {
+            (4, first as u32)
+        };
+        for &b in self.take(length.saturating_sub(1))? {
+            value = (value << 8) | b as u32;
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    // writes a jimage the way jlink lays one out, resources are added by full name
+    struct ImageBuilder {
+        big_endian: bool,
+        strings: Vec<u8>,
+        locations: Vec<u8>,
+        resources: Vec<u8>,
+        // name and location offset of each resource
+        entries: Vec<(String, usize)>,
+    }
+
+    impl ImageBuilder {
+        fn new(big_endian: bool) -> Self {
+            ImageBuilder {
+                big_endian,
+                // offset 0 is the empty string
+                strings: vec![0],
+                locations: Vec::new(),
+                resources: Vec::new(),
+                entries: Vec::new(),
+            }
+        }
+
+        fn u4(&self, value: u32) -> [u8; 4] {
+            if self.big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            }
+        }
+
+        fn string(&mut self, string: &str) -> u64 {
+            let offset = self.strings.len() as u64;
+            self.strings.extend_from_slice(string.as_bytes());
+            self.strings.push(0);
+            offset
+        }
+
+        // /module/parent/base.extension holding content, stored as is
+        fn add(&mut self, name: &str, content: &[u8]) {
+            self.add_stored(name, content, 0, content.len());
+        }
+
+        fn add_stored(&mut self, name: &str, stored: &[u8], compressed: usize, uncompressed: usize) {
+            let (module, path) = name[1..].split_once('/').unwrap();
+            let (parent, file) = path.rsplit_once('/').unwrap_or(("", path));
+            let (base, extension) = file.rsplit_once('.').unwrap_or((file, ""));
+            let attributes = [
+                (ATTRIBUTE_MODULE, self.string(module)),
+                (ATTRIBUTE_PARENT, self.string(parent)),
+                (ATTRIBUTE_BASE, self.string(base)),
+                (ATTRIBUTE_EXTENSION, self.string(extension)),
+                (ATTRIBUTE_OFFSET, self.resources.len() as u64),
+                (ATTRIBUTE_COMPRESSED, compressed as u64),
+                (ATTRIBUTE_UNCOMPRESSED, uncompressed as u64),
+            ];
+            self.entries.push((name.to_string(), self.locations.len()));
+            for (kind, value) in attributes {
+                push_attribute(&mut self.locations, kind, value);
+            }
+            self.locations.push(ATTRIBUTE_END as u8);
+            self.resources.extend_from_slice(stored);
+        }
+
+        // the header jlink puts in front of a resource compressed by decompressor
+        fn compressed_header(&mut self, decompressor: &str, body: &[u8], size: usize) -> Vec<u8> {
+            let name = self.string(decompressor) as u32;
+            let mut content = self.u4(COMPRESSED_MAGIC).to_vec();
+            let sizes = [body.len() as u64, size as u64];
+            for size in sizes {
+                content.extend(if self.big_endian {
+                    size.to_be_bytes()
+                } else {
+                    size.to_le_bytes()
+                });
+            }
+            content.extend(self.u4(name));
+            content.extend(self.u4(0));
+            content.push(1);
+            content.extend_from_slice(body);
+            content
+        }
+
+        // the redirect table of a perfect hash: names sharing a slot get a seed that places
+        // them apart, a name alone in its slot is stored there as -1 - its index
+        fn redirect(&self) -> (Vec<i32>, Vec<u32>) {
+            let length = self.entries.len();
+            let mut buckets = vec![Vec::new(); length];
+            for (name, offset) in &self.entries {
+                buckets[hash_code(name, HASH_MULTIPLIER) as usize % length].push((name, *offset));
+            }
+            let mut redirect = vec![0; length];
+            let mut offsets = vec![None; length];
+            let mut slots = (0..length).collect::<Vec<_>>();
+            slots.sort_by_key(|&slot| std::cmp::Reverse(buckets[slot].len()));
+            for slot in slots {
+                match buckets[slot].as_slice() {
+                    [] => {}
+                    [(_, offset)] => {
+                        let index = offsets.iter().position(Option::is_none).unwrap();
+                        offsets[index] = Some(*offset as u32);
+                        redirect[slot] = -1 - index as i32;
+                    }
+                    bucket => {
+                        let (seed, indexes) = (1..)
+                            .find_map(|seed| {
+                                let indexes = bucket
+                                    .iter()
+                                    .map(|(name, _)| hash_code(name, seed) as usize % length)
+                                    .collect::<Vec<_>>();
+                                let free = indexes.iter().enumerate().all(|(i, &index)| {
+                                    offsets[index].is_none() && !indexes[..i].contains(&index)
+                                });
+                                free.then_some((seed, indexes))
+                            })
+                            .unwrap();
+                        for ((_, offset), index) in bucket.iter().zip(indexes) {
+                            offsets[index] = Some(*offset as u32);
+                        }
+                        redirect[slot] = seed as i32;
+                    }
+                }
+            }
+            (redirect, offsets.into_iter().map(Option::unwrap).collect())
+        }
+
+        fn write(&self, file_name: &str) -> (String, Vec<i32>) {
+            let (redirect, offsets) = self.redirect();
+            let length = self.entries.len() as u32;
+            let mut image = Vec::new();
+            let header = [
+                IMAGE_MAGIC,
+                MAJOR_VERSION << 16,
+                0,
+                length,
+                length,
+                self.locations.len() as u32,
+                self.strings.len() as u32,
+            ];
+            for field in header {
+                image.extend(self.u4(field));
+            }
+            for value in &redirect {
+                image.extend(self.u4(*value as u32));
+            }
+            for offset in offsets {
+                image.extend(self.u4(offset));
+            }
+            image.extend_from_slice(&self.locations);
+            image.extend_from_slice(&self.strings);
+            image.extend_from_slice(&self.resources);
+            let path = std::env::temp_dir().join(format!(
+                "jimage-test-{}-{}",
+                std::process::id(),
+                file_name
+            ));
+            fs::write(&path, image).unwrap();
+            (path.to_string_lossy().into_owned(), redirect)
+        }
+    }
+
+    // the kind in the top five bits, the value's length less one in the low three
+    fn push_attribute(out: &mut Vec<u8>, kind: usize, value: u64) {
+        let bytes = value.to_be_bytes();
+        let skip = bytes.iter().take(7).take_while(|&&b| b == 0).count();
+        out.push(((kind as u8) << 3) | (7 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+
+    fn zlib(content: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn open(builder: &ImageBuilder, file_name: &str) -> (JImageEntry, Vec<i32>) {
+        let (path, redirect) = builder.write(file_name);
+        let entry = JImageEntry::new(path.clone()).unwrap();
+        fs::remove_file(path).unwrap();
+        (entry, redirect)
+    }
+
+    const NAMES: [&str; 8] = [
+        "/java.base/java/lang/Object.class",
+        "/java.base/java/lang/String.class",
+        "/java.base/java/util/List.class",
+        "/java.base/java/util/Map.class",
+        "/java.base/java/io/File.class",
+        "/java.logging/java/util/logging/Logger.class",
+        "/packages/java.lang",
+        "/modules/java.base/module-info.class",
+    ];
+
+    #[test]
+    fn finds_every_resource_through_the_redirect_table() {
+        for big_endian in [false, true] {
+            let mut builder = ImageBuilder::new(big_endian);
+            for name in NAMES {
+                builder.add(name, name.as_bytes());
+            }
+            let (image, redirect) = open(&builder, &format!("redirect-{}", big_endian));
+            // the names cover every kind of slot
+            assert!(redirect.iter().any(|&r| r == 0));
+            assert!(redirect.iter().any(|&r| r < 0));
+            assert!(redirect.iter().any(|&r| r > 0));
+            for name in NAMES {
+                let location = image.find(name).unwrap();
+                assert_eq!(image.location_name(&location), name.as_bytes());
+                assert_eq!(image.resource(&location).unwrap(), name.as_bytes());
+            }
+            assert!(image.find("/java.base/java/lang/Missing.class").is_none());
+        }
+    }
+
+    #[test]
+    fn decodes_location_attributes_of_every_length() {
+        let mut builder = ImageBuilder::new(false);
+        builder.add("/m/a/B.class", b"");
+        let (mut image, _) = open(&builder, "attributes");
+        let mut locations = Vec::new();
+        push_attribute(&mut locations, ATTRIBUTE_OFFSET, 0x12);
+        push_attribute(&mut locations, ATTRIBUTE_COMPRESSED, 0x0102_0304_0506);
+        push_attribute(&mut locations, ATTRIBUTE_UNCOMPRESSED, u64::MAX);
+        locations.push(ATTRIBUTE_END as u8);
+        // after END, not read
+        push_attribute(&mut locations, ATTRIBUTE_MODULE, 7);
+        image.index = locations.clone();
+        image.locations_start = 0;
+        image.strings_start = locations.len();
+        let attributes = image.location(0);
+        assert_eq!(attributes[ATTRIBUTE_OFFSET], 0x12);
+        assert_eq!(attributes[ATTRIBUTE_COMPRESSED], 0x0102_0304_0506);
+        assert_eq!(attributes[ATTRIBUTE_UNCOMPRESSED], u64::MAX);
+        assert_eq!(attributes[ATTRIBUTE_MODULE], 0);
+    }
+
+    #[test]
+    fn compressed_ints_take_their_length_from_the_first_byte() {
+        let read = |bytes: &[u8]| ByteReader { bytes, at: 0 }.compressed_int();
+        assert_eq!(read(&[0xA5]), Some(5));
+        assert_eq!(read(&[0xC1, 0x23]), Some(0x123));
+        assert_eq!(read(&[0xE1, 0x02, 0x03]), Some(0x1_0203));
+        // without the top bit it is a plain four byte int
+        assert_eq!(read(&[0x12, 0x34, 0x56, 0x78]), Some(0x1234_5678));
+        assert_eq!(read(&[0xC1]), None);
+        assert_eq!(read(&[0x12, 0x34]), None);
+    }
+
+    #[test]
+    fn undoes_every_compression_pass() {
+        let content = b"class file bytes, compressed twice".repeat(4);
+        let mut builder = ImageBuilder::new(true);
+        let once = zlib(&content);
+        let once = builder.compressed_header("zip", &once, content.len());
+        let twice = zlib(&once);
+        let twice = builder.compressed_header("zip", &twice, once.len());
+        builder.add_stored("/m/p/Twice.class", &twice, twice.len(), content.len());
+        let unknown = builder.compressed_header("lz4", b"body", 4);
+        builder.add("/m/p/Unknown.class", &unknown);
+        builder.add("/m/p/Plain.class", &content);
+        let (image, _) = open(&builder, "compressed");
+
+        let location = image.find("/m/p/Twice.class").unwrap();
+        assert_eq!(image.resource(&location).unwrap(), content);
+        let location = image.find("/m/p/Plain.class").unwrap();
+        assert_eq!(image.resource(&location).unwrap(), content);
+        let location = image.find("/m/p/Unknown.class").unwrap();
+        assert!(matches!(
+            image.resource(&location),
+            Err(ClassLoadingError::ParseError(message)) if message.contains("lz4")
+        ));
+    }
+
+    #[test]
+    fn rebuilds_constant_pools_of_compact_cp_classes() {
+        let mut builder = ImageBuilder::new(false);
+        // two byte compressed ints of string offsets
+        let mut shared_string = |string: &str| {
+            let offset = builder.string(string) as u16;
+            assert!(offset < 0x2000);
+            [0xC0 | (offset >> 8) as u8, offset as u8]
+        };
+        let shared = shared_string("java/lang/Object");
+        let skeleton = shared_string("(L;I)L;");
+        let package = shared_string("java/util");
+        let list = shared_string("List");
+        let lang = shared_string("java/lang");
+        let string = shared_string("String");
+
+        let mut class = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 61];
+        // six slots, the Long takes two
+        class.extend([0, 6]);
+        class.extend([CONSTANT_UTF8, 0, 4]);
+        class.extend(b"Code");
+        class.push(EXTERNALIZED_STRING);
+        class.extend(shared);
+        // the skeleton, then the length of the package and class name indexes of each L
+        class.push(EXTERNALIZED_STRING_DESCRIPTOR);
+        class.extend(skeleton);
+        class.push(0xA8);
+        class.extend([package, list, lang, string].concat());
+        class.extend([CONSTANT_LONG, 0, 0, 0, 0, 0, 0, 0, 9]);
+        // access flags and the rest follow unchanged
+        class.extend([0, 0x21, 0, 1]);
+
+        let mut expected = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 61, 0, 6];
+        push_utf8(&mut expected, b"Code").unwrap();
+        push_utf8(&mut expected, b"java/lang/Object").unwrap();
+        push_utf8(&mut expected, b"(Ljava/util/List;I)Ljava/lang/String;").unwrap();
+        expected.extend([CONSTANT_LONG, 0, 0, 0, 0, 0, 0, 0, 9]);
+        expected.extend([0, 0x21, 0, 1]);
+
+        let compact = builder.compressed_header("compact-cp", &class, expected.len());
+        let zipped = zlib(&compact);
+        let zipped = builder.compressed_header("zip", &zipped, compact.len());
+        builder.add("/m/p/Compact.class", &zipped);
+        let (image, _) = open(&builder, "compact-cp");
+        let location = image.find("/m/p/Compact.class").unwrap();
+        assert_eq!(image.resource(&location).unwrap(), expected);
+    }
+}
@@ -1,9 +1,13 @@
+use crate::inline_cache::{InlineCache, InlineCacheStats};
 use crate::jvm_error::JVMError;
 use crate::runtime::*;
 use crate::vm::VM;
 use parser::access_flag::*;
-use parser::attribute::Code;
-use parser::constant_pool::{ConstantNameAndTypeInfo, ConstantPool, FieldInfo, MethodInfo};
+use parser::attribute::{AttributeInfo, Code, ConstantValue};
+use parser::constant_pool::{
+    ConstantDoubleInfo, ConstantFloatInfo, ConstantInfo, ConstantIntegerInfo, ConstantLongInfo,
+    ConstantNameAndTypeInfo, ConstantPool, FieldInfo, MethodInfo,
+};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -13,6 +17,83 @@ pub enum InitState {
     Uninitialized,
     InProgress,
     Initialized,
+    // <clinit> completed abruptly
+    Erroneous,
+}
+
+#[derive(Debug, Clone)]
+pub enum FieldSlot {
+    Instance(usize),
+    Static(Arc<LoadedClass>, usize),
+}
+
+// Fieldref constant resolved once and cached per constant pool index
+#[derive(Debug, Clone)]
+pub struct ResolvedField {
+    pub class_name: String,
+    pub name_des: NameDes,
+    pub slot: FieldSlot,
+}
+
+// Methodref/InterfaceMethodref resolved per JVMS 5.4.3.3/5.4.3.4, cached per constant pool index
+#[derive(Debug, Clone)]
+pub struct ResolvedMethod {
+    pub referenced_class: Arc<LoadedClass>,
+    pub class: Arc<LoadedClass>,
+    pub name_des: NameDes,
+    pub access_flags: MethodFlags,
+}
+
+impl ResolvedMethod {
+    fn new(
+        referenced_class: &Arc<LoadedClass>,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+    ) -> Self {
+        let access_flags = class
+            .get_method_info_from_name_and_descriptor(name_des)
+            .map(|method| method.access_flags)
+            .unwrap_or(MethodFlags::empty());
+        ResolvedMethod {
+            referenced_class: Arc::clone(referenced_class),
+            class: Arc::clone(class),
+            name_des: name_des.clone(),
+            access_flags,
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags.contains(MethodFlags::ACC_STATIC)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.access_flags.contains(MethodFlags::ACC_PRIVATE)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags.contains(MethodFlags::ACC_ABSTRACT)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.access_flags.contains(MethodFlags::ACC_NATIVE)
+    }
+
+    pub fn code(&self) -> Result<Arc<Code>, JVMError> {
+        if self.is_abstract() {
+            return Err(JVMError::AbstractMethodCall {
+                class: self.class.class_name.clone(),
+                name: self.name_des.name.clone(),
+                descriptor: self.name_des.des.clone(),
+            });
+        }
+        self.class
+            .get_code_from_method(&self.name_des)
+            .ok_or_else(|| JVMError::MethodNotFound {
+                class: self.class.class_name.clone(),
+                name: self.name_des.name.clone(),
+                descriptor: self.name_des.des.clone(),
+            })
+    }
 }
 
 #[derive(Debug)]
@@ -23,14 +104,22 @@ pub struct LoadedClass {
     pub instance_fields: Vec<FieldInfo>,
     pub instance_fields_indices: HashMap<String, usize>,
     pub instance_fields_descriptors: HashMap<usize, String>,
+    pub declared_instance_fields: HashMap<NameDes, usize>,
     pub static_fields: Vec<FieldInfo>,
     pub static_values: RefCell<Vec<Value>>,
     pub static_field_indices: HashMap<String, usize>,
+    pub declared_static_fields: HashMap<NameDes, usize>,
+    pub field_ref_cache: Mutex<HashMap<u16, Arc<ResolvedField>>>,
     pub methods: Vec<MethodInfo>,
+    pub method_indices: HashMap<NameDes, usize>,
+    pub method_ref_cache: Mutex<HashMap<u16, Arc<ResolvedMethod>>>,
     pub constant_pool: Arc<ConstantPool>,
     pub access_flags: ClassFlags,
     pub code_cache: Mutex<HashMap<NameDes, Arc<Code>>>,
+    pub inline_caches: Mutex<HashMap<NameDes, HashMap<usize, InlineCache>>>,
     pub init_state: Mutex<InitState>,
+    // component type of a synthesised array class, None for everything else
+    pub component_class: Option<Arc<LoadedClass>>,
 }
 
 impl LoadedClass {
@@ -55,43 +144,38 @@ impl LoadedClass {
                     .map(|name| (name.clone(), i))
             })
             .collect();
-        let mut all_instance_fields = Vec::new();
-        if let Some(ref super_class) = super_class {
-            all_instance_fields.extend(super_class.instance_fields.iter().cloned());
-        }
-        all_instance_fields.extend(instance_fields);
-        let mut field_indices = HashMap::new();
-        let mut indices_des = HashMap::new();
-        let mut slot_index = 0;
-        if let Some(ref super_class) = super_class {
-            for field in &super_class.instance_fields {
-                let name = super_class
-                    .constant_pool
-                    .get_underlying_string_from_utf8_index(field.name_index)
-                    .expect("Invalid name_index in superclass constant pool");
-                let des = super_class
-                    .constant_pool
-                    .get_underlying_string_from_utf8_index(field.descriptor_index)
-                    .expect("Invalid descriptor_index in superclass constant pool");
-                field_indices.insert(name.to_string(), slot_index);
-                indices_des.insert(slot_index, des.to_string());
-                slot_index += 1;
-            }
-        }
-        for field in &all_instance_fields[slot_index..] {
-            let name = constant_pool
-                .get_underlying_string_from_utf8_index(field.name_index)
-                .expect("Invalid name_index in constant pool");
-            let des = constant_pool
-                .get_underlying_string_from_utf8_index(field.descriptor_index)
-                .expect("Invalid descriptor_index in constant pool");
-            field_indices.insert(name.to_string(), slot_index);
-            indices_des.insert(slot_index, des.to_string());
-            slot_index += 1;
+        let declared_static_fields: HashMap<NameDes, usize> = static_fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (NameDes::from_field(f, &constant_pool), i))
+            .collect();
+        // inherited slots keep their superclass positions, declared fields are appended after them
+        let (mut all_instance_fields, mut field_indices, mut indices_des) = match &super_class {
+            Some(super_class) => (
+                super_class.instance_fields.clone(),
+                super_class.instance_fields_indices.clone(),
+                super_class.instance_fields_descriptors.clone(),
+            ),
+            None => (Vec::new(), HashMap::new(), HashMap::new()),
+        };
+        let mut declared_instance_fields = HashMap::new();
+        for field in instance_fields {
+            let slot_index = all_instance_fields.len();
+            let name_des = NameDes::from_field(&field, &constant_pool);
+            // a field hiding a superclass field of the same name shadows it for name based access
+            field_indices.insert(name_des.name.clone(), slot_index);
+            indices_des.insert(slot_index, name_des.des.clone());
+            declared_instance_fields.insert(name_des, slot_index);
+            all_instance_fields.push(field);
         }
         let static_values = static_fields
             .iter()
             .map(|field| {
+                // primitive constants take their ConstantValue right away rather than at
+                // initialization (JVMS 5.5), String constants are inlined by javac at every use
+                if let Some(value) = Self::constant_value(field, &constant_pool) {
+                    return value;
+                }
                 let descriptor = constant_pool
                     .get_underlying_string_from_utf8_index(field.descriptor_index)
                     .expect("Invalid descriptor_index in constant pool");
@@ -110,6 +194,18 @@ impl LoadedClass {
             })
             .collect::<Vec<_>>();
 
+        let method_indices = methods
+            .iter()
+            .enumerate()
+            .map(|(i, method)| {
+                let name_des = NameDes {
+                    name: method.get_name(&constant_pool).to_string(),
+                    des: method.get_des(&constant_pool).to_string(),
+                };
+                (name_des, i)
+            })
+            .collect();
+
         LoadedClass {
             class_name,
             super_class,
@@ -117,14 +213,35 @@ impl LoadedClass {
             instance_fields: all_instance_fields,
             instance_fields_indices: field_indices,
             instance_fields_descriptors: indices_des,
+            declared_instance_fields,
             static_fields,
             static_values: RefCell::new(static_values),
             static_field_indices,
+            declared_static_fields,
+            field_ref_cache: Mutex::new(HashMap::new()),
             methods,
+            method_indices,
+            method_ref_cache: Mutex::new(HashMap::new()),
             constant_pool,
             access_flags,
             code_cache: Mutex::new(HashMap::new()),
+            inline_caches: Mutex::new(HashMap::new()),
             init_state: Mutex::new(InitState::Uninitialized),
+            component_class: None,
+        }
+    }
+
+    fn constant_value(field: &FieldInfo, cp: &ConstantPool) -> Option<Value> {
+        let index = field.attributes.iter().find_map(|attribute| match attribute {
+            AttributeInfo::ConstantValue(ConstantValue(index)) => Some(*index),
+            _ => None,
+        })?;
+        match cp.get_entry(index)? {
+            ConstantInfo::Integer(ConstantIntegerInfo(i)) => Some(Value::Int(*i)),
+            ConstantInfo::Long(ConstantLongInfo(l)) => Some(Value::Long(*l)),
+            ConstantInfo::Float(ConstantFloatInfo(f)) => Some(Value::Float(*f)),
+            ConstantInfo::Double(ConstantDoubleInfo(d)) => Some(Value::Double(*d)),
+            _ => None,
         }
     }
 
@@ -132,10 +249,70 @@ impl LoadedClass {
         &self,
         name_des: &NameDes,
     ) -> Option<&MethodInfo> {
-        self.methods.iter().find(|method| {
-            method.get_name(&self.constant_pool) == name_des.name
-                && method.get_des(&self.constant_pool) == name_des.des
-        })
+        self.method_indices
+            .get(name_des)
+            .map(|&index| &self.methods[index])
+    }
+
+    // array classes (JVMS 5.3.3) have no class file, they extend Object and implement
+    // Cloneable and Serializable
+    pub fn new_array_class(
+        class_name: String,
+        object_class: Arc<LoadedClass>,
+        interfaces: Vec<Arc<LoadedClass>>,
+        component_class: Option<Arc<LoadedClass>>,
+    ) -> Self {
+        LoadedClass {
+            component_class,
+            ..Self::new(
+                class_name,
+                Some(object_class),
+                interfaces,
+                Vec::new(),
+                Vec::new(),
+                Arc::new(ConstantPool::default()),
+                ClassFlags::ACC_PUBLIC | ClassFlags::ACC_FINAL | ClassFlags::ACC_ABSTRACT,
+            )
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.class_name.starts_with('[')
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        matches!(*self.init_state.lock().unwrap(), InitState::Initialized)
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.access_flags.contains(ClassFlags::ACC_INTERFACE)
+    }
+
+    // true if `other` is this class, one of its superclasses or one of its superinterfaces
+    pub fn is_subtype_of(&self, other: &LoadedClass) -> bool {
+        if self.class_name == other.class_name {
+            return true;
+        }
+        // arrays of references are covariant in their component type
+        if self.is_array() && other.is_array() {
+            return match (&self.component_class, &other.component_class) {
+                (Some(component), Some(other_component)) => {
+                    component.is_subtype_of(other_component)
+                }
+                _ => false,
+            };
+        }
+        if self
+            .interfaces
+            .iter()
+            .any(|interface| interface.is_subtype_of(other))
+        {
+            return true;
+        }
+        match &self.super_class {
+            Some(super_class) => super_class.is_subtype_of(other),
+            None => false,
+        }
     }
 
     pub fn get_code_from_method(&self, name_des: &NameDes) -> Option<Arc<Code>> {
@@ -157,36 +334,249 @@ impl LoadedClass {
         }
     }
 
+    pub fn inline_cache_stats(&self) -> Vec<InlineCacheStats> {
+        let caches = self.inline_caches.lock().unwrap();
+        let mut stats = Vec::new();
+        for (method, sites) in caches.iter() {
+            for (pc, cache) in sites.iter() {
+                stats.push(InlineCacheStats {
+                    class_name: self.class_name.clone(),
+                    method: format!("{}{}", method.name, method.des),
+                    pc: *pc,
+                    state: cache.state(),
+                    receivers: cache.receivers(),
+                    hits: cache.hits,
+                    misses: cache.misses,
+                    megamorphic_lookups: cache.megamorphic_lookups,
+                });
+            }
+        }
+        stats.sort_by(|a, b| a.method.cmp(&b.method).then(a.pc.cmp(&b.pc)));
+        stats
+    }
+
+    // class initialization of JVMS 5.5, triggered by new, getstatic, putstatic and invokestatic.
+    // There is one Java thread, so a class already in progress is being initialized by the
+    // caller and is used as it is.
     pub async fn initialize(self_class: Arc<LoadedClass>, vm: &VM) -> Result<(), JVMError> {
-        let mut state = self_class.init_state.lock().unwrap();
-        match *state {
-            InitState::Initialized => return Ok(()),
-            InitState::InProgress => {
-                return Err(JVMError::Other(
-                    "Circular initialization detected".to_string(),
-                ))
+        {
+            let mut state = self_class.init_state.lock().unwrap();
+            match *state {
+                InitState::Initialized | InitState::InProgress => return Ok(()),
+                InitState::Erroneous => {
+                    return Err(JVMError::Other(format!(
+                        "NoClassDefFoundError: Could not initialize class {}",
+                        self_class.class_name
+                    )))
+                }
+                InitState::Uninitialized => *state = InitState::InProgress,
             }
-            InitState::Uninitialized => {
-                *state = InitState::InProgress;
+        }
+
+        let result = Self::run_initializers(&self_class, vm).await;
+        *self_class.init_state.lock().unwrap() = match result {
+            Ok(()) => InitState::Initialized,
+            Err(_) => InitState::Erroneous,
+        };
+        result
+    }
+
+    async fn run_initializers(self_class: &Arc<LoadedClass>, vm: &VM) -> Result<(), JVMError> {
+        if let Some(super_class) = &self_class.super_class {
+            let fut = Box::pin(LoadedClass::initialize(super_class.clone(), vm));
+            fut.await?;
+        }
+
+        let clinit_name_des = NameDes {
+            name: "<clinit>".to_string(),
+            des: "()V".to_string(),
+        };
+        if let Some(code) = self_class.get_code_from_method(&clinit_name_des) {
+            let frame = Frame::new(self_class.clone(), &clinit_name_des, code);
+            let mut stack = Stack::new();
+            stack.push_frame(frame)?;
+            stack.execute_current_frame(vm).await?;
+        }
+        Ok(())
+    }
+
+    // method resolution of JVMS 5.4.3.3 for Methodref constants
+    pub fn resolve_method(
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+    ) -> Result<ResolvedMethod, JVMError> {
+        if class.is_interface() {
+            return Err(JVMError::IncompatibleClassChange(format!(
+                "Found interface {}, but class was expected",
+                class.class_name
+            )));
+        }
+        let mut current = Some(class);
+        while let Some(cls) = current {
+            if cls.method_indices.contains_key(name_des) {
+                return Ok(ResolvedMethod::new(class, cls, name_des));
+            }
+            current = cls.super_class.as_ref();
+        }
+        Self::resolve_in_superinterfaces(class, name_des)
+    }
 
-                if let Some(super_class) = &self_class.super_class {
-                    let fut = Box::pin(LoadedClass::initialize(super_class.clone(), vm));
-                    fut.await?;
+    // interface method resolution of JVMS 5.4.3.4 for InterfaceMethodref constants
+    pub fn resolve_interface_method(
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+    ) -> Result<ResolvedMethod, JVMError> {
+        if !class.is_interface() {
+            return Err(JVMError::IncompatibleClassChange(format!(
+                "Found class {}, but interface was expected",
+                class.class_name
+            )));
+        }
+        if class.method_indices.contains_key(name_des) {
+            return Ok(ResolvedMethod::new(class, class, name_des));
+        }
+        // public instance methods of Object are members of every interface
+        if let Some(object) = &class.super_class {
+            if let Some(method) = object.get_method_info_from_name_and_descriptor(name_des) {
+                if method.access_flags.contains(MethodFlags::ACC_PUBLIC)
+                    && !method.access_flags.contains(MethodFlags::ACC_STATIC)
+                {
+                    return Ok(ResolvedMethod::new(class, object, name_des));
                 }
+            }
+        }
+        Self::resolve_in_superinterfaces(class, name_des)
+    }
 
-                let clinit_name_des = NameDes {
-                    name: "<clinit>".to_string(),
-                    des: "()V".to_string(),
-                };
-                if let Some(code) = self_class.get_code_from_method(&clinit_name_des) {
-                    let frame = Frame::new(self_class.clone(), &clinit_name_des, code);
-                    let mut stack = Stack::new();
-                    stack.push_frame(frame)?;
-                    stack.execute_current_frame(vm).await?;
+    fn resolve_in_superinterfaces(
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+    ) -> Result<ResolvedMethod, JVMError> {
+        let candidates = Self::superinterface_methods(class, name_des);
+        let maximally_specific = Self::maximally_specific(&candidates);
+        let mut concrete = maximally_specific
+            .iter()
+            .filter(|interface| !ResolvedMethod::new(class, interface, name_des).is_abstract());
+        if let (Some(interface), None) = (concrete.next(), concrete.next()) {
+            return Ok(ResolvedMethod::new(class, interface, name_des));
+        }
+        match candidates.first() {
+            Some(interface) => Ok(ResolvedMethod::new(class, interface, name_des)),
+            None => Err(JVMError::MethodNotFound {
+                class: class.class_name.clone(),
+                name: name_des.name.clone(),
+                descriptor: name_des.des.clone(),
+            }),
+        }
+    }
+
+    // every superinterface (direct or inherited) declaring a non-private, non-static method name_des
+    fn superinterface_methods(
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+    ) -> Vec<Arc<LoadedClass>> {
+        let mut found: Vec<Arc<LoadedClass>> = Vec::new();
+        let mut pending: Vec<Arc<LoadedClass>> = Vec::new();
+        let mut current = Some(class);
+        while let Some(cls) = current {
+            pending.extend(cls.interfaces.iter().cloned());
+            current = cls.super_class.as_ref();
+        }
+        let mut visited = Vec::new();
+        while let Some(interface) = pending.pop() {
+            if visited.contains(&interface.class_name) {
+                continue;
+            }
+            visited.push(interface.class_name.clone());
+            if let Some(method) = interface.get_method_info_from_name_and_descriptor(name_des) {
+                if !method
+                    .access_flags
+                    .intersects(MethodFlags::ACC_PRIVATE | MethodFlags::ACC_STATIC)
+                {
+                    found.push(Arc::clone(&interface));
                 }
-                *state = InitState::Initialized;
-                Ok(())
             }
+            pending.extend(interface.interfaces.iter().cloned());
+        }
+        found
+    }
+
+    // candidates for which no other candidate is declared in a subinterface
+    fn maximally_specific(candidates: &[Arc<LoadedClass>]) -> Vec<Arc<LoadedClass>> {
+        candidates
+            .iter()
+            .filter(|candidate| {
+                !candidates.iter().any(|other| {
+                    other.class_name != candidate.class_name && other.is_subtype_of(candidate)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    // method selection of JVMS 5.4.6 for invokevirtual/invokeinterface on a receiver class
+    pub fn select_method(
+        receiver: &Arc<LoadedClass>,
+        resolved: &ResolvedMethod,
+    ) -> Result<ResolvedMethod, JVMError> {
+        if resolved.is_private() {
+            return Ok(resolved.clone());
+        }
+        let name_des = &resolved.name_des;
+        let mut current = Some(receiver);
+        while let Some(cls) = current {
+            if let Some(method) = cls.get_method_info_from_name_and_descriptor(name_des) {
+                if !method.access_flags.contains(MethodFlags::ACC_STATIC) {
+                    return Ok(ResolvedMethod::new(
+                        &resolved.referenced_class,
+                        cls,
+                        name_des,
+                    ));
+                }
+            }
+            current = cls.super_class.as_ref();
+        }
+        let candidates = Self::superinterface_methods(receiver, name_des);
+        let concrete = Self::maximally_specific(&candidates)
+            .into_iter()
+            .filter(|interface| {
+                !ResolvedMethod::new(&resolved.referenced_class, interface, name_des).is_abstract()
+            })
+            .collect::<Vec<_>>();
+        match concrete.as_slice() {
+            [interface] => Ok(ResolvedMethod::new(
+                &resolved.referenced_class,
+                interface,
+                name_des,
+            )),
+            [] => Err(JVMError::AbstractMethodCall {
+                class: receiver.class_name.clone(),
+                name: name_des.name.clone(),
+                descriptor: name_des.des.clone(),
+            }),
+            _ => Err(JVMError::IncompatibleClassChange(format!(
+                "Conflicting default methods for {}{} in {}",
+                name_des.name, name_des.des, receiver.class_name
+            ))),
+        }
+    }
+
+    // field lookup of JVMS 5.4.3.2: declared fields, then superinterfaces, then the superclass
+    pub fn resolve_field(class: &Arc<LoadedClass>, name_des: &NameDes) -> Option<FieldSlot> {
+        if let Some(&slot) = class.declared_instance_fields.get(name_des) {
+            return Some(FieldSlot::Instance(slot));
+        }
+        if let Some(&index) = class.declared_static_fields.get(name_des) {
+            return Some(FieldSlot::Static(Arc::clone(class), index));
+        }
+        for interface in &class.interfaces {
+            if let Some(slot) = Self::resolve_field(interface, name_des) {
+                return Some(slot);
+            }
+        }
+        match &class.super_class {
+            Some(super_class) => Self::resolve_field(super_class, name_des),
+            None => None,
         }
     }
 
@@ -229,6 +619,19 @@ pub struct NameDes {
 }
 
 impl NameDes {
+    pub fn from_field(field: &FieldInfo, cp: &ConstantPool) -> Self {
+        Self {
+            name: cp
+                .get_underlying_string_from_utf8_index(field.name_index)
+                .expect("Invalid name_index in constant pool")
+                .clone(),
+            des: cp
+                .get_underlying_string_from_utf8_index(field.descriptor_index)
+                .expect("Invalid descriptor_index in constant pool")
+                .clone(),
+        }
+    }
+
     pub fn new(argu: &ConstantNameAndTypeInfo, cp: &ConstantPool) -> Self {
         Self {
             name: cp
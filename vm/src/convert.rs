@@ -0,0 +1,344 @@
+// Conversions between Rust values and the Values the VM works with. Reading a Value is
+// synchronous, making one may allocate on the heap and so has to await the heap lock.
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::object::ObjectKind;
+use crate::runtime::{Stack, Value};
+use crate::vm::VM;
+use async_trait::async_trait;
+
+pub trait FromJava: Sized {
+    fn from_java(value: &Value) -> Result<Self, JVMError>;
+}
+
+#[async_trait(?Send)]
+pub trait IntoJava {
+    // the descriptor of the Java type the value becomes, the element type of arrays made
+    // from a Vec of it
+    fn descriptor() -> String
+    where
+        Self: Sized;
+
+    async fn into_java(self, vm: &VM, stack: &Stack) -> Result<Value, JVMError>;
+}
+
+// Rust values that become Java references. Only these go into an Option, None being null,
+// an Option<i32> would have no Java type to become.
+pub trait IntoJavaReference: IntoJava {}
+
+// what a Value holds, for type errors
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Default => "an uninitialized value".to_string(),
+        Value::Int(i) => format!("int {}", i),
+        Value::Long(l) => format!("long {}", l),
+        Value::Float(f) => format!("float {}", f),
+        Value::Double(d) => format!("double {}", d),
+        Value::Reference(None) => "null".to_string(),
+        Value::Reference(Some(object)) => match &object.class {
+            Some(class) => format!("an instance of {}", class.class_name),
+            None => "an object without a class".to_string(),
+        },
+    }
+}
+
+fn mismatch<T>(expected: &str, found: &Value) -> Result<T, JVMError> {
+    Err(JVMError::TypeMismatch {
+        expected: expected.to_string(),
+        found: describe(found),
+    })
+}
+
+impl FromJava for Value {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        Ok(value.clone())
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for Value {
+    fn descriptor() -> String {
+        "Ljava/lang/Object;".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(self)
+    }
+}
+
+impl FromJava for i32 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Int(i) => Ok(*i),
+            value => mismatch("int", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for i32 {
+    fn descriptor() -> String {
+        "I".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Int(self))
+    }
+}
+
+// byte and short travel as int, the value has to fit
+impl FromJava for i8 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Int(i) if i8::try_from(*i).is_ok() => Ok(*i as i8),
+            value => mismatch("byte", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for i8 {
+    fn descriptor() -> String {
+        "B".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Int(self as i32))
+    }
+}
+
+impl FromJava for i16 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Int(i) if i16::try_from(*i).is_ok() => Ok(*i as i16),
+            value => mismatch("short", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for i16 {
+    fn descriptor() -> String {
+        "S".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Int(self as i32))
+    }
+}
+
+impl FromJava for i64 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Long(l) => Ok(*l),
+            value => mismatch("long", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for i64 {
+    fn descriptor() -> String {
+        "J".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Long(self))
+    }
+}
+
+impl FromJava for f32 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Float(f) => Ok(*f),
+            value => mismatch("float", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for f32 {
+    fn descriptor() -> String {
+        "F".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Float(self))
+    }
+}
+
+impl FromJava for f64 {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Double(d) => Ok(*d),
+            value => mismatch("double", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for f64 {
+    fn descriptor() -> String {
+        "D".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Double(self))
+    }
+}
+
+// booleans are ints on the operand stack, anything but 0 is true
+impl FromJava for bool {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Int(i) => Ok(*i != 0),
+            value => mismatch("boolean", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for bool {
+    fn descriptor() -> String {
+        "Z".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        Ok(Value::Int(self as i32))
+    }
+}
+
+// a Java char is one UTF-16 code unit, so surrogates and characters outside the Basic
+// Multilingual Plane have no counterpart on the other side
+impl FromJava for char {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Int(i) => match u16::try_from(*i).ok().and_then(|c| char::from_u32(c as u32)) {
+                Some(c) => Ok(c),
+                None => mismatch("char", value),
+            },
+            value => mismatch("char", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for char {
+    fn descriptor() -> String {
+        "C".to_string()
+    }
+
+    async fn into_java(self, _vm: &VM, _stack: &Stack) -> Result<Value, JVMError> {
+        let mut units = [0; 2];
+        match self.encode_utf16(&mut units) {
+            [unit] => Ok(Value::Int(*unit as i32)),
+            _ => Err(JVMError::TypeMismatch {
+                expected: "char".to_string(),
+                found: format!("{:?}, which takes two UTF-16 code units", self),
+            }),
+        }
+    }
+}
+
+// null is not a String, read an Option<String> where null is expected
+impl FromJava for String {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Reference(Some(object))
+                if object
+                    .class
+                    .as_ref()
+                    .is_some_and(|class| class.class_name == "java/lang/String") =>
+            {
+                extract_string(object).map_err(JVMError::Other)
+            }
+            value => mismatch("java/lang/String", value),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for String {
+    fn descriptor() -> String {
+        "Ljava/lang/String;".to_string()
+    }
+
+    async fn into_java(self, vm: &VM, stack: &Stack) -> Result<Value, JVMError> {
+        self.as_str().into_java(vm, stack).await
+    }
+}
+
+#[async_trait(?Send)]
+impl IntoJava for &str {
+    fn descriptor() -> String {
+        "Ljava/lang/String;".to_string()
+    }
+
+    async fn into_java(self, vm: &VM, stack: &Stack) -> Result<Value, JVMError> {
+        let mut heap = vm.heap.write().await;
+        heap.allocate_string(stack, vm, self).await
+    }
+}
+
+impl IntoJavaReference for String {}
+
+impl IntoJavaReference for &str {}
+
+// None is null
+impl<T: FromJava> FromJava for Option<T> {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        match value {
+            Value::Reference(None) => Ok(None),
+            value => T::from_java(value).map(Some),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: IntoJavaReference> IntoJava for Option<T> {
+    fn descriptor() -> String {
+        T::descriptor()
+    }
+
+    async fn into_java(self, vm: &VM, stack: &Stack) -> Result<Value, JVMError> {
+        match self {
+            Some(value) => value.into_java(vm, stack).await,
+            None => Ok(Value::Reference(None)),
+        }
+    }
+}
+
+// arrays of any element type, a null array is an error like a null String
+impl<T: FromJava> FromJava for Vec<T> {
+    fn from_java(value: &Value) -> Result<Self, JVMError> {
+        let Value::Reference(Some(object)) = value else {
+            return mismatch("an array", value);
+        };
+        let ObjectKind::ArrayInstance { elements, .. } = &object.kind else {
+            return mismatch("an array", value);
+        };
+        elements.borrow().to_values().iter().map(T::from_java).collect()
+    }
+}
+
+#[async_trait(?Send)]
+impl<T: IntoJava> IntoJava for Vec<T> {
+    fn descriptor() -> String {
+        format!("[{}", T::descriptor())
+    }
+
+    async fn into_java(self, vm: &VM, stack: &Stack) -> Result<Value, JVMError> {
+        let array = vm
+            .allocate_array(stack, &T::descriptor(), self.len())
+            .await?;
+        if let Value::Reference(Some(object)) = &array {
+            for (i, element) in self.into_iter().enumerate() {
+                let element = element.into_java(vm, stack).await?;
+                object.set_element(i, element)?;
+            }
+        }
+        Ok(array)
+    }
+}
+
+impl<T: IntoJava> IntoJavaReference for Vec<T> {}
@@ -0,0 +1,428 @@
+// The API for running Java code from a Rust program:
+//
+//     let vm = VM::builder().class_path("classes").build().await?;
+//     let args = vec![Value::Int(1), Value::Int(2)];
+//     let sum = vm.invoke_static("Calc", "add", "(II)I", args).await?;
+//
+// Every call runs the method to completion on a stack of its own. Classes are loaded and
+// initialized on first use, as the interpreter does.
+use crate::class_loader::bundled::require_bundled;
+use crate::class_loader::classpath_entry::ClassProvider;
+use crate::class_loader::java_home::JavaHome;
+use crate::class_loader::loaded_class::{LoadedClass, NameDes, ResolvedMethod};
+use crate::convert::IntoJava;
+use crate::execute::execute::ExecutionResult;
+use crate::jvm_error::JVMError;
+use crate::native::{NativeContext, RustNative};
+use crate::parse_des::parse_descriptor;
+use crate::runtime::{Stack, Value};
+use crate::state::Property;
+use crate::vm::VM;
+use parser::access_flag::ClassFlags;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Configures a VM before it starts up
+pub struct VMBuilder {
+    heap_size: usize,
+    java_home: Option<PathBuf>,
+    bundled_runtime: bool,
+    class_path: Vec<ClassPathSource>,
+    libraries: Vec<PathBuf>,
+    natives: Vec<(String, String, String, RustNative)>,
+    properties: Vec<Property>,
+}
+
+// Where a class path entry of the builder reads its classes from
+enum ClassPathSource {
+    // a directory or a .jar
+    Path(String),
+    Memory(HashMap<String, Vec<u8>>),
+    Callback(ClassProvider),
+}
+
+// An exception a Java method threw and did not catch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaException {
+    // internal form, java/lang/IllegalStateException
+    pub class_name: String,
+    // the detail message of the Throwable, None when it has none or the VM raised it
+    pub message: Option<String>,
+}
+
+// Why a method invoked from Rust did not return
+#[derive(Debug)]
+pub enum InvokeError {
+    Exception(JavaException),
+    // the VM failed, for example on a class it could not load or bytecode it does not run
+    VM(JVMError),
+}
+
+impl fmt::Display for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // as Throwable.toString puts it
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", self.class_name.replace('/', "."), message),
+            None => write!(f, "{}", self.class_name.replace('/', ".")),
+        }
+    }
+}
+
+impl From<JVMError> for InvokeError {
+    fn from(error: JVMError) -> Self {
+        match error {
+            JVMError::UncaughtException(class_name) => {
+                InvokeError::Exception(JavaException {
+                    class_name,
+                    message: None,
+                })
+            }
+            error => InvokeError::VM(error),
+        }
+    }
+}
+
+impl Default for VMBuilder {
+    fn default() -> Self {
+        VMBuilder {
+            heap_size: 4096,
+            java_home: None,
+            bundled_runtime: false,
+            class_path: Vec::new(),
+            libraries: Vec::new(),
+            natives: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+}
+
+impl VMBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the number of heap slots
+    pub fn heap_size(mut self, heap_size: usize) -> Self {
+        self.heap_size = heap_size;
+        self
+    }
+
+    // the JDK to boot from instead of the one JavaHome::find picks
+    pub fn java_home(mut self, path: impl Into<PathBuf>) -> Self {
+        self.java_home = Some(path.into());
+        self
+    }
+
+    // boots from the class library bundled with the VM instead of a JDK
+    pub fn bundled_runtime(mut self) -> Self {
+        self.bundled_runtime = true;
+        self
+    }
+
+    // a directory of class files or a .jar, searched in the order they are added
+    pub fn class_path(mut self, entry: impl Into<String>) -> Self {
+        self.class_path.push(ClassPathSource::Path(entry.into()));
+        self
+    }
+
+    // class files by internal class name (pkg/Main), searched in class path order like the
+    // entries class_path adds
+    pub fn class_files(mut self, classes: HashMap<String, Vec<u8>>) -> Self {
+        self.class_path.push(ClassPathSource::Memory(classes));
+        self
+    }
+
+    // a closure returning the class file for an internal class name, or None when it has none,
+    // searched in class path order like the entries class_path adds
+    pub fn class_provider(
+        mut self,
+        provider: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        self.class_path
+            .push(ClassPathSource::Callback(Box::new(provider)));
+        self
+    }
+
+    // a JNI library loaded as System.load would, its JNI_OnLoad runs at build time
+    pub fn library(mut self, path: impl Into<PathBuf>) -> Self {
+        self.libraries.push(path.into());
+        self
+    }
+
+    // implements a native method with a Rust closure, see VM::register_rust_native
+    pub fn native(
+        mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        function: impl Fn(&NativeContext, Vec<Value>) -> Result<ExecutionResult, JVMError>
+            + 'static,
+    ) -> Self {
+        self.natives.push((
+            class_name.to_string(),
+            name.to_string(),
+            descriptor.to_string(),
+            Rc::new(function),
+        ));
+        self
+    }
+
+    // a system property as -Dname=value sets it, java.library.path and java.class.path
+    // included
+    pub fn property(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.push((name.into(), value.into()));
+        self
+    }
+
+    // starts the VM, which runs the startup sequence of its runtime before it returns
+    pub async fn build(self) -> Result<VM, JVMError> {
+        let java_home = if self.bundled_runtime {
+            require_bundled().map_err(|e| JVMError::Other(e.to_string()))?;
+            None
+        } else {
+            let java_home = JavaHome::find(self.java_home.as_deref())
+                .map_err(|e| JVMError::Other(e.to_string()))?;
+            Some(java_home)
+        };
+        // java.class.path lists the entries on the filesystem only
+        let paths: Vec<&str> = self
+            .class_path
+            .iter()
+            .filter_map(|source| match source {
+                ClassPathSource::Path(path) => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut properties = Vec::new();
+        if !paths.is_empty() {
+            properties.push(("java.class.path".to_string(), paths.join(":")));
+        }
+        properties.extend(self.properties);
+        let mut vm = VM::with_config(self.heap_size, java_home, properties).await?;
+        for source in self.class_path {
+            let added = match source {
+                ClassPathSource::Path(entry) if entry.ends_with(".jar") => {
+                    vm.class_loader.add_jar_entry(entry)
+                }
+                ClassPathSource::Path(entry) => vm.class_loader.add_directory_entry(entry),
+                ClassPathSource::Memory(classes) => vm.class_loader.add_memory_entry(classes),
+                ClassPathSource::Callback(provider) => vm.class_loader.add_callback_entry(provider),
+            };
+            added.map_err(|e| JVMError::Other(e.to_string()))?;
+        }
+        for (class_name, name, descriptor, function) in self.natives {
+            vm.register_rust_native(&class_name, &name, &descriptor, move |context, args| {
+                function(context, args)
+            });
+        }
+        let stack = Stack::new();
+        for library in self.libraries {
+            if let ExecutionResult::Throw(exception) =
+                vm.native_stack.load_library(&vm, &stack, &library)?
+            {
+                return Err(JVMError::UncaughtException(exception));
+            }
+        }
+        Ok(vm)
+    }
+}
+
+impl VM {
+    pub fn builder() -> VMBuilder {
+        VMBuilder::new()
+    }
+
+    // loads and initializes a class, class_name is in internal form such as java/lang/String
+    pub async fn load_class(&self, class_name: &str) -> Result<Arc<LoadedClass>, InvokeError> {
+        let class = self
+            .class_loader
+            .load_class(class_name, self)
+            .await
+            .map_err(|e| JVMError::Other(e.to_string()))?;
+        LoadedClass::initialize(Arc::clone(&class), self).await?;
+        Ok(class)
+    }
+
+    // the method a class declares or inherits with the given name and descriptor
+    pub fn find_method(
+        &self,
+        class: &Arc<LoadedClass>,
+        name: &str,
+        descriptor: &str,
+    ) -> Result<ResolvedMethod, InvokeError> {
+        let name_des = NameDes {
+            name: name.to_string(),
+            des: descriptor.to_string(),
+        };
+        // static methods of interfaces go through interface method resolution
+        if class.is_interface() {
+            return Ok(LoadedClass::resolve_interface_method(class, &name_des)?);
+        }
+        Ok(LoadedClass::resolve_method(class, &name_des)?)
+    }
+
+    // runs a method to completion, an exception it does not catch comes back with its detail
+    // message
+    async fn invoke_method(
+        &self,
+        method: &ResolvedMethod,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, InvokeError> {
+        check_args(method, &args)?;
+        let mut stack = Stack::new();
+        match self.call_method_on(&mut stack, method, args).await {
+            Err(JVMError::UncaughtException(class_name)) => {
+                let message = stack.exception_message(&class_name);
+                Err(InvokeError::Exception(JavaException {
+                    class_name,
+                    message,
+                }))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    // calls a static method, the result is None for void methods
+    pub async fn invoke_static(
+        &self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, InvokeError> {
+        let class = self.load_class(class_name).await?;
+        let method = self.find_method(&class, name, descriptor)?;
+        if !method.is_static() {
+            return Err(InvokeError::VM(JVMError::IncompatibleClassChange(format!(
+                "Expected static method {}.{}{}",
+                class_name, name, descriptor
+            ))));
+        }
+        self.invoke_method(&method, args).await
+    }
+
+    // calls an instance method on receiver, selecting the override of its class as
+    // invokevirtual does
+    pub async fn invoke_virtual(
+        &self,
+        receiver: &Value,
+        name: &str,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, InvokeError> {
+        let class = match receiver {
+            Value::Reference(Some(object)) => object.class.clone(),
+            Value::Reference(None) => {
+                return Err(InvokeError::Exception(JavaException {
+                    class_name: "java/lang/NullPointerException".to_string(),
+                    message: None,
+                }))
+            }
+            _ => None,
+        }
+        .ok_or(JVMError::NullReference)?;
+        let resolved = self.find_method(&class, name, descriptor)?;
+        if resolved.is_static() {
+            return Err(InvokeError::VM(JVMError::IncompatibleClassChange(format!(
+                "Expected instance method {}.{}{}",
+                class.class_name, name, descriptor
+            ))));
+        }
+        let method = LoadedClass::select_method(&class, &resolved)?;
+        let mut receiver_and_args = vec![receiver.clone()];
+        receiver_and_args.extend(args);
+        self.invoke_method(&method, receiver_and_args).await
+    }
+
+    // allocates an object of class_name and runs its constructor of the given descriptor
+    pub async fn new_object(
+        &self,
+        class_name: &str,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, InvokeError> {
+        let class = self.load_class(class_name).await?;
+        if class.is_interface() || class.access_flags.contains(ClassFlags::ACC_ABSTRACT) {
+            return Err(InvokeError::Exception(JavaException {
+                class_name: "java/lang/InstantiationException".to_string(),
+                message: Some(class_name.replace('/', ".")),
+            }));
+        }
+        // constructors are not inherited, only the class itself declares them
+        let name_des = NameDes {
+            name: "<init>".to_string(),
+            des: descriptor.to_string(),
+        };
+        if !class.method_indices.contains_key(&name_des) {
+            return Err(InvokeError::Exception(JavaException {
+                class_name: "java/lang/NoSuchMethodError".to_string(),
+                message: Some(format!("{}.<init>{}", class_name.replace('/', "."), descriptor)),
+            }));
+        }
+        let constructor = self.find_method(&class, "<init>", descriptor)?;
+        let object = self.allocate_object(&Stack::new(), class_name).await?;
+        let mut receiver_and_args = vec![object.clone()];
+        receiver_and_args.extend(args);
+        self.invoke_method(&constructor, receiver_and_args).await?;
+        Ok(object)
+    }
+
+    // a java.lang.String holding string
+    pub async fn new_string(&self, string: &str) -> Result<Value, InvokeError> {
+        self.to_java(string).await
+    }
+
+    // the Value of a Rust value, allocating Strings and arrays on the heap
+    pub async fn to_java<T: IntoJava>(&self, value: T) -> Result<Value, InvokeError> {
+        Ok(value.into_java(self, &Stack::new()).await?)
+    }
+}
+
+// the arguments must match the descriptor in number and kind before they become locals, a
+// long where an int goes would shift every local after it
+fn check_args(method: &ResolvedMethod, args: &[Value]) -> Result<(), JVMError> {
+    let arg_types = parse_descriptor(&method.name_des.des)
+        .map_err(JVMError::Other)?
+        .arg_types;
+    // the receiver of an instance method comes first
+    let receiver = usize::from(!method.is_static());
+    let name = format!(
+        "{}.{}{}",
+        method.class.class_name, method.name_des.name, method.name_des.des
+    );
+    if args.len() != arg_types.len() + receiver {
+        return Err(JVMError::Other(format!(
+            "{} takes {} arguments, got {}",
+            name,
+            arg_types.len(),
+            args.len().saturating_sub(receiver)
+        )));
+    }
+    for (position, (arg_type, arg)) in arg_types.iter().zip(&args[receiver..]).enumerate() {
+        let matches = match arg_type.as_bytes()[0] {
+            b'Z' | b'B' | b'C' | b'S' | b'I' => matches!(arg, Value::Int(_)),
+            b'J' => matches!(arg, Value::Long(_)),
+            b'F' => matches!(arg, Value::Float(_)),
+            b'D' => matches!(arg, Value::Double(_)),
+            _ => matches!(arg, Value::Reference(_)),
+        };
+        if !matches {
+            let found = match arg {
+                Value::Default => "no value",
+                Value::Int(_) => "an int",
+                Value::Long(_) => "a long",
+                Value::Float(_) => "a float",
+                Value::Double(_) => "a double",
+                Value::Reference(_) => "a reference",
+            };
+            return Err(JVMError::Other(format!(
+                "argument {} of {} is {}, expected {}",
+                position, name, found, arg_type
+            )));
+        }
+    }
+    Ok(())
+}
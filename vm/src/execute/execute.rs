@@ -5,6 +5,7 @@ use crate::vm::{convert_instructions, VM};
 use parser::instruction::Operation;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum ExecutionResult {
@@ -45,11 +46,12 @@ pub fn serialize_vec(values: Vec<Value>) -> Vec<SerValue> {
 }
 
 impl Stack {
-    pub async fn execute_current_frame(&mut self, vm: &VM) -> Result<(), JVMError> {
+    // runs the top frame until it returns, the result is the return value of the bottom frame
+    pub async fn execute_current_frame(&mut self, vm: &VM) -> Result<Option<Value>, JVMError> {
         if self.frames.is_empty() {
             return Err(JVMError::NoFrame);
         }
-        let mut frame_index = self.frames.len() - 1;
+        let frame_index = self.frames.len() - 1;
         //                println!("{:?}", self.frames[frame_index].method_name_des);
         //println!("{:?}", self.frames[frame_index].locals);
         while self.frames[frame_index].pc < self.frames[frame_index].code.code.len() {
@@ -87,13 +89,28 @@ impl Stack {
                             }
                         }
             */
+            // athrow pops the object it throws, it is kept for the handler
+            let thrown = match (&operation, self.frames[frame_index].operands.last()) {
+                (Operation::Athrow, Some(Value::Reference(Some(object)))) => Some(Arc::clone(object)),
+                _ => None,
+            };
             let stack_snapshot = self.clone();
+            #[cfg(feature = "jit")]
+            let pc_before = self.frames[frame_index].pc;
             match self.frames[frame_index]
                 .execute_instruction(&operation, &stack_snapshot, vm)
                 .await?
             {
                 ExecutionResult::Continue => {
                     self.frames[frame_index].pc += 1;
+                    #[cfg(feature = "jit")]
+                    if self.frames[frame_index].pc <= pc_before {
+                        let frame = &self.frames[frame_index];
+                        vm.jit
+                            .lock()
+                            .unwrap()
+                            .record_backedge(&frame.class.class_name, &frame.method_name_des);
+                    }
                 }
                 ExecutionResult::Invoke(new_frame) => {
                     {
@@ -110,10 +127,27 @@ impl Stack {
                             }
                         }
                     }
-                    self.push_frame(new_frame)?;
+                    match self.push_frame(new_frame) {
+                        Ok(()) => {}
+                        // the callee does not fit, the caller gets a StackOverflowError
+                        Err(JVMError::StackOverflow) => {
+                            let exception = "java/lang/StackOverflowError".to_string();
+                            self.exception = None;
+                            self.catch(frame_index, exception, vm).await?;
+                            continue;
+                        }
+                        Err(error) => return Err(error),
+                    }
                     let fut = Box::pin(self.execute_current_frame(vm));
-                    fut.await?;
-                    self.frames[frame_index].pc += 1;
+                    match fut.await {
+                        Ok(_) => self.frames[frame_index].pc += 1,
+                        // the callee did not catch it, its frames are gone and this one gets a go
+                        Err(JVMError::UncaughtException(exception)) => {
+                            self.frames.truncate(frame_index + 1);
+                            self.catch(frame_index, exception, vm).await?;
+                        }
+                        Err(error) => return Err(error),
+                    }
                 }
                 ExecutionResult::Return(return_value) => {
                     if frame_index == 0 {
@@ -138,7 +172,7 @@ impl Stack {
                                 }
                             }
                         }
-                        return Ok(());
+                        return Ok(return_value);
                     }
                     {
                         let flag = GLOBAL_BOOL.lock().unwrap();
@@ -158,38 +192,13 @@ impl Stack {
                     if let Some(value) = return_value {
                         self.frames[frame_index - 1].push(value)?;
                     }
-                    return Ok(());
+                    return Ok(None);
                 }
                 ExecutionResult::Throw(exception) => {
-                    if let Some(handler_pc) = self.frames[frame_index]
-                        .find_exception_handler(&exception, vm)
-                        .await
-                    {
-                        self.frames[frame_index].operands.clear();
-                        self.frames[frame_index].push(Value::Reference(None))?;
-                        self.frames[frame_index].pc = self.frames[frame_index]
-                            .code
-                            .get_index_at_address(handler_pc as u32);
-                    } else if frame_index > 0 {
-                        self.pop_frame()?;
-                        frame_index -= 1;
-                        if let Some(handler_pc) = self.frames[frame_index]
-                            .find_exception_handler(&exception, vm)
-                            .await
-                        {
-                            self.frames[frame_index].operands.clear();
-                            self.frames[frame_index].push(Value::Reference(None))?;
-                            self.frames[frame_index].pc = self.frames[frame_index]
-                                .code
-                                .get_index_at_address(handler_pc as u32);
-                        } else {
-                            if frame_index == 0 {
-                                return Err(JVMError::UncaughtException(exception));
-                            }
-                        }
-                    } else {
-                        return Err(JVMError::UncaughtException(exception));
-                    }
+                    // the object a native method threw comes back through the JNI state
+                    self.exception =
+                        thrown.or_else(|| vm.native_stack.jni.take_thrown(&exception));
+                    self.catch(frame_index, exception, vm).await?;
                     /*
                     let exception_obj = match &exception {
                         Value::Reference(Some(obj)) => obj,
@@ -223,7 +232,35 @@ impl Stack {
                 }
             }
         }
-        Ok(())
+        Ok(None)
+    }
+
+    // continues at the handler of the frame covering the exception, an exception the frame
+    // does not handle goes back to its caller as JVMError::UncaughtException
+    async fn catch(
+        &mut self,
+        frame_index: usize,
+        exception: String,
+        vm: &VM,
+    ) -> Result<(), JVMError> {
+        let frame = &mut self.frames[frame_index];
+        match frame.find_exception_handler(&exception, vm).await {
+            Some(handler_pc) => {
+                self.backtrace.clear();
+                // exceptions the VM raised by name reach the handler as null
+                let object = self.take_exception(&exception);
+                let frame = &mut self.frames[frame_index];
+                frame.operands.clear();
+                frame.push(Value::Reference(object))?;
+                frame.pc = frame.code.get_index_at_address(handler_pc as u32);
+                Ok(())
+            }
+            None => {
+                let element = frame.trace_element();
+                self.backtrace.push(element);
+                Err(JVMError::UncaughtException(exception))
+            }
+        }
     }
 }
 impl Frame {
@@ -235,11 +272,11 @@ impl Frame {
     ) -> Result<ExecutionResult, JVMError> {
         let return_op_type = match operation {
             // Load Instructions
-            Operation::Iload(index) => self.iload(*index)?,
-            Operation::Lload(index) => self.lload(*index)?,
-            Operation::Fload(index) => self.fload(*index)?,
-            Operation::Dload(index) => self.dload(*index)?,
-            Operation::Aload(index) => self.aload(*index)?,
+            Operation::Iload(index) => self.iload((*index).into())?,
+            Operation::Lload(index) => self.lload((*index).into())?,
+            Operation::Fload(index) => self.fload((*index).into())?,
+            Operation::Dload(index) => self.dload((*index).into())?,
+            Operation::Aload(index) => self.aload((*index).into())?,
             Operation::Iload0 => self.iload(0)?,
             Operation::Iload1 => self.iload(1)?,
             Operation::Iload2 => self.iload(2)?,
@@ -262,11 +299,11 @@ impl Frame {
             Operation::Aload3 => self.aload(3)?,
 
             // Store Instructions
-            Operation::Istore(index) => self.istore(*index)?,
-            Operation::Lstore(index) => self.lstore(*index)?,
-            Operation::Fstore(index) => self.fstore(*index)?,
-            Operation::Dstore(index) => self.dstore(*index)?,
-            Operation::Astore(index) => self.astore(*index)?,
+            Operation::Istore(index) => self.istore((*index).into())?,
+            Operation::Lstore(index) => self.lstore((*index).into())?,
+            Operation::Fstore(index) => self.fstore((*index).into())?,
+            Operation::Dstore(index) => self.dstore((*index).into())?,
+            Operation::Astore(index) => self.astore((*index).into())?,
             Operation::Istore0 => self.istore(0)?,
             Operation::Istore1 => self.istore(1)?,
             Operation::Istore2 => self.istore(2)?,
@@ -338,7 +375,7 @@ impl Frame {
             Operation::Idiv => self.idiv(vm).await?,
             Operation::Irem => self.irem()?,
             Operation::Ineg => self.ineg()?,
-            Operation::Iinc(index, value) => self.iinc(*index, *value as i8)?,
+            Operation::Iinc(index, value) => self.iinc((*index).into(), (*value as i8).into())?,
 
             // Long Arithmetic
             Operation::Ladd => self.ladd()?,
@@ -347,6 +384,7 @@ impl Frame {
             Operation::Ldiv => self.ldiv(vm).await?,
             Operation::Lrem => self.lrem()?,
             Operation::Lneg => self.lneg()?,
+            Operation::Lcmp => self.lcmp()?,
 
             // Float Arithmetic
             Operation::Fadd => self.fadd()?,
@@ -497,12 +535,14 @@ impl Frame {
             Operation::Dup2 => self.dup2()?,
             Operation::Dup2x1 => self.dup2_x1()?,
             Operation::Dup2x2 => self.dup2_x2()?,
+            Operation::Swap => self.swap()?,
+            Operation::Wide(opcode, index, constant) => self.wide(*opcode, *index, *constant)?,
             Operation::Putfield(index1, index2) => {
                 self.putfield(((*index1 as u16) << 8) | *index2 as u16, vm)
                     .await?
             }
             Operation::Getfield(index1, index2) => {
-                self.getfield(((*index1 as u16) << 8) | *index2 as u16)
+                self.getfield(((*index1 as u16) << 8) | *index2 as u16, vm)
                     .await?
             }
             Operation::Putstatic(index1, index2) => {
@@ -516,19 +556,19 @@ impl Frame {
 
             //Invoke statements
             Operation::Invokestatic(index1, index2) => {
-                self.invokestatic(((*index1 as u16) << 8) | *index2 as u16, vm)
+                self.invokestatic(((*index1 as u16) << 8) | *index2 as u16, stack, vm)
                     .await?
             }
             Operation::Invokespecial(index1, index2) => {
-                self.invokespecial(((*index1 as u16) << 8) | *index2 as u16, vm)
+                self.invokespecial(((*index1 as u16) << 8) | *index2 as u16, stack, vm)
                     .await?
             }
             Operation::Invokevirtual(index1, index2) => {
-                self.invokevirtual(((*index1 as u16) << 8) | *index2 as u16, vm)
+                self.invokevirtual(((*index1 as u16) << 8) | *index2 as u16, stack, vm)
                     .await?
             }
             Operation::Invokeinterface(index1, index2, _, _) => {
-                self.invokeinterface(((*index1 as u16) << 8) | *index2 as u16, vm)
+                self.invokeinterface(((*index1 as u16) << 8) | *index2 as u16, stack, vm)
                     .await?
             }
 
@@ -571,13 +611,17 @@ impl Frame {
                 ExecutionResult::Continue
             }
 
+            //monitor
+            Operation::Monitorenter => self.monitor_enter()?,
+            Operation::Monitorexit => self.monitor_exit()?,
+
             //check cast and instance of
             Operation::Checkcast(index1, index2) => {
                 self.checkcast(((*index1 as u16) << 8) | *index2 as u16, vm)
                     .await?
             }
             Operation::Instanceof(index1, index2) => {
-                self.checkcast(((*index1 as u16) << 8) | *index2 as u16, vm)
+                self.instanceof(((*index1 as u16) << 8) | *index2 as u16, vm)
                     .await?
             }
             _ => {
@@ -56,56 +56,64 @@ impl Frame {
                 })
             }
         };
+        let element_type = if element_type.starts_with('[') {
+            element_type.to_string()
+        } else {
+            format!("L{};", element_type)
+        };
         let fut = Box::pin(vm.allocate_array(stack, &element_type, length));
         let array_ref = fut.await?;
         self.push(array_ref)?;
         Ok(ExecutionResult::Continue)
     }
 
+    // xaload/xastore must match the array's storage, baload/bastore serve both byte[] and boolean[]
+    fn check_array_type(array: &Object, data_type: &str) -> Result<(), JVMError> {
+        match &array.kind {
+            ObjectKind::ArrayInstance {
+                elements,
+                element_type,
+                ..
+            } => {
+                if elements.borrow().type_name() == data_type {
+                    Ok(())
+                } else {
+                    Err(JVMError::TypeMismatch {
+                        expected: if data_type == "L" {
+                            "L... or [...".to_string()
+                        } else {
+                            data_type.to_string()
+                        },
+                        found: element_type.clone(),
+                    })
+                }
+            }
+            _ => Err(JVMError::TypeMismatch {
+                expected: data_type.to_string(),
+                found: "non-array".to_string(),
+            }),
+        }
+    }
+
     pub async fn array_load(
         &mut self,
         data_type: String,
-        vm: &VM,
+        _vm: &VM,
     ) -> Result<ExecutionResult, JVMError> {
-        let index = self.pop_expect_int()? as usize;
+        let index = self.pop_expect_int()?;
         let array_ref = self.pop_expect_reference()?;
         match array_ref {
             Some(array) => {
-                let is_valid = match &array.kind {
-                    ObjectKind::ArrayInstance { element_type, .. } => {
-                        if data_type == "L" {
-                            element_type.starts_with("L") || element_type.starts_with("[")
-                        } else {
-                            *element_type == data_type
-                        }
-                    }
-                    _ => false,
-                };
-                if !is_valid {
-                    let found = match &array.kind {
-                        ObjectKind::ArrayInstance { element_type, .. } => element_type.clone(),
-                        _ => "non-array".to_string(),
-                    };
-                    return Err(JVMError::TypeMismatch {
-                        expected: if data_type == "L" {
-                            "L... or [...".to_string()
-                        } else {
-                            data_type
-                        },
-                        found,
-                    });
-                }
-                let value = match array.get_element(index) {
-                    Ok(sth) => sth,
+                Self::check_array_type(&array, &data_type)?;
+                let value = match array.get_element(index as usize) {
+                    Ok(value) if index >= 0 => value,
                     _ => {
-                        //let fut = Box::pin(vm.allocate_object("/java/lang/ArrayIndexOutOfBoundsException"));
-                        //let exception = fut.await?;
                         return Ok(ExecutionResult::Throw(
                             "java/lang/ArrayIndexOutOfBoundsException".to_string(),
                         ));
                     }
                 };
-                self.push(value.clone())?;
+                self.push(value)?;
                 Ok(ExecutionResult::Continue)
             }
             None => Err(JVMError::NullReference),
@@ -114,109 +122,57 @@ impl Frame {
 
     pub async fn array_store(
         &mut self,
-        _data_type: String,
+        data_type: String,
         vm: &VM,
     ) -> Result<ExecutionResult, JVMError> {
-        let value = match _data_type.as_str() {
-            "I" => self.pop_expect_int().map(Value::Int)?,
+        let value = match data_type.as_str() {
+            "I" | "B" | "C" | "S" => self.pop_expect_int().map(Value::Int)?,
             "J" => self.pop_expect_long().map(Value::Long)?,
             "F" => self.pop_expect_float().map(Value::Float)?,
             "D" => self.pop_expect_double().map(Value::Double)?,
             "L" => self.pop_expect_reference().map(Value::Reference)?,
-            "B" => self.pop_expect_int().map(Value::Int)?,
-            "C" => self.pop_expect_int().map(Value::Int)?,
-            "S" => self.pop_expect_int().map(Value::Int)?,
             _ => {
                 return Err(JVMError::Other(format!(
                     "Unsupported array data type: {}",
-                    _data_type
+                    data_type
                 )))
             }
         };
-        let index = self.pop_expect_int()? as usize;
+        let index = self.pop_expect_int()?;
         let array_ref = self.pop_expect_reference()?;
 
         match array_ref {
             Some(array) => {
-                let is_valid = match &array.kind {
-                    ObjectKind::ArrayInstance { element_type, .. } => {
-                        if _data_type == "L" {
-                            element_type.starts_with("L") || element_type.starts_with("[")
-                        } else {
-                            *element_type == _data_type
+                Self::check_array_type(&array, &data_type)?;
+
+                if let (Value::Reference(Some(ref_obj)), Some(array_class)) =
+                    (&value, array.class.as_ref())
+                {
+                    if let (Some(component), Some(ref_class)) =
+                        (&array_class.component_class, ref_obj.class.as_ref())
+                    {
+                        if !ref_class.is_subtype_of(component) {
+                            return Ok(ExecutionResult::Throw(
+                                "java/lang/ArrayStoreException".to_string(),
+                            ));
                         }
                     }
-                    _ => false,
-                };
-                if !is_valid {
-                    let found = match &array.kind {
-                        ObjectKind::ArrayInstance { element_type, .. } => element_type.clone(),
-                        _ => "non-array".to_string(),
-                    };
-                    return Err(JVMError::TypeMismatch {
-                        expected: if _data_type == "L" {
-                            "L... or [...".to_string()
-                        } else {
-                            _data_type.clone()
-                        },
-                        found,
-                    });
                 }
 
-                match (_data_type.as_str(), &value) {
-                    ("B", Value::Int(v)) if *v < i8::MIN as i32 || *v > i8::MAX as i32 => {
-                        return Err(JVMError::TypeMismatch {
-                            expected: "B (byte)".to_string(),
-                            found: "out of range".to_string(),
-                        });
-                    }
-                    ("C", Value::Int(v)) if *v < 0 || *v > u16::MAX as i32 => {
-                        return Err(JVMError::TypeMismatch {
-                            expected: "C (char)".to_string(),
-                            found: "out of range".to_string(),
-                        });
-                    }
-                    ("S", Value::Int(v)) if *v < i16::MIN as i32 || *v > i16::MAX as i32 => {
-                        return Err(JVMError::TypeMismatch {
-                            expected: "S (short)".to_string(),
-                            found: "out of range".to_string(),
-                        });
-                    }
-                    ("L", Value::Reference(Some(ref_obj))) => {
-                        if let ObjectKind::ArrayInstance { element_type, .. } = &array.kind {
-                            let expected_type = element_type
-                                .strip_prefix("L")
-                                .and_then(|s| s.strip_suffix(";"))
-                                .unwrap_or(element_type);
-                            if let Some(ref_class) = ref_obj.class.as_ref() {
-                                if !self.is_compatible_class(ref_class, expected_type) {
-                                    return Err(JVMError::TypeMismatch {
-                                        expected: expected_type.to_string(),
-                                        found: ref_class.class_name.clone(),
-                                    });
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
+                if index < 0 {
+                    return Ok(ExecutionResult::Throw(
+                        "java/lang/ArrayIndexOutOfBoundsException".to_string(),
+                    ));
                 }
-                /*
-                                array.set_element(index, value).map_err(|_| {
-                                        let fut = Box::pin(vm.allocate_object("/java/lang/ArrayIndexOutOfBoundsException"));
-                                    let exception = tokio::runtime::Handle::current().block_on(fut)?;
-                                        return Ok(ExecutionResult::Throw(exception));
-                                });
-                */
-                let _ = match array.set_element(index, value) {
-                    Ok(()) => Ok::<ExecutionResult, JVMError>(ExecutionResult::Continue),
-                    Err(_) => {
-                        //let fut = Box::pin(vm.allocate_object("/java/lang/ArrayIndexOutOfBoundsException"));
-                        //let exception = fut.await?;
+                match array.set_element(index as usize, value) {
+                    Ok(()) => {}
+                    Err(JVMError::IndexOutOfBounds { .. }) => {
                         return Ok(ExecutionResult::Throw(
                             "java/lang/ArrayIndexOutOfBoundsException".to_string(),
                         ));
                     }
-                };
+                    Err(e) => return Err(e),
+                }
                 vm.memory_snap().await;
                 Ok(ExecutionResult::Continue)
             }
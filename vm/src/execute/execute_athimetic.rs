@@ -29,7 +29,7 @@ impl Frame {
         let v2 = self.pop_expect_int()?;
         let v1 = self.pop_expect_int()?;
 
-        let result = v1.checked_add(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_add(v2);
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -39,7 +39,7 @@ impl Frame {
         let v2 = self.pop_expect_int()?;
         let v1 = self.pop_expect_int()?;
 
-        let result = v1.checked_sub(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_sub(v2);
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -49,7 +49,7 @@ impl Frame {
         let v2 = self.pop_expect_int()?;
         let v1 = self.pop_expect_int()?;
 
-        let result = v1.checked_mul(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_mul(v2);
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -65,7 +65,7 @@ impl Frame {
             return Ok(ExecutionResult::Throw("java/lang/ArithmeticException".to_string()));
             //return Err(JVMError::DivisionByZero);
         }
-        let result = v1.checked_div(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_div(v2);
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -76,9 +76,9 @@ impl Frame {
         let v1 = self.pop_expect_int()?;
 
         if v2 == 0 {
-            return Err(JVMError::DivisionByZero);
+            return Ok(ExecutionResult::Throw("java/lang/ArithmeticException".to_string()));
         }
-        let result = v1.checked_rem(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_rem(v2);
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -87,12 +87,12 @@ impl Frame {
         self.ensure_operands(1)?;
         let v = self.pop_expect_int()?;
 
-        let result = v.checked_neg().ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v.wrapping_neg();
         self.push(Value::Int(result))?;
         Ok(ExecutionResult::Continue)
     }
 
-    pub fn iinc(&mut self, index: u8, value: i8) -> Result<ExecutionResult, JVMError> {
+    pub fn iinc(&mut self, index: u16, value: i16) -> Result<ExecutionResult, JVMError> {
         let current = match self.get_local(index as usize).cloned() {
             Some(Value::Int(val)) => val,
             Some(other) => {
@@ -109,8 +109,7 @@ impl Frame {
         };
 
         let result = current
-            .checked_add(value as i32)
-            .ok_or(JVMError::ArithmeticOverflow)?;
+            .wrapping_add(value as i32);
         self.set_local(index as usize, Value::Int(result));
         Ok(ExecutionResult::Continue)
     }
@@ -129,7 +128,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let v2 = self.pop_expect_long()?;
         let v1 = self.pop_expect_long()?;
-        let result = v1.checked_add(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_add(v2);
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -138,7 +137,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let v2 = self.pop_expect_long()?;
         let v1 = self.pop_expect_long()?;
-        let result = v1.checked_sub(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_sub(v2);
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -147,7 +146,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let v2 = self.pop_expect_long()?;
         let v1 = self.pop_expect_long()?;
-        let result = v1.checked_mul(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_mul(v2);
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -162,7 +161,7 @@ impl Frame {
             return Ok(ExecutionResult::Throw("java/lang/ArithmeticException".to_string()));
             //return Err(JVMError::DivisionByZero);
         }
-        let result = v1.checked_div(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_div(v2);
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -172,9 +171,9 @@ impl Frame {
         let v2 = self.pop_expect_long()?;
         let v1 = self.pop_expect_long()?;
         if v2 == 0 {
-            return Err(JVMError::DivisionByZero);
+            return Ok(ExecutionResult::Throw("java/lang/ArithmeticException".to_string()));
         }
-        let result = v1.checked_rem(v2).ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v1.wrapping_rem(v2);
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
@@ -182,11 +181,19 @@ impl Frame {
     pub fn lneg(&mut self) -> Result<ExecutionResult, JVMError> {
         self.ensure_operands(1)?;
         let v = self.pop_expect_long()?;
-        let result = v.checked_neg().ok_or(JVMError::ArithmeticOverflow)?;
+        let result = v.wrapping_neg();
         self.push(Value::Long(result))?;
         Ok(ExecutionResult::Continue)
     }
 
+    pub fn lcmp(&mut self) -> Result<ExecutionResult, JVMError> {
+        self.ensure_operands(2)?;
+        let v2 = self.pop_expect_long()?;
+        let v1 = self.pop_expect_long()?;
+        self.push(Value::Int(v1.cmp(&v2) as i32))?;
+        Ok(ExecutionResult::Continue)
+    }
+
     pub fn pop_expect_float(&mut self) -> Result<f32, JVMError> {
         match self.pop()? {
             Value::Float(v) => Ok(v),
@@ -239,9 +246,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let v2 = self.pop_expect_float()?;
         let v1 = self.pop_expect_float()?;
-        if v2 == 0.0 {
-            return Err(JVMError::DivisionByZero);
-        }
+        // NaN for a zero divisor, only irem and lrem throw
         self.push(Value::Float(v1 % v2))?;
         Ok(ExecutionResult::Continue)
     }
@@ -254,8 +259,8 @@ impl Frame {
     }
 
     pub fn fcmpg(&mut self) -> Result<ExecutionResult, JVMError> {
-        let value1 = self.pop_expect_float()?;
         let value2 = self.pop_expect_float()?;
+        let value1 = self.pop_expect_float()?;
         let result = if value1.is_nan() || value2.is_nan() {
             1
         } else if value1 > value2 {
@@ -270,8 +275,8 @@ impl Frame {
     }
 
     pub fn fcmpl(&mut self) -> Result<ExecutionResult, JVMError> {
-        let value1 = self.pop_expect_float()?;
         let value2 = self.pop_expect_float()?;
+        let value1 = self.pop_expect_float()?;
         let result = if value1.is_nan() || value2.is_nan() {
             -1
         } else if value1 > value2 {
@@ -337,9 +342,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let v2 = self.pop_expect_double()?;
         let v1 = self.pop_expect_double()?;
-        if v2 == 0.0 {
-            return Err(JVMError::DivisionByZero);
-        }
+        // NaN for a zero divisor, only irem and lrem throw
         self.push(Value::Double(v1 % v2))?;
         Ok(ExecutionResult::Continue)
     }
@@ -352,8 +355,8 @@ impl Frame {
     }
 
     pub fn dcmpg(&mut self) -> Result<ExecutionResult, JVMError> {
-        let value1 = self.pop_expect_double()?;
         let value2 = self.pop_expect_double()?;
+        let value1 = self.pop_expect_double()?;
         let result = if value1.is_nan() || value2.is_nan() {
             1
         } else if value1 > value2 {
@@ -368,8 +371,8 @@ impl Frame {
     }
 
     pub fn dcmpl(&mut self) -> Result<ExecutionResult, JVMError> {
-        let value1 = self.pop_expect_double()?;
         let value2 = self.pop_expect_double()?;
+        let value1 = self.pop_expect_double()?;
         let result = if value1.is_nan() || value2.is_nan() {
             -1
         } else if value1 > value2 {
@@ -383,3 +386,69 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_loader::loaded_class::{LoadedClass, NameDes};
+    use parser::access_flag::ClassFlags;
+    use parser::attribute::Code;
+    use parser::constant_pool::ConstantPool;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // a frame with room for two operands and nothing to run
+    fn frame() -> Frame {
+        let class = LoadedClass::new(
+            "Test".to_string(),
+            None,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Arc::new(ConstantPool::default()),
+            ClassFlags::ACC_PUBLIC,
+        );
+        let code = Code {
+            max_stack: 2,
+            max_locals: 0,
+            code: Vec::new(),
+            exception_table: Vec::new(),
+            attributes: Vec::new(),
+            address_to_index: HashMap::new(),
+        };
+        let name_des = NameDes {
+            name: "test".to_string(),
+            des: "()V".to_string(),
+        };
+        Frame::new(Arc::new(class), &name_des, Arc::new(code))
+    }
+
+    #[test]
+    fn frem_by_zero_is_nan() {
+        let mut frame = frame();
+        frame.push(Value::Float(5.0)).unwrap();
+        frame.push(Value::Float(0.0)).unwrap();
+        assert!(matches!(frame.frem(), Ok(ExecutionResult::Continue)));
+        assert!(frame.pop_expect_float().unwrap().is_nan());
+    }
+
+    #[test]
+    fn drem_by_zero_is_nan() {
+        let mut frame = frame();
+        frame.push(Value::Double(5.0)).unwrap();
+        frame.push(Value::Double(-0.0)).unwrap();
+        assert!(matches!(frame.drem(), Ok(ExecutionResult::Continue)));
+        assert!(frame.pop_expect_double().unwrap().is_nan());
+    }
+
+    #[test]
+    fn irem_by_zero_throws() {
+        let mut frame = frame();
+        frame.push(Value::Int(5)).unwrap();
+        frame.push(Value::Int(0)).unwrap();
+        assert!(matches!(
+            frame.irem(),
+            Ok(ExecutionResult::Throw(exception)) if exception == "java/lang/ArithmeticException"
+        ));
+    }
+}
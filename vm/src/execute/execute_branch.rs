@@ -169,11 +169,20 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
+    // reference identity, two nulls are the same reference
+    fn same_reference(ref1: &Option<Arc<Object>>, ref2: &Option<Arc<Object>>) -> bool {
+        match (ref1, ref2) {
+            (Some(obj1), Some(obj2)) => Arc::ptr_eq(obj1, obj2),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
     pub fn if_acmpeq(&mut self, offset: i16) -> Result<ExecutionResult, JVMError> {
         self.ensure_operands(2)?;
         let ref2 = self.pop_expect_reference()?;
         let ref1 = self.pop_expect_reference()?;
-        if std::ptr::eq(&ref1, &ref2) {
+        if Self::same_reference(&ref1, &ref2) {
             self.branch_16(offset)?;
         }
         Ok(ExecutionResult::Continue)
@@ -183,7 +192,7 @@ impl Frame {
         self.ensure_operands(2)?;
         let ref2 = self.pop_expect_reference()?;
         let ref1 = self.pop_expect_reference()?;
-        if !std::ptr::eq(&ref1, &ref2) {
+        if !Self::same_reference(&ref1, &ref2) {
             self.branch_16(offset)?;
         }
         Ok(ExecutionResult::Continue)
@@ -1,9 +1,8 @@
 use super::execute::ExecutionResult;
-use crate::class_loader::loaded_class::LoadedClass;
 use crate::jvm_error::JVMError;
+use crate::object::Object;
 use crate::runtime::*;
 use crate::vm::VM;
-use std::sync::Arc;
 
 impl Frame {
     pub async fn checkcast(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
@@ -18,22 +17,14 @@ impl Frame {
                     .constant_pool
                     .get_underlying_string_from_constant_class_info_index(index)
                     .ok_or_else(|| JVMError::Other(format!("Invalid class index: {}", index)))?;
-                let fut = Box::pin(vm.class_loader.load_class(&target_class_name, vm));
-                let target_class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
 
-                let obj_class = obj
-                    .class
-                    .as_ref()
-                    .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
-
-                if self.is_assignable(obj_class, &target_class) {
+                if self.is_instance_of(&obj, target_class_name, vm).await? {
                     self.push(Value::Reference(Some(obj)))?;
                     Ok(ExecutionResult::Continue)
                 } else {
-                    Err(JVMError::ClassCastException(format!(
-                        "Cannot cast {} to {}",
-                        obj_class.class_name, target_class_name
-                    )))
+                    Ok(ExecutionResult::Throw(
+                        "java/lang/ClassCastException".to_string(),
+                    ))
                 }
             }
             _ => Err(JVMError::Other(
@@ -54,15 +45,8 @@ impl Frame {
                     .constant_pool
                     .get_underlying_string_from_constant_class_info_index(index)
                     .ok_or_else(|| JVMError::Other(format!("Invalid class index: {}", index)))?;
-                let fut = Box::pin(vm.class_loader.load_class(&target_class_name, vm));
-                let target_class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
-
-                let obj_class = obj
-                    .class
-                    .as_ref()
-                    .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
 
-                let is_instance = self.is_assignable(obj_class, &target_class);
+                let is_instance = self.is_instance_of(&obj, target_class_name, vm).await?;
                 self.push(Value::Int(if is_instance { 1 } else { 0 }))?;
                 Ok(ExecutionResult::Continue)
             }
@@ -72,17 +56,18 @@ impl Frame {
         }
     }
 
-    fn is_assignable(&self, from_class: &Arc<LoadedClass>, to_class: &Arc<LoadedClass>) -> bool {
-        if from_class.class_name == to_class.class_name {
-            return true;
-        }
-        let mut current = from_class.super_class.clone();
-        while let Some(super_class) = current {
-            if super_class.class_name == to_class.class_name {
-                return true;
-            }
-            current = super_class.super_class.clone();
-        }
-        false
+    pub async fn is_instance_of(
+        &self,
+        obj: &Object,
+        target_name: &str,
+        vm: &VM,
+    ) -> Result<bool, JVMError> {
+        let obj_class = obj
+            .class
+            .as_ref()
+            .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
+        let fut = Box::pin(vm.class_loader.load_class(target_name, vm));
+        let target_class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        Ok(obj_class.is_subtype_of(&target_class))
     }
 }
@@ -1,9 +1,10 @@
 use super::execute::ExecutionResult;
-use crate::class_loader::loaded_class::LoadedClass;
+use crate::class_loader::loaded_class::{FieldSlot, LoadedClass, NameDes, ResolvedField};
 use crate::jvm_error::JVMError;
 use crate::runtime::*;
 use crate::vm::VM;
 use parser::constant_pool::{ConstantFieldRefInfo, ConstantInfo, ConstantNameAndTypeInfo};
+use std::sync::Arc;
 
 impl Frame {
     fn pop_expect(&mut self, expected: &'static str) -> Result<Value, JVMError> {
@@ -25,14 +26,14 @@ impl Frame {
         actual_class: &LoadedClass,
         expected_class_name: &str,
     ) -> bool {
-        let mut current = Some(actual_class);
-        while let Some(cls) = current {
-            if cls.class_name == expected_class_name {
-                return true;
-            }
-            current = cls.super_class.as_deref();
+        if actual_class.class_name == expected_class_name {
+            return true;
         }
-        false
+        actual_class
+            .interfaces
+            .iter()
+            .chain(actual_class.super_class.iter())
+            .any(|cls| self.is_compatible_class(cls, expected_class_name))
     }
 
     fn get_name_and_type(&self, index: u16) -> Result<(String, String), JVMError> {
@@ -79,7 +80,10 @@ impl Frame {
         Ok((name, type_))
     }
 
-    pub async fn putfield(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
+    async fn resolve_field_ref(&self, index: u16, vm: &VM) -> Result<Arc<ResolvedField>, JVMError> {
+        if let Some(resolved) = self.class.field_ref_cache.lock().unwrap().get(&index) {
+            return Ok(Arc::clone(resolved));
+        }
         let cp_entry = self.constant_pool.get_entry(index).ok_or_else(|| {
             JVMError::ConstantPoolIndexOutOfBounds {
                 index,
@@ -98,7 +102,7 @@ impl Frame {
                 })
             }
         };
-        let expected_class_name = self
+        let class_name = self
             .constant_pool
             .get_underlying_string_from_constant_class_info_index(*class_index)
             .ok_or_else(|| JVMError::InvalidConstantType {
@@ -106,34 +110,82 @@ impl Frame {
                 found: "missing name",
             })?
             .to_string();
-        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
-        let expected_type = descriptor.split(';').next().unwrap_or(&descriptor);
-        let value = match expected_type {
-            "Z" | "B" | "C" | "S" | "I" => self.pop_expect("I")?,
-            "J" => self.pop_expect("J")?,
-            "F" => self.pop_expect("F")?,
-            "D" => self.pop_expect("D")?,
-            t if t.starts_with("L") || t.starts_with("[") => self.pop_expect("L")?,
-            _ => {
-                return Err(JVMError::Other(format!(
-                    "Unsupported field descriptor: {}",
-                    descriptor
-                )))
+        let (name, des) = self.get_name_and_type(*name_and_type_index)?;
+        let name_des = NameDes { name, des };
+
+        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
+        let class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        let slot = LoadedClass::resolve_field(&class, &name_des).ok_or_else(|| {
+            JVMError::FieldNotFound {
+                class: class_name.clone(),
+                name: name_des.name.clone(),
+                descriptor: name_des.des.clone(),
             }
-        };
+        })?;
+        let resolved = Arc::new(ResolvedField {
+            class_name,
+            name_des,
+            slot,
+        });
+        self.class
+            .field_ref_cache
+            .lock()
+            .unwrap()
+            .insert(index, Arc::clone(&resolved));
+        Ok(resolved)
+    }
+
+    fn pop_field_value(&mut self, descriptor: &str) -> Result<Value, JVMError> {
+        match descriptor {
+            "Z" | "B" | "C" | "S" | "I" => self.pop_expect("I"),
+            "J" => self.pop_expect("J"),
+            "F" => self.pop_expect("F"),
+            "D" => self.pop_expect("D"),
+            t if t.starts_with("L") || t.starts_with("[") => self.pop_expect("L"),
+            _ => Err(JVMError::Other(format!(
+                "Unsupported field descriptor: {}",
+                descriptor
+            ))),
+        }
+    }
+
+    fn instance_slot(field: &ResolvedField) -> Result<usize, JVMError> {
+        match field.slot {
+            FieldSlot::Instance(slot) => Ok(slot),
+            FieldSlot::Static(..) => Err(JVMError::IncompatibleClassChange(format!(
+                "Expected non-static field {}.{}",
+                field.class_name, field.name_des.name
+            ))),
+        }
+    }
+
+    fn static_slot(field: &ResolvedField) -> Result<(&Arc<LoadedClass>, usize), JVMError> {
+        match &field.slot {
+            FieldSlot::Static(class, index) => Ok((class, *index)),
+            FieldSlot::Instance(_) => Err(JVMError::IncompatibleClassChange(format!(
+                "Expected static field {}.{}",
+                field.class_name, field.name_des.name
+            ))),
+        }
+    }
+
+    pub async fn putfield(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
+        let field = self.resolve_field_ref(index, vm).await?;
+        let slot = Self::instance_slot(&field)?;
+        let value = self.pop_field_value(&field.name_des.des)?;
         let obj_ref = self.pop_expect("L")?;
         if let Value::Reference(Some(obj)) = &obj_ref {
             let actual_class = obj
                 .class
                 .as_ref()
                 .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
-            if !self.is_compatible_class(actual_class, &expected_class_name) {
+            if !self.is_compatible_class(actual_class, &field.class_name) {
                 return Err(JVMError::IncompatibleClass {
-                    expected: expected_class_name.to_string(),
+                    expected: field.class_name.clone(),
                     found: actual_class.class_name.clone(),
                 });
             }
-            obj.set_field(&name, value)?;
+            obj.set_field_at(slot, value)?;
             vm.memory_snap().await;
             Ok(ExecutionResult::Continue)
         } else {
@@ -141,49 +193,23 @@ impl Frame {
         }
     }
 
-    pub async fn getfield(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
-        let cp_entry = self.constant_pool.get_entry(index).ok_or_else(|| {
-            JVMError::ConstantPoolIndexOutOfBounds {
-                index,
-                max: self.constant_pool.get_len(),
-            }
-        })?;
-        let (class_index, name_and_type_index) = match cp_entry {
-            ConstantInfo::FieldRef(ConstantFieldRefInfo {
-                class_index,
-                name_and_type_index,
-            }) => (class_index, name_and_type_index),
-            _ => {
-                return Err(JVMError::InvalidConstantType {
-                    expected: "Fieldref",
-                    found: "other",
-                })
-            }
-        };
-        let expected_class_name = self
-            .constant_pool
-            .get_underlying_string_from_constant_class_info_index(*class_index)
-            .ok_or_else(|| JVMError::InvalidConstantType {
-                expected: "Class",
-                found: "missing name",
-            })?
-            .to_string();
-        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
-
+    pub async fn getfield(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
+        let field = self.resolve_field_ref(index, vm).await?;
+        let slot = Self::instance_slot(&field)?;
         let obj_ref = self.pop_expect_reference()?;
         if let Some(obj) = &obj_ref {
             let actual_class = obj
                 .class
                 .as_ref()
                 .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
-            if !self.is_compatible_class(actual_class, &expected_class_name) {
+            if !self.is_compatible_class(actual_class, &field.class_name) {
                 return Err(JVMError::IncompatibleClass {
-                    expected: expected_class_name.to_string(),
+                    expected: field.class_name.clone(),
                     found: actual_class.class_name.clone(),
                 });
             }
-            let value = obj.get_field(&name)?;
-            self.push(value.clone())?;
+            let value = obj.get_field_at(slot)?;
+            self.push(value)?;
             Ok(ExecutionResult::Continue)
         } else {
             Err(JVMError::NullReference)
@@ -191,88 +217,20 @@ impl Frame {
     }
 
     pub async fn putstatic(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
-        let cp_entry = self.constant_pool.get_entry(index).ok_or_else(|| {
-            JVMError::ConstantPoolIndexOutOfBounds {
-                index,
-                max: self.constant_pool.get_len(),
-            }
-        })?;
-        let (class_index, name_and_type_index) = match cp_entry {
-            ConstantInfo::FieldRef(ConstantFieldRefInfo {
-                class_index,
-                name_and_type_index,
-            }) => (class_index, name_and_type_index),
-            _ => {
-                return Err(JVMError::InvalidConstantType {
-                    expected: "Fieldref",
-                    found: "other",
-                })
-            }
-        };
-        let class_name = self
-            .constant_pool
-            .get_underlying_string_from_constant_class_info_index(*class_index)
-            .ok_or_else(|| JVMError::InvalidConstantType {
-                expected: "Class",
-                found: "missing name",
-            })?
-            .to_string();
-        let (name, descriptor) = self.get_name_and_type(*name_and_type_index)?;
-        let expected_type = descriptor.split(';').next().unwrap_or(&descriptor);
-
-        let value = match expected_type {
-            "Z" | "B" | "C" | "S" | "I" => self.pop_expect("I")?,
-            "J" => self.pop_expect("J")?,
-            "F" => self.pop_expect("F")?,
-            "D" => self.pop_expect("D")?,
-            t if t.starts_with("L") || t.starts_with("[") => self.pop_expect("L")?,
-            _ => {
-                return Err(JVMError::Other(format!(
-                    "Unsupported field descriptor: {}",
-                    descriptor
-                )))
-            }
-        };
-
-        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
-        let class = fut.await.unwrap();
-        class.set_static_field(&name, value)?;
+        let field = self.resolve_field_ref(index, vm).await?;
+        let (class, slot) = Self::static_slot(&field)?;
+        Box::pin(LoadedClass::initialize(Arc::clone(class), vm)).await?;
+        let value = self.pop_field_value(&field.name_des.des)?;
+        class.static_values.borrow_mut()[slot] = value;
         vm.memory_snap().await;
         Ok(ExecutionResult::Continue)
     }
 
     pub async fn getstatic(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
-        let cp_entry = self.constant_pool.get_entry(index).ok_or_else(|| {
-            JVMError::ConstantPoolIndexOutOfBounds {
-                index,
-                max: self.constant_pool.get_len(),
-            }
-        })?;
-        let (class_index, name_and_type_index) = match cp_entry {
-            ConstantInfo::FieldRef(ConstantFieldRefInfo {
-                class_index,
-                name_and_type_index,
-            }) => (class_index, name_and_type_index),
-            _ => {
-                return Err(JVMError::InvalidConstantType {
-                    expected: "Fieldref",
-                    found: "other",
-                })
-            }
-        };
-        let class_name = self
-            .constant_pool
-            .get_underlying_string_from_constant_class_info_index(*class_index)
-            .ok_or_else(|| JVMError::InvalidConstantType {
-                expected: "Class",
-                found: "missing name",
-            })?
-            .to_string();
-        let (name, _descriptor) = self.get_name_and_type(*name_and_type_index)?;
-
-        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
-        let class = fut.await.unwrap();
-        let value = class.get_static_field(&name)?;
+        let field = self.resolve_field_ref(index, vm).await?;
+        let (class, slot) = Self::static_slot(&field)?;
+        Box::pin(LoadedClass::initialize(Arc::clone(class), vm)).await?;
+        let value = class.static_values.borrow()[slot].clone();
         self.push(value)?;
         Ok(ExecutionResult::Continue)
     }
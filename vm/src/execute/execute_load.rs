@@ -25,7 +25,7 @@ impl Frame {
         }
     }
 
-    pub fn iload(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn iload(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -43,7 +43,7 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
-    pub fn lload(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn lload(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -61,7 +61,7 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
-    pub fn fload(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn fload(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -79,7 +79,7 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
-    pub fn dload(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn dload(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -97,7 +97,7 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
-    pub fn aload(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn aload(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -115,4 +115,27 @@ impl Frame {
         }
         Ok(ExecutionResult::Continue)
     }
+
+    // the wide forms of the local variable instructions, with a 16 bit index
+    pub fn wide(
+        &mut self,
+        opcode: u8,
+        index: u16,
+        constant: i16,
+    ) -> Result<ExecutionResult, JVMError> {
+        match opcode {
+            0x15 => self.iload(index),
+            0x16 => self.lload(index),
+            0x17 => self.fload(index),
+            0x18 => self.dload(index),
+            0x19 => self.aload(index),
+            0x36 => self.istore(index),
+            0x37 => self.lstore(index),
+            0x38 => self.fstore(index),
+            0x39 => self.dstore(index),
+            0x3a => self.astore(index),
+            0x84 => self.iinc(index, constant),
+            _ => Err(JVMError::Other(format!("Invalid wide opcode {:#x}", opcode))),
+        }
+    }
 }
@@ -1,14 +1,26 @@
 use super::execute::ExecutionResult;
-use crate::class_loader::loaded_class::LoadedClass;
+use crate::class_loader::loaded_class::{LoadedClass, ResolvedMethod};
+use crate::inline_cache::InlineCache;
+#[cfg(feature = "jit")]
+use crate::jit::compiler::{JitContext, JitType, STATUS_ARITHMETIC, STATUS_STACK_OVERFLOW};
+#[cfg(feature = "jit")]
+use crate::jit::jit::trace;
 use crate::runtime::*;
+use crate::state::{Header, MessageData, GLOBAL_BOOL, SERVER_STATE};
 use crate::vm::VM;
 use crate::{class_loader::loaded_class::NameDes, jvm_error::JVMError};
-use parser::access_flag::ClassFlags;
-use parser::access_flag::MethodFlags;
 use parser::attribute::Code;
 use parser::constant_pool::{ConstantInfo, ConstantInterfaceMethodRefInfo, ConstantMethodRefInfo};
+use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+// what a virtual call site dispatches to, natives have no code and bypass the inline cache
+enum CallTarget {
+    Bytecode(Arc<LoadedClass>, Arc<Code>),
+    Native(ResolvedMethod),
+}
+
 impl Frame {
     pub fn get_entry(&self, index: u16) -> Result<ConstantInfo, JVMError> {
         let constant_info =
@@ -21,40 +33,63 @@ impl Frame {
         Ok(constant_info.clone())
     }
 
-    fn resolve_method_ref(&self, index: u16) -> Result<(String, NameDes), JVMError> {
-        let cp_entry = self.get_entry(index)?;
-        match cp_entry {
+    // resolves a Methodref/InterfaceMethodref per JVMS 5.4.3.3/5.4.3.4, cached per constant pool index
+    async fn resolve_method_ref(
+        &self,
+        index: u16,
+        vm: &VM,
+    ) -> Result<Arc<ResolvedMethod>, JVMError> {
+        if let Some(resolved) = self.class.method_ref_cache.lock().unwrap().get(&index) {
+            return Ok(Arc::clone(resolved));
+        }
+        let (class_index, name_and_type_index, is_interface) = match self.get_entry(index)? {
             ConstantInfo::Methodref(ConstantMethodRefInfo {
                 class_index,
                 name_and_type_index,
-            })
-            | ConstantInfo::InterfaceMethodRef(ConstantInterfaceMethodRefInfo {
+            }) => (class_index, name_and_type_index, false),
+            ConstantInfo::InterfaceMethodRef(ConstantInterfaceMethodRefInfo {
                 class_index,
                 name_and_type_index,
-            }) => {
-                let class_name = self
-                    .constant_pool
-                    .get_underlying_string_from_constant_class_info_index(class_index)
-                    .ok_or(JVMError::ConstantPoolIndexOutOfBounds {
-                        index,
-                        max: self.constant_pool.get_len(),
-                    })?;
-                let constant_name_and_type = self.get_entry(name_and_type_index)?;
-                let name_and_type = match constant_name_and_type {
-                    ConstantInfo::NameAndType(value) => Ok(value.clone()),
-                    _ => Err(JVMError::InvalidConstantType {
-                        expected: "Methodref or InterfaceMethodref",
-                        found: "other",
-                    }),
-                };
-                let name_des = NameDes::new(&name_and_type?, &self.constant_pool);
-                Ok((class_name.to_string(), name_des))
+            }) => (class_index, name_and_type_index, true),
+            _ => {
+                return Err(JVMError::InvalidConstantType {
+                    expected: "Methodref or InterfaceMethodref",
+                    found: "other",
+                })
             }
-            _ => Err(JVMError::InvalidConstantType {
-                expected: "Methodref or InterfaceMethodref",
-                found: "other",
-            }),
-        }
+        };
+        let class_name = self
+            .constant_pool
+            .get_underlying_string_from_constant_class_info_index(class_index)
+            .ok_or(JVMError::ConstantPoolIndexOutOfBounds {
+                index,
+                max: self.constant_pool.get_len(),
+            })?
+            .to_string();
+        let name_and_type = match self.get_entry(name_and_type_index)? {
+            ConstantInfo::NameAndType(value) => value,
+            _ => {
+                return Err(JVMError::InvalidConstantType {
+                    expected: "NameAndType",
+                    found: "other",
+                })
+            }
+        };
+        let name_des = NameDes::new(&name_and_type, &self.constant_pool);
+
+        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
+        let class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        let resolved = Arc::new(if is_interface {
+            LoadedClass::resolve_interface_method(&class, &name_des)?
+        } else {
+            LoadedClass::resolve_method(&class, &name_des)?
+        });
+        self.class
+            .method_ref_cache
+            .lock()
+            .unwrap()
+            .insert(index, Arc::clone(&resolved));
+        Ok(resolved)
     }
 
     fn prepare_arguments(&mut self, descriptor: &str) -> Result<Vec<Value>, JVMError> {
@@ -163,90 +198,85 @@ impl Frame {
         class: &Arc<LoadedClass>,
         name_des: &NameDes,
     ) -> Result<(Arc<LoadedClass>, Arc<Code>), JVMError> {
-        let code = class
-            .get_code_from_method(name_des)
-            .ok_or_else(|| JVMError::MethodNotFound {
-                class: class.class_name.clone(),
-                name: name_des.name.clone(),
-                descriptor: name_des.des.clone(),
-            });
-        Ok((class.clone(), code?))
+        let resolved = LoadedClass::resolve_method(class, name_des)?;
+        let code = resolved.code()?;
+        Ok((resolved.class, code))
     }
 
-    fn lookup_virtual_method(
+    fn lookup_virtual_method_cached(
         &self,
-        class: &Arc<LoadedClass>,
-        name_des: &NameDes,
-    ) -> Result<(Arc<LoadedClass>, Arc<Code>), JVMError> {
-        if let Some(code) = class.get_code_from_method(name_des) {
-            return Ok((class.clone(), code));
+        receiver: &Arc<LoadedClass>,
+        resolved: &ResolvedMethod,
+    ) -> Result<CallTarget, JVMError> {
+        let mut caches = self.class.inline_caches.lock().unwrap();
+        if !caches.contains_key(&self.method_name_des) {
+            caches.insert(self.method_name_des.clone(), HashMap::new());
         }
-        let mut current_class = class.clone();
-        while let Some(super_class) = &current_class.super_class {
-            if let Some(code) = super_class.get_code_from_method(name_des) {
-                return Ok((super_class.clone(), code));
-            }
-            current_class = Arc::clone(super_class);
-        }
-        if class.access_flags.contains(ClassFlags::ACC_INTERFACE) {
-            for interface in &class.interfaces {
-                let code = self.lookup_virtual_method(interface, name_des)?;
-                return Ok(code);
+        let cache = caches
+            .get_mut(&self.method_name_des)
+            .unwrap()
+            .entry(self.pc)
+            .or_default();
+        let target = match cache.lookup(receiver) {
+            Some(target) => target,
+            None => {
+                let selected = LoadedClass::select_method(receiver, resolved)?;
+                if selected.is_native() {
+                    return Ok(CallTarget::Native(selected));
+                }
+                let method_code = selected.code()?;
+                cache.record(receiver, &selected.class, &method_code);
+                (selected.class, method_code)
             }
-        }
-
-        Err(JVMError::MethodNotFound {
-            class: class.class_name.clone(),
-            name: name_des.name.clone(),
-            descriptor: name_des.des.clone(),
-        })
+        };
+        self.trace_inline_cache(cache, receiver, &target.0, &resolved.name_des);
+        Ok(CallTarget::Bytecode(target.0, target.1))
     }
 
-    pub async fn invokestatic(&mut self, index: u16, vm: &VM) -> Result<ExecutionResult, JVMError> {
-        let (class_name, name_des) = self.resolve_method_ref(index)?;
-        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
+    fn trace_inline_cache(
+        &self,
+        cache: &InlineCache,
+        receiver: &LoadedClass,
+        method_class: &LoadedClass,
+        name_des: &NameDes,
+    ) {
+        let flag = GLOBAL_BOOL.lock().unwrap();
+        if *flag {
+            let cache_json = MessageData {
+                header: Header::DATA,
+                json: json!({"header": "inline_cache", "caller": self.method_name_des.name, "pc": self.pc, "callee": name_des.name, "receiver": receiver.class_name, "target": method_class.class_name, "state": cache.state(), "hits": cache.hits, "misses": cache.misses, "megamorphic": cache.megamorphic_lookups}).to_string(),
+            };
+            let mut queue = SERVER_STATE.lock().unwrap();
+            queue.push_back(cache_json);
+        }
+    }
 
-        let target_class = fut
-            .await
-            .map_err(|e| JVMError::Other(e.to_string()))
-            .unwrap();
-        let method_info = target_class
-            .methods
-            .iter()
-            .find(|method| {
-                let method_name = target_class
-                    .constant_pool
-                    .get_underlying_string_from_utf8_index(method.name_index)
-                    .unwrap();
-                let method_desc = target_class
-                    .constant_pool
-                    .get_underlying_string_from_utf8_index(method.descriptor_index)
-                    .unwrap();
-                *method_name == name_des.name && *method_desc == name_des.des
-            })
-            .ok_or_else(|| JVMError::Other(format!("Method not found: {}", name_des.name)))?;
+    pub async fn invokestatic(
+        &mut self,
+        index: u16,
+        stack: &Stack,
+        vm: &VM,
+    ) -> Result<ExecutionResult, JVMError> {
+        let resolved = self.resolve_method_ref(index, vm).await?;
+        if !resolved.is_static() {
+            return Err(JVMError::IncompatibleClassChange(format!(
+                "Expected static method {}.{}{}",
+                resolved.class.class_name, resolved.name_des.name, resolved.name_des.des
+            )));
+        }
+        let name_des = resolved.name_des.clone();
+        Box::pin(LoadedClass::initialize(Arc::clone(&resolved.class), vm)).await?;
 
-        if method_info.access_flags.contains(MethodFlags::ACC_NATIVE) {
-            let mut args = self.prepare_arguments(&name_des.des)?;
-            args.reverse();
-            if class_name.starts_with("java") {
-                return Ok(ExecutionResult::Continue);
-            }
-            let native_name = format!("Java_{}_{}", class_name.replace('/', "_"), name_des.name);
-            //println!("{native_name}");
-            let result = vm
-                .native_stack
-                .invoke(&native_name, &class_name, &args, &name_des.des)
-                .map_err(|e| JVMError::Other(format!("Native call failed: {}", e)))?;
-            if name_des.des.ends_with("V") {
-                Ok(ExecutionResult::Continue)
-            } else {
-                self.push(result)?;
-                Ok(ExecutionResult::Continue)
-            }
+        if resolved.is_native() {
+            let args = self.prepare_arguments(&name_des.des)?;
+            self.invoke_native(&resolved, args, stack, vm).await
         } else {
-            let (method_class, method_code) =
-                self.lookup_virtual_method(&target_class, &name_des)?;
+            let method_class = Arc::clone(&resolved.class);
+            let method_code = resolved.code()?;
+            #[cfg(feature = "jit")]
+            if let Some(result) = self.invoke_compiled(&method_class, &name_des, vm)? {
+                return Ok(result);
+            }
             let args = self.prepare_arguments(&name_des.des)?;
 
             let mut new_frame = Frame::new(method_class, &name_des, method_code);
@@ -264,15 +294,84 @@ impl Frame {
         }
     }
 
+    // runs the callee as native code once the JIT has compiled it, None keeps it interpreted
+    #[cfg(feature = "jit")]
+    fn invoke_compiled(
+        &mut self,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+        vm: &VM,
+    ) -> Result<Option<ExecutionResult>, JVMError> {
+        let compiled = match vm.jit.lock().unwrap().on_invoke(class, name_des, vm) {
+            Some(compiled) => compiled,
+            None => return Ok(None),
+        };
+        let args = self
+            .prepare_arguments(&name_des.des)?
+            .into_iter()
+            .map(|arg| match arg {
+                Value::Int(i) => Ok(i as i64),
+                Value::Long(l) => Ok(l),
+                other => Err(JVMError::InvalidOperandType {
+                    expected: "int or long",
+                    found: Self::get_value_type(&other),
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        trace("enter", &class.class_name, name_des, json!({"args": args}));
+        let mut context = JitContext::default();
+        let result = unsafe { (compiled.entry)(&mut context, args.as_ptr()) };
+        match context.status {
+            STATUS_ARITHMETIC => {
+                trace(
+                    "leave",
+                    &class.class_name,
+                    name_des,
+                    json!({"exception": "java/lang/ArithmeticException"}),
+                );
+                return Ok(Some(ExecutionResult::Throw(
+                    "java/lang/ArithmeticException".to_string(),
+                )));
+            }
+            STATUS_STACK_OVERFLOW => return Err(JVMError::StackOverflow),
+            _ => {}
+        }
+        trace(
+            "leave",
+            &class.class_name,
+            name_des,
+            json!({"result": result}),
+        );
+        match compiled.signature.ret {
+            Some(JitType::Int) => self.push(Value::Int(result as i32))?,
+            Some(JitType::Long) => self.push(Value::Long(result))?,
+            None => {}
+        }
+        Ok(Some(ExecutionResult::Continue))
+    }
+
     pub async fn invokespecial(
         &mut self,
         index: u16,
+        stack: &Stack,
         vm: &VM,
     ) -> Result<ExecutionResult, JVMError> {
-        let (class_name, name_des) = self.resolve_method_ref(index)?;
-        let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
-        let target_class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
-        let (method_class, method_code) = Self::lookup_method(&target_class, &name_des)?;
+        let resolved = self.resolve_method_ref(index, vm).await?;
+        let class_name = resolved.referenced_class.class_name.clone();
+        let name_des = resolved.name_des.clone();
+        // super calls select from the direct superclass of the current class (JVMS 6.5 invokespecial)
+        let selected = match &self.class.super_class {
+            Some(super_class)
+                if name_des.name != "<init>"
+                    && !resolved.referenced_class.is_interface()
+                    && class_name != self.class.class_name
+                    && self.class.is_subtype_of(&resolved.referenced_class) =>
+            {
+                LoadedClass::select_method(super_class, &resolved)?
+            }
+            _ => (*resolved).clone(),
+        };
         let mut args = self.prepare_arguments(&name_des.des)?;
         let object_ref = self.pop()?;
         match object_ref {
@@ -295,8 +394,12 @@ impl Frame {
                 })
             }
         }
+        if selected.is_native() {
+            return self.invoke_native(&selected, args, stack, vm).await;
+        }
 
-        let mut new_frame = Frame::new(method_class, &name_des, method_code);
+        let method_code = selected.code()?;
+        let mut new_frame = Frame::new(selected.class, &name_des, method_code);
         let mut i = 0;
         for arg in args.into_iter() {
             new_frame.set_local(i, arg.clone());
@@ -313,18 +416,27 @@ impl Frame {
     pub async fn invokevirtual(
         &mut self,
         index: u16,
+        stack: &Stack,
         vm: &VM,
     ) -> Result<ExecutionResult, JVMError> {
-        let (class_name, name_des) = self.resolve_method_ref(index)?;
+        let resolved = self.resolve_method_ref(index, vm).await?;
+        let name_des = resolved.name_des.clone();
         let mut args = self.prepare_arguments(&name_des.des)?;
         let object_ref = self.pop()?;
         match object_ref {
             Value::Reference(Some(obj)) => match &obj.class {
                 Some(target_class) => {
                     let target_class = Arc::clone(target_class);
-                    let (method_class, method_code) =
-                        self.lookup_virtual_method(&target_class, &name_des)?;
+                    let target = self.lookup_virtual_method_cached(&target_class, &resolved)?;
                     args.insert(0, Value::Reference(Some(obj)));
+                    let (method_class, method_code) = match target {
+                        CallTarget::Bytecode(method_class, method_code) => {
+                            (method_class, method_code)
+                        }
+                        CallTarget::Native(method) => {
+                            return self.invoke_native(&method, args, stack, vm).await
+                        }
+                    };
 
                     let mut new_frame = Frame::new(method_class, &name_des, method_code);
                     let mut i = 0;
@@ -351,44 +463,36 @@ impl Frame {
         }
     }
 
-    fn implements_interface(&self, class: &Arc<LoadedClass>, interface: &Arc<LoadedClass>) -> bool {
-        if class.class_name == interface.class_name {
-            return true;
-        }
-        for impl_interface in &class.interfaces {
-            if self.implements_interface(impl_interface, interface) {
-                return true;
-            }
-        }
-        if let Some(super_class) = &class.super_class {
-            return self.implements_interface(super_class, interface);
-        }
-        false
-    }
-
     pub async fn invokeinterface(
         &mut self,
         index: u16,
+        stack: &Stack,
         vm: &VM,
     ) -> Result<ExecutionResult, JVMError> {
-        let (class_name, name_des) = self.resolve_method_ref(index)?;
+        let resolved = self.resolve_method_ref(index, vm).await?;
+        let name_des = resolved.name_des.clone();
         let mut args = self.prepare_arguments(&name_des.des)?;
         let object_ref = self.pop()?;
         match object_ref {
             Value::Reference(Some(obj)) => match &obj.class {
                 Some(target_class) => {
                     let target_class = Arc::clone(target_class);
-                    let fut = Box::pin(vm.class_loader.load_class(&class_name, vm));
-                    let interface_class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
-                    if !self.implements_interface(&target_class, &interface_class) {
+                    if !target_class.is_subtype_of(&resolved.referenced_class) {
                         return Err(JVMError::IncompatibleClass {
-                            expected: class_name,
+                            expected: resolved.referenced_class.class_name.clone(),
                             found: target_class.class_name.clone(),
                         });
                     }
-                    let (method_class, method_code) =
-                        self.lookup_virtual_method(&target_class, &name_des)?;
+                    let target = self.lookup_virtual_method_cached(&target_class, &resolved)?;
                     args.insert(0, Value::Reference(Some(obj)));
+                    let (method_class, method_code) = match target {
+                        CallTarget::Bytecode(method_class, method_code) => {
+                            (method_class, method_code)
+                        }
+                        CallTarget::Native(method) => {
+                            return self.invoke_native(&method, args, stack, vm).await
+                        }
+                    };
                     let mut new_frame = Frame::new(method_class, &name_des, method_code);
                     let mut i = 0;
                     for arg in args.into_iter() {
@@ -1,48 +1,28 @@
 use super::execute::ExecutionResult;
 use crate::jvm_error::JVMError;
 use crate::runtime::*;
-use crate::vm::VM;
-use std::sync::Arc;
 
+// there is a single Java thread, every monitor is uncontended and owned by it, so entering and
+// exiting only check the reference
 impl Frame {
-    async fn monitor_enter(&mut self, vm: &VM) -> Result<ExecutionResult, JVMError> {
-        let ref_value = self.pop()?;
-        match ref_value {
-            Value::Reference(None) => Err(JVMError::NullReference),
-            Value::Reference(Some(obj)) => {
-                let monitor = &obj.monitor;
-                let _guard = monitor
-                    .lock()
-                    .map_err(|_| JVMError::Other("Failed to acquire monitor".to_string()))?;
-                Ok(ExecutionResult::Continue)
-            }
-            _ => Err(JVMError::Other(
-                "MonitorEnter requires a reference type".to_string(),
-            )),
-        }
+    pub fn monitor_enter(&mut self) -> Result<ExecutionResult, JVMError> {
+        self.monitor_operand("MonitorEnter")
+    }
+
+    pub fn monitor_exit(&mut self) -> Result<ExecutionResult, JVMError> {
+        self.monitor_operand("MonitorExit")
     }
 
-    async fn monitor_exit(&mut self, vm: &VM) -> Result<ExecutionResult, JVMError> {
-        let ref_value = self.pop()?;
-        match ref_value {
-            Value::Reference(None) => Err(JVMError::NullReference),
-            Value::Reference(Some(obj)) => {
-                let monitor = &obj.monitor;
-                if monitor.try_lock().is_ok() {
-                    return Err(JVMError::IllegalMonitorStateException(
-                        "Current thread does not own the monitor".to_string(),
-                    ));
-                }
-                drop(
-                    monitor
-                        .lock()
-                        .map_err(|_| JVMError::Other("Failed to release monitor".to_string()))?,
-                );
-                Ok(ExecutionResult::Continue)
-            }
-            _ => Err(JVMError::Other(
-                "MonitorExit requires a reference type".to_string(),
+    fn monitor_operand(&mut self, instruction: &str) -> Result<ExecutionResult, JVMError> {
+        match self.pop()? {
+            Value::Reference(None) => Ok(ExecutionResult::Throw(
+                "java/lang/NullPointerException".to_string(),
             )),
+            Value::Reference(Some(_)) => Ok(ExecutionResult::Continue),
+            _ => Err(JVMError::Other(format!(
+                "{} requires a reference type",
+                instruction
+            ))),
         }
     }
 }
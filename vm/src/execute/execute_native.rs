@@ -0,0 +1,69 @@
+use super::execute::ExecutionResult;
+use crate::class_loader::loaded_class::ResolvedMethod;
+use crate::intrinsics::intrinsics::{mirror_of, Intrinsic};
+use crate::jvm_error::JVMError;
+use crate::native::NativeContext;
+use crate::runtime::*;
+use crate::vm::VM;
+use std::sync::Arc;
+
+impl Frame {
+    // runs a method flagged ACC_NATIVE and pushes its result, args include the receiver of
+    // instance methods
+    pub async fn invoke_native(
+        &mut self,
+        method: &ResolvedMethod,
+        args: Vec<Value>,
+        stack: &Stack,
+        vm: &VM,
+    ) -> Result<ExecutionResult, JVMError> {
+        match call_native(method, args, stack, vm).await? {
+            ExecutionResult::Return(Some(value)) => {
+                self.push(value)?;
+                Ok(ExecutionResult::Continue)
+            }
+            ExecutionResult::Return(None) => Ok(ExecutionResult::Continue),
+            other => Ok(other),
+        }
+    }
+}
+
+// runs a method flagged ACC_NATIVE, args include the receiver of instance methods.
+// Rust intrinsics come first, then the closures and libraries registered with the NativeStack.
+pub async fn call_native(
+    method: &ResolvedMethod,
+    mut args: Vec<Value>,
+    stack: &Stack,
+    vm: &VM,
+) -> Result<ExecutionResult, JVMError> {
+    let class_name = &method.class.class_name;
+    let name_des = &method.name_des;
+    match vm.intrinsics.lookup(class_name, name_des) {
+        Some(Intrinsic::Sync(function)) => function(&args),
+        Some(Intrinsic::Async(function)) => function(vm, stack, args).await,
+        // natives that bind C functions to HotSpot, there is nothing to bind here
+        None if (name_des.name == "registerNatives" || name_des.name == "initIDs")
+            && name_des.des == "()V" =>
+        {
+            Ok(ExecutionResult::Return(None))
+        }
+        None => {
+            if let Some(function) = vm.native_stack.find_rust_native(class_name, name_des) {
+                return function(&NativeContext { vm, stack }, args);
+            }
+            let Some(function) = vm.native_stack.find_function(class_name, name_des) else {
+                return Ok(ExecutionResult::Throw(
+                    "java/lang/UnsatisfiedLinkError".to_string(),
+                ));
+            };
+            // JNI passes static natives their class and instance natives their receiver
+            let this = if method.is_static() {
+                mirror_of(vm, stack, Arc::clone(&method.class)).await?
+            } else {
+                args.remove(0)
+            };
+            vm.native_stack
+                .invoke(vm, stack, function, this, &args, &name_des.des)
+        }
+    }
+}
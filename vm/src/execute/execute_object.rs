@@ -3,6 +3,7 @@ use crate::jvm_error::JVMError;
 use super::execute::ExecutionResult;
 use parser::constant_pool::{ConstantInfo, ConstantClassInfo};
 use crate::vm::VM;
+use crate::class_loader::loaded_class::LoadedClass;
 
 impl Frame {
     pub async fn execute_new(&mut self, index: u16, stack: &Stack, vm: &VM) -> Result<ExecutionResult, JVMError> {
@@ -27,6 +28,11 @@ impl Frame {
         };
         //println!("{class_name}");
 
+        // resolve and initialize the class before taking the heap lock, <clinit> may allocate
+        let fut = Box::pin(vm.class_loader.load_class(class_name, vm));
+        let class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        Box::pin(LoadedClass::initialize(class, vm)).await?;
+
         let fut = Box::pin(vm.allocate_object(stack, class_name));
         let obj_ref = fut.await?;
         self.push(obj_ref)?;
@@ -49,9 +55,29 @@ impl Frame {
         Ok(ExecutionResult::Continue)
     }
 
+    pub fn swap(&mut self) -> Result<ExecutionResult, JVMError> {
+        let value1 = self.pop()?;
+        let value2 = self.pop()?;
+        self.push(value1)?;
+        self.push(value2)?;
+        Ok(ExecutionResult::Continue)
+    }
+
+    // longs and doubles take a single operand entry, so the dup forms of JVMS 6.5 that move
+    // category 2 values pick their shape from the values on top of the stack
+    fn is_category2(value: &Value) -> bool {
+        matches!(value, Value::Long(_) | Value::Double(_))
+    }
+
     pub fn dup_x2(&mut self) -> Result<ExecutionResult, JVMError> {
         let value1 = self.pop()?;
         let value2 = self.pop()?;
+        if Self::is_category2(&value2) {
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            self.push(value1)?;
+            return Ok(ExecutionResult::Continue);
+        }
         let value3 = self.pop()?;
         self.push(value1.clone())?;
         self.push(value3)?;
@@ -62,6 +88,11 @@ impl Frame {
 
     pub fn dup2(&mut self) -> Result<ExecutionResult, JVMError> {
         let value1 = self.pop()?;
+        if Self::is_category2(&value1) {
+            self.push(value1.clone())?;
+            self.push(value1)?;
+            return Ok(ExecutionResult::Continue);
+        }
         let value2 = self.pop()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
@@ -73,6 +104,12 @@ impl Frame {
     pub fn dup2_x1(&mut self) -> Result<ExecutionResult, JVMError> {
         let value1 = self.pop()?;
         let value2 = self.pop()?;
+        if Self::is_category2(&value1) {
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            self.push(value1)?;
+            return Ok(ExecutionResult::Continue);
+        }
         let value3 = self.pop()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
@@ -85,14 +122,40 @@ impl Frame {
     pub fn dup2_x2(&mut self) -> Result<ExecutionResult, JVMError> {
         let value1 = self.pop()?;
         let value2 = self.pop()?;
-        let value3 = self.pop()?;
-        let value4 = self.pop()?;
-        self.push(value2.clone())?;
-        self.push(value1.clone())?;
-        self.push(value4)?;
-        self.push(value3)?;
-        self.push(value2)?;
-        self.push(value1)?;
+        match (Self::is_category2(&value1), Self::is_category2(&value2)) {
+            // form 4
+            (true, true) => {
+                self.push(value1.clone())?;
+                self.push(value2)?;
+                self.push(value1)?;
+            }
+            // form 2
+            (true, false) => {
+                let value3 = self.pop()?;
+                self.push(value1.clone())?;
+                self.push(value3)?;
+                self.push(value2)?;
+                self.push(value1)?;
+            }
+            (false, _) => {
+                let value3 = self.pop()?;
+                if Self::is_category2(&value3) {
+                    // form 3
+                    self.push(value2.clone())?;
+                    self.push(value1.clone())?;
+                    self.push(value3)?;
+                } else {
+                    // form 1
+                    let value4 = self.pop()?;
+                    self.push(value2.clone())?;
+                    self.push(value1.clone())?;
+                    self.push(value4)?;
+                    self.push(value3)?;
+                }
+                self.push(value2)?;
+                self.push(value1)?;
+            }
+        }
         Ok(ExecutionResult::Continue)
     }
 }
@@ -4,7 +4,7 @@ use crate::jvm_error::JVMError;
 use super::execute::ExecutionResult;
 
 impl Frame {
-    pub fn istore(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn istore(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -20,7 +20,7 @@ impl Frame {
         }
     }
 
-    pub fn lstore(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn lstore(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -36,7 +36,7 @@ impl Frame {
         }
     }
 
-    pub fn fstore(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn fstore(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -52,7 +52,7 @@ impl Frame {
         }
     }
 
-    pub fn dstore(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn dstore(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
@@ -68,7 +68,7 @@ impl Frame {
         }
     }
 
-    pub fn astore(&mut self, index: u8) -> Result<ExecutionResult, JVMError> {
+    pub fn astore(&mut self, index: u16) -> Result<ExecutionResult, JVMError> {
         let index = index as usize;
         self.check_local_index(index)?;
 
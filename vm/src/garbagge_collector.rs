@@ -5,6 +5,7 @@ use super::object::Object;
 use super::object::ObjectKind;
 use super::runtime::*;
 use super::vm::VM;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 impl Heap {
@@ -99,6 +100,33 @@ impl Heap {
             }
         }
 
+        for mirror in self.class_mirrors.values() {
+            Self::mark_object(mirror);
+        }
+        if let Some(thread) = &self.main_thread {
+            Self::mark_object(thread);
+        }
+
+        // Arcs to an object beyond its heap slot and the references heap objects hold to it
+        // are held by Rust code: frames of other stacks, static fields, JNI local and global
+        // references, an array still being filled or a Value of an embedding host
+        let mut held_in_heap: HashMap<*const Object, usize> = HashMap::new();
+        for slot in &self.objects {
+            if let Slot::Occupied(obj) = slot {
+                Self::for_each_reference(obj, |ref_obj| {
+                    *held_in_heap.entry(Arc::as_ptr(ref_obj)).or_default() += 1;
+                });
+            }
+        }
+        for slot in &self.objects {
+            if let Slot::Occupied(obj) = slot {
+                let in_heap = held_in_heap.get(&Arc::as_ptr(obj)).copied().unwrap_or(0);
+                if Arc::strong_count(obj) > 1 + in_heap {
+                    Self::mark_object(obj);
+                }
+            }
+        }
+
         for frame in &stack.frames {
             for value in &frame.operands {
                 if let Value::Reference(Some(obj)) = value {
@@ -118,21 +146,23 @@ impl Heap {
         if !header.mark {
             header.mark = true;
             drop(header);
+            Self::for_each_reference(obj, Self::mark_object);
+        }
+    }
 
-            match &obj.kind {
-                ObjectKind::ClassInstance { fields } => {
-                    for value in &*fields.borrow() {
-                        if let Value::Reference(Some(ref_obj)) = value {
-                            Self::mark_object(&ref_obj);
-                        }
+    // calls f with every object a field or element of obj refers to
+    fn for_each_reference(obj: &Object, mut f: impl FnMut(&Arc<Object>)) {
+        match &obj.kind {
+            ObjectKind::ClassInstance { fields } => {
+                for value in &*fields.borrow() {
+                    if let Value::Reference(Some(ref_obj)) = value {
+                        f(ref_obj);
                     }
                 }
-                ObjectKind::ArrayInstance { elements, .. } => {
-                    for value in &*elements.borrow() {
-                        if let Value::Reference(Some(ref_obj)) = value {
-                            Self::mark_object(&ref_obj);
-                        }
-                    }
+            }
+            ObjectKind::ArrayInstance { elements, .. } => {
+                for ref_obj in elements.borrow().references().iter().flatten() {
+                    f(ref_obj);
                 }
             }
         }
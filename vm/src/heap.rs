@@ -1,5 +1,5 @@
 use super::jvm_error::JVMError;
-use super::object::{Object, ObjectKind};
+use super::object::{ArrayStorage, Object, ObjectKind};
 use super::runtime::*;
 use super::vm::VM;
 use crate::class_loader::loaded_class::LoadedClass;
@@ -7,6 +7,7 @@ use crate::execute::execute::{serialize_vec, SerValue};
 use crate::state::{Header, MessageData, GLOBAL_BOOL, MEMORY_SNAP, SERVER_STATE};
 use serde::ser::SerializeStruct;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -25,6 +26,12 @@ pub struct Heap {
     pub free_head: Option<usize>,
     pub young_count: usize,
     pub old_count: usize,
+    // java/lang/Class mirror per loaded class, these stay reachable for the VM's lifetime
+    pub class_mirrors: HashMap<String, Arc<Object>>,
+    // the reverse mapping, keyed by the object id of the mirror
+    pub mirror_classes: HashMap<u32, Arc<LoadedClass>>,
+    // java/lang/Thread object of the interpreter thread, created by Thread.currentThread
+    pub main_thread: Option<Arc<Object>>,
 }
 
 impl Heap {
@@ -42,6 +49,9 @@ impl Heap {
             free_head: Some(0),
             young_count: 0,
             old_count: 0,
+            class_mirrors: HashMap::new(),
+            mirror_classes: HashMap::new(),
+            main_thread: None,
         }
     }
 
@@ -89,24 +99,56 @@ impl Heap {
         }
     }
 
+    pub async fn allocate_clone(
+        &mut self,
+        stack: &Stack,
+        vm: &VM,
+        original: &Object,
+    ) -> Result<Value, JVMError> {
+        let obj_ref = Arc::new(original.shallow_copy());
+
+        match self.free_head {
+            Some(index) => {
+                self.young_count += 1;
+                self.take_slot(index, Arc::clone(&obj_ref));
+                self.memory_json();
+                Ok(Value::Reference(Some(obj_ref)))
+            }
+            None => {
+                self.run_minor_gc(stack, vm).await?;
+                match self.free_head {
+                    Some(index) => {
+                        self.young_count += 1;
+                        self.take_slot(index, Arc::clone(&obj_ref));
+                        self.memory_json();
+                        Ok(Value::Reference(Some(obj_ref)))
+                    }
+                    None => {
+                        self.run_major_gc(stack, vm).await?;
+                        match self.free_head {
+                            Some(index) => {
+                                self.young_count += 1;
+                                self.take_slot(index, Arc::clone(&obj_ref));
+                                self.memory_json();
+                                Ok(Value::Reference(Some(obj_ref)))
+                            }
+                            None => Err(JVMError::Other("Heap exhausted after GC".to_string())),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub async fn allocate_array(
         &mut self,
         stack: &Stack,
         vm: &VM,
-        element_type: &str,
+        array_class: Arc<LoadedClass>,
         length: usize,
     ) -> Result<Value, JVMError> {
-        let class = if element_type.starts_with("[") || element_type.len() == 1 {
-            None
-        } else {
-            Some(vm.class_loader.load_class(element_type, vm).await.unwrap())
-        };
-        let appended_element_type = if element_type.starts_with("[") || element_type.len() == 1 {
-            element_type.to_string()
-        } else {
-            format!("L{};", element_type)
-        };
-        let obj = Object::new_array(class, length, &appended_element_type);
+        let element_type = array_class.class_name[1..].to_string();
+        let obj = Object::new_array(Some(array_class), length, &element_type);
         let obj_ref = Arc::new(obj);
 
         match self.free_head {
@@ -153,33 +195,43 @@ impl Heap {
             .load_class("java/lang/String", vm)
             .await
             .unwrap();
-        let chars: Vec<Value> = string_value.chars().map(|c| Value::Int(c as i32)).collect();
-        let char_array = Object::new_array(None, chars.len(), "C");
-        let char_array_ref = Arc::new(char_array);
-        for (i, char_value) in chars.into_iter().enumerate() {
-            char_array_ref.set_element(i, char_value)?;
-        }
-        let char_array_slot = match self.free_head {
+        // java.lang.String keeps Latin-1 text one byte per char (coder 0), anything else as
+        // UTF-16 in native byte order (coder 1)
+        let utf16 = string_value.encode_utf16().collect::<Vec<_>>();
+        let (bytes, coder) = if utf16.iter().all(|&c| c <= 0xff) {
+            (utf16.iter().map(|&c| c as i8).collect::<Vec<_>>(), 0)
+        } else {
+            let bytes = utf16
+                .iter()
+                .flat_map(|c| c.to_ne_bytes())
+                .map(|b| b as i8)
+                .collect::<Vec<_>>();
+            (bytes, 1)
+        };
+        let byte_array_class = vm.class_loader.load_class("[B", vm).await.unwrap();
+        let value_array_ref = Arc::new(Object::new_array_with(
+            Some(byte_array_class),
+            "B",
+            ArrayStorage::Byte(bytes),
+        ));
+        match self.free_head {
             Some(index) => {
                 self.young_count += 1;
-                self.take_slot(index, Arc::clone(&char_array_ref));
-                index
+                self.take_slot(index, Arc::clone(&value_array_ref));
             }
             None => {
                 self.run_minor_gc(stack, vm).await?;
                 match self.free_head {
                     Some(index) => {
                         self.young_count += 1;
-                        self.take_slot(index, Arc::clone(&char_array_ref));
-                        index
+                        self.take_slot(index, Arc::clone(&value_array_ref));
                     }
                     None => {
                         self.run_major_gc(stack, vm).await?;
                         match self.free_head {
                             Some(index) => {
                                 self.young_count += 1;
-                                self.take_slot(index, Arc::clone(&char_array_ref));
-                                index
+                                self.take_slot(index, Arc::clone(&value_array_ref));
                             }
                             None => {
                                 return Err(JVMError::Other("Heap exhausted after GC".to_string()))
@@ -191,7 +243,8 @@ impl Heap {
         };
         let string_obj = Object::new_class(string_class, vm).await;
         let string_ref = Arc::new(string_obj);
-        string_ref.set_field("value", Value::Reference(Some(Arc::clone(&char_array_ref))))?;
+        string_ref.set_field("value", Value::Reference(Some(Arc::clone(&value_array_ref))))?;
+        string_ref.set_field("coder", Value::Int(coder))?;
         match self.free_head {
             Some(index) => {
                 self.young_count += 1;
@@ -231,20 +284,33 @@ impl Heap {
         vm: &VM,
         loaded_class: Arc<LoadedClass>,
     ) -> Result<Value, JVMError> {
+        if let Some(mirror) = self.class_mirrors.get(&loaded_class.class_name) {
+            return Ok(Value::Reference(Some(Arc::clone(mirror))));
+        }
         let class_class = vm
             .class_loader
             .load_class("java/lang/Class", vm)
             .await
             .unwrap();
 
+        // Class.getName reports binary names, java.lang.String rather than java/lang/String
         let name_value = self
-            .allocate_string(stack, vm, &loaded_class.class_name)
+            .allocate_string(stack, vm, &loaded_class.class_name.replace('/', "."))
             .await?;
 
         let class_obj = Object::new_class(class_class, vm).await;
         let class_ref = Arc::new(class_obj);
 
         class_ref.set_field("name", name_value)?;
+        if let Some(component) = &loaded_class.component_class {
+            let component_mirror =
+                Box::pin(self.allocate_class(stack, vm, Arc::clone(component))).await?;
+            class_ref.set_field("componentType", component_mirror)?;
+        }
+        self.class_mirrors
+            .insert(loaded_class.class_name.clone(), Arc::clone(&class_ref));
+        self.mirror_classes
+            .insert(class_ref.header.borrow().object_id, loaded_class);
 
         match self.free_head {
             Some(index) => {
@@ -276,6 +342,13 @@ impl Heap {
         }
     }
 
+    // the class a java/lang/Class object stands for
+    pub fn class_of_mirror(&self, mirror: &Object) -> Option<Arc<LoadedClass>> {
+        self.mirror_classes
+            .get(&mirror.header.borrow().object_id)
+            .cloned()
+    }
+
     pub fn take_slot(&mut self, index: usize, obj: Arc<Object>) {
         if let Slot::Free { next, prev } = self.objects[index] {
             if let Some(p) = prev {
@@ -359,7 +432,7 @@ impl Heap {
                 let serialized_values = match &object.kind {
                     ObjectKind::ClassInstance { fields } => serialize_vec(fields.borrow().clone()),
                     ObjectKind::ArrayInstance { elements, .. } => {
-                        serialize_vec(elements.borrow().clone())
+                        serialize_vec(elements.borrow().to_values())
                     }
                 };
 
@@ -0,0 +1,119 @@
+use crate::class_loader::loaded_class::LoadedClass;
+use parser::attribute::Code;
+use serde::Serialize;
+use std::sync::Arc;
+
+// number of receiver classes a call site remembers before it goes megamorphic
+pub const POLYMORPHIC_LIMIT: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum CacheState {
+    Uninitialized,
+    Monomorphic,
+    Polymorphic,
+    Megamorphic,
+}
+
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub receiver: Arc<LoadedClass>,
+    pub method_class: Arc<LoadedClass>,
+    pub code: Arc<Code>,
+}
+
+// Inline cache of a single invokevirtual/invokeinterface site.
+// Receivers are compared by identity since the class loader hands out one Arc per class.
+#[derive(Debug, Default)]
+pub struct InlineCache {
+    pub entries: Vec<CacheEntry>,
+    pub megamorphic: bool,
+    pub hits: u64,
+    pub misses: u64,
+    pub megamorphic_lookups: u64,
+}
+
+impl InlineCache {
+    pub fn state(&self) -> CacheState {
+        if self.megamorphic {
+            CacheState::Megamorphic
+        } else {
+            match self.entries.len() {
+                0 => CacheState::Uninitialized,
+                1 => CacheState::Monomorphic,
+                _ => CacheState::Polymorphic,
+            }
+        }
+    }
+
+    pub fn lookup(&mut self, receiver: &Arc<LoadedClass>) -> Option<(Arc<LoadedClass>, Arc<Code>)> {
+        if self.megamorphic {
+            self.megamorphic_lookups += 1;
+            return None;
+        }
+        match self
+            .entries
+            .iter()
+            .find(|entry| Arc::ptr_eq(&entry.receiver, receiver))
+        {
+            Some(entry) => {
+                self.hits += 1;
+                Some((Arc::clone(&entry.method_class), Arc::clone(&entry.code)))
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        receiver: &Arc<LoadedClass>,
+        method_class: &Arc<LoadedClass>,
+        code: &Arc<Code>,
+    ) {
+        if self.megamorphic {
+            return;
+        }
+        if self.entries.len() >= POLYMORPHIC_LIMIT {
+            self.entries.clear();
+            self.megamorphic = true;
+            return;
+        }
+        self.entries.push(CacheEntry {
+            receiver: Arc::clone(receiver),
+            method_class: Arc::clone(method_class),
+            code: Arc::clone(code),
+        });
+    }
+
+    pub fn receivers(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.receiver.class_name.clone())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineCacheStats {
+    pub class_name: String,
+    pub method: String,
+    pub pc: usize,
+    pub state: CacheState,
+    pub receivers: Vec<String>,
+    pub hits: u64,
+    pub misses: u64,
+    pub megamorphic_lookups: u64,
+}
+
+impl InlineCacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses + self.megamorphic_lookups;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
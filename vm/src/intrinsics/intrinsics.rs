@@ -0,0 +1,728 @@
+use super::{intrinsics_class, intrinsics_system, intrinsics_unsafe};
+use crate::class_loader::loaded_class::{LoadedClass, NameDes};
+use crate::execute::execute::ExecutionResult;
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::object::{Object, ObjectKind};
+use crate::runtime::*;
+use crate::vm::VM;
+use futures_util::future::LocalBoxFuture;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// Natives implemented in Rust, keyed by declaring class and NameDes. They return
+// ExecutionResult::Return with the method's result (None for void) or ExecutionResult::Throw.
+pub enum Intrinsic {
+    Sync(SyncIntrinsic),
+    // natives that allocate on the heap
+    Async(AsyncIntrinsic),
+}
+
+pub type IntrinsicFuture<'a> = LocalBoxFuture<'a, Result<ExecutionResult, JVMError>>;
+pub type SyncIntrinsic = fn(&[Value]) -> Result<ExecutionResult, JVMError>;
+pub type AsyncIntrinsic = for<'a> fn(&'a VM, &'a Stack, Vec<Value>) -> IntrinsicFuture<'a>;
+
+#[derive(Default)]
+pub struct IntrinsicRegistry {
+    methods: HashMap<String, HashMap<NameDes, Intrinsic>>,
+    // methods with bytecode the VM implements itself, the class loader flags them native
+    replaced: HashMap<String, HashSet<NameDes>>,
+}
+
+impl IntrinsicRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the natives java.base needs for everyday code
+    pub fn with_java_base() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(
+            "java/lang/Object",
+            "getClass",
+            "()Ljava/lang/Class;",
+            Intrinsic::Async(object_get_class),
+        );
+        registry.register(
+            "java/lang/Object",
+            "hashCode",
+            "()I",
+            Intrinsic::Sync(object_hash_code),
+        );
+        registry.register(
+            "java/lang/Object",
+            "clone",
+            "()Ljava/lang/Object;",
+            Intrinsic::Async(object_clone),
+        );
+        registry.register(
+            "java/lang/Object",
+            "notify",
+            "()V",
+            Intrinsic::Sync(object_notify),
+        );
+        registry.register(
+            "java/lang/Object",
+            "notifyAll",
+            "()V",
+            Intrinsic::Sync(object_notify),
+        );
+        registry.register(
+            "java/lang/Object",
+            "wait",
+            "(J)V",
+            Intrinsic::Sync(object_wait),
+        );
+
+        registry.register(
+            "java/lang/System",
+            "arraycopy",
+            "(Ljava/lang/Object;ILjava/lang/Object;II)V",
+            Intrinsic::Sync(system_arraycopy),
+        );
+        registry.register(
+            "java/lang/System",
+            "currentTimeMillis",
+            "()J",
+            Intrinsic::Sync(system_current_time_millis),
+        );
+        registry.register(
+            "java/lang/System",
+            "nanoTime",
+            "()J",
+            Intrinsic::Sync(system_nano_time),
+        );
+        registry.register(
+            "java/lang/System",
+            "identityHashCode",
+            "(Ljava/lang/Object;)I",
+            Intrinsic::Sync(system_identity_hash_code),
+        );
+
+        registry.register(
+            "java/lang/Float",
+            "floatToRawIntBits",
+            "(F)I",
+            Intrinsic::Sync(float_to_raw_int_bits),
+        );
+        registry.register(
+            "java/lang/Float",
+            "intBitsToFloat",
+            "(I)F",
+            Intrinsic::Sync(int_bits_to_float),
+        );
+        registry.register(
+            "java/lang/Double",
+            "doubleToRawLongBits",
+            "(D)J",
+            Intrinsic::Sync(double_to_raw_long_bits),
+        );
+        registry.register(
+            "java/lang/Double",
+            "longBitsToDouble",
+            "(J)D",
+            Intrinsic::Sync(long_bits_to_double),
+        );
+        // Java code in java.base, natives of the bundled class library
+        registry.register(
+            "java/lang/Double",
+            "toString",
+            "(D)Ljava/lang/String;",
+            Intrinsic::Async(double_to_string),
+        );
+        registry.register(
+            "java/lang/Float",
+            "toString",
+            "(F)Ljava/lang/String;",
+            Intrinsic::Async(float_to_string),
+        );
+        registry.register(
+            "java/lang/Double",
+            "parseDouble",
+            "(Ljava/lang/String;)D",
+            Intrinsic::Sync(parse_double),
+        );
+        registry.register(
+            "java/lang/Float",
+            "parseFloat",
+            "(Ljava/lang/String;)F",
+            Intrinsic::Sync(parse_float),
+        );
+
+        // exp and those after log1p are Java code in java.base, the bundled class library has
+        // them native as well
+        let unary: [(&str, SyncIntrinsic); 19] = [
+            ("sin", |args| unary_math(args, f64::sin)),
+            ("cos", |args| unary_math(args, f64::cos)),
+            ("tan", |args| unary_math(args, f64::tan)),
+            ("asin", |args| unary_math(args, f64::asin)),
+            ("acos", |args| unary_math(args, f64::acos)),
+            ("atan", |args| unary_math(args, f64::atan)),
+            ("log", |args| unary_math(args, f64::ln)),
+            ("log10", |args| unary_math(args, f64::log10)),
+            ("sqrt", |args| unary_math(args, f64::sqrt)),
+            ("sinh", |args| unary_math(args, f64::sinh)),
+            ("cosh", |args| unary_math(args, f64::cosh)),
+            ("tanh", |args| unary_math(args, f64::tanh)),
+            ("expm1", |args| unary_math(args, f64::exp_m1)),
+            ("log1p", |args| unary_math(args, f64::ln_1p)),
+            ("exp", |args| unary_math(args, f64::exp)),
+            ("cbrt", |args| unary_math(args, f64::cbrt)),
+            ("floor", |args| unary_math(args, f64::floor)),
+            ("ceil", |args| unary_math(args, f64::ceil)),
+            ("rint", |args| unary_math(args, f64::round_ties_even)),
+        ];
+        for (name, function) in unary {
+            registry.register(
+                "java/lang/StrictMath",
+                name,
+                "(D)D",
+                Intrinsic::Sync(function),
+            );
+        }
+        registry.register(
+            "java/lang/StrictMath",
+            "atan2",
+            "(DD)D",
+            Intrinsic::Sync(|args| binary_math(args, f64::atan2)),
+        );
+        registry.register(
+            "java/lang/StrictMath",
+            "IEEEremainder",
+            "(DD)D",
+            Intrinsic::Sync(|args| binary_math(args, ieee_remainder)),
+        );
+        registry.register(
+            "java/lang/StrictMath",
+            "pow",
+            "(DD)D",
+            Intrinsic::Sync(|args| binary_math(args, java_pow)),
+        );
+        registry.register(
+            "java/lang/StrictMath",
+            "hypot",
+            "(DD)D",
+            Intrinsic::Sync(|args| binary_math(args, f64::hypot)),
+        );
+
+        registry.register(
+            "java/lang/Throwable",
+            "fillInStackTrace",
+            "(I)Ljava/lang/Throwable;",
+            Intrinsic::Sync(throwable_fill_in_stack_trace),
+        );
+        registry.register(
+            "java/lang/Thread",
+            "currentThread",
+            "()Ljava/lang/Thread;",
+            Intrinsic::Async(thread_current_thread),
+        );
+        registry.register(
+            "java/lang/Runtime",
+            "availableProcessors",
+            "()I",
+            Intrinsic::Sync(runtime_available_processors),
+        );
+
+        // there is no class data sharing archive
+        for name in ["isDumpingClassList0", "isDumpingArchive0", "isSharingEnabled0"] {
+            registry.register(
+                "jdk/internal/misc/CDS",
+                name,
+                "()Z",
+                Intrinsic::Sync(|_| returns(Value::Int(0))),
+            );
+        }
+        registry.register(
+            "jdk/internal/misc/CDS",
+            "initializeFromArchive",
+            "(Ljava/lang/Class;)V",
+            Intrinsic::Sync(|_| Ok(ExecutionResult::Return(None))),
+        );
+        registry.register(
+            "jdk/internal/misc/CDS",
+            "getRandomSeedForDumping",
+            "()J",
+            Intrinsic::Sync(|_| returns(Value::Long(0))),
+        );
+
+        intrinsics_class::register(&mut registry);
+        intrinsics_system::register(&mut registry);
+        intrinsics_unsafe::register(&mut registry);
+
+        registry
+    }
+
+    pub fn register(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        intrinsic: Intrinsic,
+    ) {
+        let name_des = NameDes {
+            name: name.to_string(),
+            des: descriptor.to_string(),
+        };
+        self.methods
+            .entry(class_name.to_string())
+            .or_default()
+            .insert(name_des, intrinsic);
+    }
+
+    // registers an intrinsic for a method that is not native in its class file
+    pub fn replace(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        intrinsic: Intrinsic,
+    ) {
+        self.register(class_name, name, descriptor, intrinsic);
+        let name_des = NameDes {
+            name: name.to_string(),
+            des: descriptor.to_string(),
+        };
+        self.replaced
+            .entry(class_name.to_string())
+            .or_default()
+            .insert(name_des);
+    }
+
+    pub fn replaces(&self, class_name: &str, name_des: &NameDes) -> bool {
+        self.replaced
+            .get(class_name)
+            .is_some_and(|methods| methods.contains(name_des))
+    }
+
+    pub fn lookup(&self, class_name: &str, name_des: &NameDes) -> Option<&Intrinsic> {
+        self.methods.get(class_name)?.get(name_des)
+    }
+}
+
+pub(crate) fn reference_arg(args: &[Value], index: usize) -> Result<Option<Arc<Object>>, JVMError> {
+    match args.get(index) {
+        Some(Value::Reference(reference)) => Ok(reference.clone()),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "Reference".to_string(),
+            found: "non-reference".to_string(),
+        }),
+    }
+}
+
+// the class behind a java/lang/Class argument
+pub(crate) async fn class_arg(
+    vm: &VM,
+    args: &[Value],
+    index: usize,
+) -> Result<Arc<LoadedClass>, JVMError> {
+    let mirror = reference_arg(args, index)?.ok_or(JVMError::NullReference)?;
+    vm.heap
+        .read()
+        .await
+        .class_of_mirror(&mirror)
+        .ok_or_else(|| JVMError::Other("Class object without a loaded class".to_string()))
+}
+
+// the java/lang/Class object of a loaded class
+pub(crate) async fn mirror_of(
+    vm: &VM,
+    stack: &Stack,
+    class: Arc<LoadedClass>,
+) -> Result<Value, JVMError> {
+    let mut heap = vm.heap.write().await;
+    heap.allocate_class(stack, vm, class).await
+}
+
+// a java/lang/String[] holding the given strings, None becomes a null element
+pub(crate) async fn string_array(
+    vm: &VM,
+    stack: &Stack,
+    strings: &[Option<String>],
+) -> Result<Value, JVMError> {
+    let array = vm
+        .allocate_array(stack, "Ljava/lang/String;", strings.len())
+        .await?;
+    if let Value::Reference(Some(array)) = &array {
+        let mut heap = vm.heap.write().await;
+        for (i, string) in strings.iter().enumerate() {
+            if let Some(string) = string {
+                let string = heap.allocate_string(stack, vm, string).await?;
+                array.set_element(i, string)?;
+            }
+        }
+    }
+    Ok(array)
+}
+
+pub(crate) fn receiver_arg(args: &[Value]) -> Result<Arc<Object>, JVMError> {
+    reference_arg(args, 0)?.ok_or(JVMError::NullReference)
+}
+
+pub(crate) fn int_arg(args: &[Value], index: usize) -> Result<i32, JVMError> {
+    match args.get(index) {
+        Some(Value::Int(i)) => Ok(*i),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "int".to_string(),
+            found: "other".to_string(),
+        }),
+    }
+}
+
+pub(crate) fn long_arg(args: &[Value], index: usize) -> Result<i64, JVMError> {
+    match args.get(index) {
+        Some(Value::Long(l)) => Ok(*l),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "long".to_string(),
+            found: "other".to_string(),
+        }),
+    }
+}
+
+pub(crate) fn float_arg(args: &[Value], index: usize) -> Result<f32, JVMError> {
+    match args.get(index) {
+        Some(Value::Float(f)) => Ok(*f),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "float".to_string(),
+            found: "other".to_string(),
+        }),
+    }
+}
+
+pub(crate) fn double_arg(args: &[Value], index: usize) -> Result<f64, JVMError> {
+    match args.get(index) {
+        Some(Value::Double(d)) => Ok(*d),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "double".to_string(),
+            found: "other".to_string(),
+        }),
+    }
+}
+
+pub(crate) fn returns(value: Value) -> Result<ExecutionResult, JVMError> {
+    Ok(ExecutionResult::Return(Some(value)))
+}
+
+pub(crate) fn throws(class_name: &str) -> Result<ExecutionResult, JVMError> {
+    Ok(ExecutionResult::Throw(class_name.to_string()))
+}
+
+fn object_get_class<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = receiver_arg(&args)?
+            .class
+            .clone()
+            .ok_or_else(|| JVMError::Other("Object has no class".to_string()))?;
+        returns(mirror_of(vm, stack, class).await?)
+    })
+}
+
+fn object_hash_code(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Int(receiver_arg(args)?.identity_hash()))
+}
+
+fn object_clone<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let receiver = receiver_arg(&args)?;
+        let cloneable = match (&receiver.kind, &receiver.class) {
+            (ObjectKind::ArrayInstance { .. }, _) => true,
+            (ObjectKind::ClassInstance { .. }, Some(class)) => {
+                let fut = Box::pin(vm.class_loader.load_class("java/lang/Cloneable", vm));
+                let cloneable = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+                class.is_subtype_of(&cloneable)
+            }
+            (ObjectKind::ClassInstance { .. }, None) => false,
+        };
+        if !cloneable {
+            return throws("java/lang/CloneNotSupportedException");
+        }
+        let mut heap = vm.heap.write().await;
+        let copy = heap.allocate_clone(stack, vm, &receiver).await?;
+        Ok(ExecutionResult::Return(Some(copy)))
+    })
+}
+
+// the interpreter runs a single Java thread, so nobody can be waiting on a monitor
+fn object_notify(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    receiver_arg(args)?;
+    Ok(ExecutionResult::Return(None))
+}
+
+// wait returns at once, which the JLS allows as a spurious wakeup
+fn object_wait(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    receiver_arg(args)?;
+    if long_arg(args, 1)? < 0 {
+        return throws("java/lang/IllegalArgumentException");
+    }
+    Ok(ExecutionResult::Return(None))
+}
+
+// exceptions are thrown by class name, there is no backtrace to record
+fn throwable_fill_in_stack_trace(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let receiver = receiver_arg(args)?;
+    returns(Value::Reference(Some(receiver)))
+}
+
+fn system_arraycopy(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let (src, dest) = match (reference_arg(args, 0)?, reference_arg(args, 2)?) {
+        (Some(src), Some(dest)) => (src, dest),
+        _ => return throws("java/lang/NullPointerException"),
+    };
+    let (src_pos, dest_pos, length) = (int_arg(args, 1)?, int_arg(args, 3)?, int_arg(args, 4)?);
+    let (src_elements, dest_elements) = match (&src.kind, &dest.kind) {
+        (
+            ObjectKind::ArrayInstance {
+                elements: src_elements,
+                element_type: src_type,
+                ..
+            },
+            ObjectKind::ArrayInstance {
+                elements: dest_elements,
+                element_type: dest_type,
+                ..
+            },
+        ) => {
+            // primitive arrays only copy into arrays of the very same type
+            let primitive = src_type.len() == 1 || dest_type.len() == 1;
+            if primitive && src_type != dest_type {
+                return throws("java/lang/ArrayStoreException");
+            }
+            (src_elements, dest_elements)
+        }
+        _ => return throws("java/lang/ArrayStoreException"),
+    };
+    let src_len = src_elements.borrow().len() as i64;
+    let dest_len = dest_elements.borrow().len() as i64;
+    if src_pos < 0
+        || dest_pos < 0
+        || length < 0
+        || src_pos as i64 + length as i64 > src_len
+        || dest_pos as i64 + length as i64 > dest_len
+    {
+        return throws("java/lang/ArrayIndexOutOfBoundsException");
+    }
+
+    // read everything first, source and destination may be the same array
+    let values = {
+        let src_elements = src_elements.borrow();
+        (0..length as usize)
+            .map(|i| src_elements.get(src_pos as usize + i).unwrap())
+            .collect::<Vec<_>>()
+    };
+    // reference elements are checked one by one unless the component types already guarantee it
+    let store_check = match (&src.class, &dest.class) {
+        (Some(src_class), Some(dest_class)) => {
+            match (&src_class.component_class, &dest_class.component_class) {
+                (Some(src_component), Some(dest_component))
+                    if !src_component.is_subtype_of(dest_component) =>
+                {
+                    Some(Arc::clone(dest_component))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let mut dest_elements = dest_elements.borrow_mut();
+    for (i, value) in values.into_iter().enumerate() {
+        if let (Some(component), Value::Reference(Some(obj))) = (&store_check, &value) {
+            if !obj
+                .class
+                .as_ref()
+                .is_some_and(|class| class.is_subtype_of(component))
+            {
+                return throws("java/lang/ArrayStoreException");
+            }
+        }
+        dest_elements.set(dest_pos as usize + i, value)?;
+    }
+    Ok(ExecutionResult::Return(None))
+}
+
+fn system_current_time_millis(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| JVMError::Other(e.to_string()))?
+        .as_millis();
+    returns(Value::Long(millis as i64))
+}
+
+static NANO_TIME_ORIGIN: Lazy<Instant> = Lazy::new(Instant::now);
+
+fn system_nano_time(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Long(NANO_TIME_ORIGIN.elapsed().as_nanos() as i64))
+}
+
+fn system_identity_hash_code(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let hash = reference_arg(args, 0)?.map_or(0, |obj| obj.identity_hash());
+    returns(Value::Int(hash))
+}
+
+fn float_to_raw_int_bits(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Int(float_arg(args, 0)?.to_bits() as i32))
+}
+
+fn int_bits_to_float(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Float(f32::from_bits(int_arg(args, 0)? as u32)))
+}
+
+fn double_to_raw_long_bits(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Long(double_arg(args, 0)?.to_bits() as i64))
+}
+
+fn long_bits_to_double(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Double(f64::from_bits(long_arg(args, 0)? as u64)))
+}
+
+// Java's text for a double or float: plain from 10^-3 up to 10^7 and computerized scientific
+// notation otherwise, always with a digit after the point. plain and scientific are the
+// shortest digits that read back as the value, as Rust's {} and {:e} print them.
+fn java_float_string(value: f64, plain: String, scientific: String) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let magnitude = value.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        return if plain.contains('.') {
+            plain
+        } else {
+            plain + ".0"
+        };
+    }
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    if mantissa.contains('.') {
+        format!("{}E{}", mantissa, exponent)
+    } else {
+        format!("{}.0E{}", mantissa, exponent)
+    }
+}
+
+fn double_to_string<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let d = double_arg(&args, 0)?;
+        let text = java_float_string(d, format!("{}", d), format!("{:e}", d));
+        let mut heap = vm.heap.write().await;
+        returns(heap.allocate_string(stack, vm, &text).await?)
+    })
+}
+
+fn float_to_string<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let f = float_arg(&args, 0)?;
+        let text = java_float_string(f as f64, format!("{}", f), format!("{:e}", f));
+        let mut heap = vm.heap.write().await;
+        returns(heap.allocate_string(stack, vm, &text).await?)
+    })
+}
+
+// the decimal forms Double.parseDouble takes: surrounding control chars and spaces, a sign,
+// NaN, Infinity or digits with an optional point and exponent, and a type suffix. Hexadecimal
+// floats are not read.
+fn parse_java_float<T: std::str::FromStr>(text: &str) -> Option<T> {
+    let text = text.trim_matches(|c: char| c <= ' ');
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let sign = &text[..text.len() - unsigned.len()];
+    if unsigned == "NaN" || unsigned == "Infinity" {
+        return format!("{}{}", sign, unsigned).parse().ok();
+    }
+    let number = unsigned
+        .strip_suffix(['f', 'F', 'd', 'D'])
+        .unwrap_or(unsigned);
+    if !number
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+    format!("{}{}", sign, number).parse().ok()
+}
+
+fn parse_double(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let Some(text) = reference_arg(args, 0)? else {
+        return throws("java/lang/NullPointerException");
+    };
+    match parse_java_float(&extract_string(&text).map_err(JVMError::Other)?) {
+        Some(d) => returns(Value::Double(d)),
+        None => throws("java/lang/NumberFormatException"),
+    }
+}
+
+fn parse_float(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let Some(text) = reference_arg(args, 0)? else {
+        return throws("java/lang/NullPointerException");
+    };
+    match parse_java_float(&extract_string(&text).map_err(JVMError::Other)?) {
+        Some(f) => returns(Value::Float(f)),
+        None => throws("java/lang/NumberFormatException"),
+    }
+}
+
+fn unary_math(args: &[Value], function: fn(f64) -> f64) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Double(function(double_arg(args, 0)?)))
+}
+
+fn binary_math(args: &[Value], function: fn(f64, f64) -> f64) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Double(function(
+        double_arg(args, 0)?,
+        double_arg(args, 1)?,
+    )))
+}
+
+// Math.pow differs from C's pow in that a NaN exponent gives NaN even for a base of 1 and
+// that 1 to the power of an infinity is NaN
+fn java_pow(x: f64, y: f64) -> f64 {
+    if y.is_nan() || (x.abs() == 1.0 && y.is_infinite()) {
+        return f64::NAN;
+    }
+    x.powf(y)
+}
+
+// IEEE 754 remainder: x - y * n with n the integer nearest to x / y, ties to even
+fn ieee_remainder(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() || x.is_infinite() || y == 0.0 {
+        return f64::NAN;
+    }
+    if y.is_infinite() {
+        return x;
+    }
+    let remainder = x % y;
+    let half = y.abs() / 2.0;
+    let result = if remainder.abs() > half
+        || (remainder.abs() == half && ((x - remainder) / y) % 2.0 != 0.0)
+    {
+        remainder - y.abs().copysign(remainder)
+    } else {
+        remainder
+    };
+    if result == 0.0 {
+        0.0f64.copysign(x)
+    } else {
+        result
+    }
+}
+
+// the main thread is created by VM::initialize_system and kept as a heap root
+fn thread_current_thread<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    _args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let thread = vm.heap.read().await.main_thread.clone().ok_or_else(|| {
+            JVMError::Other("The main thread has not been created yet".to_string())
+        })?;
+        returns(Value::Reference(Some(thread)))
+    })
+}
+
+fn runtime_available_processors(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    receiver_arg(args)?;
+    let processors = std::thread::available_parallelism().map_or(1, |n| n.get());
+    returns(Value::Int(processors as i32))
+}
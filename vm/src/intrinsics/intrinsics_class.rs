@@ -0,0 +1,298 @@
+use super::intrinsics::{
+    class_arg, int_arg, mirror_of, reference_arg, returns, throws, Intrinsic, IntrinsicFuture,
+    IntrinsicRegistry,
+};
+use crate::class_loader::class_loader::PRIMITIVE_TYPES;
+use crate::class_loader::loaded_class::LoadedClass;
+use crate::execute::execute::ExecutionResult;
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::runtime::*;
+use crate::vm::VM;
+use parser::access_flag::ClassFlags;
+use std::sync::Arc;
+
+// natives of java.lang.Class and jdk.internal.reflect.Reflection, the receiver is a mirror
+// created by Heap::allocate_class
+const CLASS: &str = "java/lang/Class";
+const REFLECTION: &str = "jdk/internal/reflect/Reflection";
+
+pub fn register(registry: &mut IntrinsicRegistry) {
+    registry.register(
+        CLASS,
+        "getPrimitiveClass",
+        "(Ljava/lang/String;)Ljava/lang/Class;",
+        Intrinsic::Async(get_primitive_class),
+    );
+    registry.register(
+        CLASS,
+        "desiredAssertionStatus0",
+        "(Ljava/lang/Class;)Z",
+        Intrinsic::Sync(desired_assertion_status),
+    );
+    registry.register(
+        CLASS,
+        "forName0",
+        "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+        Intrinsic::Async(for_name),
+    );
+    registry.register(
+        CLASS,
+        "initClassName",
+        "()Ljava/lang/String;",
+        Intrinsic::Async(init_class_name),
+    );
+    registry.register(
+        CLASS,
+        "isInstance",
+        "(Ljava/lang/Object;)Z",
+        Intrinsic::Async(is_instance),
+    );
+    registry.register(
+        CLASS,
+        "isAssignableFrom",
+        "(Ljava/lang/Class;)Z",
+        Intrinsic::Async(is_assignable_from),
+    );
+    registry.register(CLASS, "isInterface", "()Z", Intrinsic::Async(is_interface));
+    registry.register(CLASS, "isArray", "()Z", Intrinsic::Async(is_array));
+    registry.register(CLASS, "isPrimitive", "()Z", Intrinsic::Async(is_primitive));
+    registry.register(
+        CLASS,
+        "getModifiers",
+        "()I",
+        Intrinsic::Async(get_modifiers),
+    );
+    registry.register(
+        CLASS,
+        "getSuperclass",
+        "()Ljava/lang/Class;",
+        Intrinsic::Async(get_superclass),
+    );
+    registry.register(
+        CLASS,
+        "getInterfaces0",
+        "()[Ljava/lang/Class;",
+        Intrinsic::Async(get_interfaces),
+    );
+    // hidden classes, records, nests and generic signatures do not exist here
+    registry.register(CLASS, "isHidden", "()Z", Intrinsic::Sync(returns_false));
+    registry.register(CLASS, "isRecord0", "()Z", Intrinsic::Sync(returns_false));
+    registry.register(
+        CLASS,
+        "getNestHost0",
+        "()Ljava/lang/Class;",
+        Intrinsic::Sync(returns_receiver),
+    );
+    for (name, descriptor) in [
+        ("getDeclaringClass0", "()Ljava/lang/Class;"),
+        ("getSimpleBinaryName0", "()Ljava/lang/String;"),
+        ("getGenericSignature0", "()Ljava/lang/String;"),
+        ("getEnclosingMethod0", "()[Ljava/lang/Object;"),
+        ("getRawAnnotations", "()[B"),
+        ("getProtectionDomain0", "()Ljava/security/ProtectionDomain;"),
+    ] {
+        registry.register(CLASS, name, descriptor, Intrinsic::Sync(returns_null));
+    }
+
+    registry.register(
+        REFLECTION,
+        "getCallerClass",
+        "()Ljava/lang/Class;",
+        Intrinsic::Async(get_caller_class),
+    );
+    registry.register(
+        REFLECTION,
+        "getClassAccessFlags",
+        "(Ljava/lang/Class;)I",
+        Intrinsic::Async(get_class_access_flags),
+    );
+    registry.register(
+        REFLECTION,
+        "areNestMates",
+        "(Ljava/lang/Class;Ljava/lang/Class;)Z",
+        Intrinsic::Async(are_nest_mates),
+    );
+}
+
+fn returns_false(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Int(0))
+}
+
+fn returns_null(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Reference(None))
+}
+
+fn returns_receiver(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Reference(reference_arg(args, 0)?))
+}
+
+fn is_primitive_class(class: &LoadedClass) -> bool {
+    PRIMITIVE_TYPES.contains(&class.class_name.as_str())
+}
+
+fn get_primitive_class<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let name = reference_arg(&args, 0)?.ok_or(JVMError::NullReference)?;
+        let name = extract_string(&name).map_err(JVMError::Other)?;
+        let fut = Box::pin(vm.class_loader.load_class(&name, vm));
+        let class = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        returns(mirror_of(vm, stack, class).await?)
+    })
+}
+
+// assertions are disabled, as with java run without -ea
+fn desired_assertion_status(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Int(0))
+}
+
+fn for_name<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let name = reference_arg(&args, 0)?.ok_or(JVMError::NullReference)?;
+        let name = extract_string(&name).map_err(JVMError::Other)?;
+        // binary names only, primitive types have no Class.forName
+        if name.contains('/') || PRIMITIVE_TYPES.contains(&name.as_str()) {
+            return throws("java/lang/ClassNotFoundException");
+        }
+        let internal_name = name.replace('.', "/");
+        let fut = Box::pin(vm.class_loader.load_class(&internal_name, vm));
+        let class = match fut.await {
+            Ok(class) => class,
+            Err(_) => return throws("java/lang/ClassNotFoundException"),
+        };
+        if int_arg(&args, 1)? != 0 {
+            Box::pin(LoadedClass::initialize(Arc::clone(&class), vm)).await?;
+        }
+        returns(mirror_of(vm, stack, class).await?)
+    })
+}
+
+// mirrors are created with their name, this only runs for a name cleared by Java code
+fn init_class_name<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        let name = {
+            let mut heap = vm.heap.write().await;
+            heap.allocate_string(stack, vm, &class.class_name.replace('/', "."))
+                .await?
+        };
+        reference_arg(&args, 0)?
+            .ok_or(JVMError::NullReference)?
+            .set_field("name", name.clone())?;
+        returns(name)
+    })
+}
+
+fn is_instance<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        let instance = reference_arg(&args, 1)?
+            .and_then(|obj| obj.class.clone())
+            .is_some_and(|obj_class| obj_class.is_subtype_of(&class));
+        returns(Value::Int(instance as i32))
+    })
+}
+
+fn is_assignable_from<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        let other = class_arg(vm, &args, 1).await?;
+        returns(Value::Int(other.is_subtype_of(&class) as i32))
+    })
+}
+
+fn is_interface<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        returns(Value::Int(class.is_interface() as i32))
+    })
+}
+
+fn is_array<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        returns(Value::Int(class.is_array() as i32))
+    })
+}
+
+fn is_primitive<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        returns(Value::Int(is_primitive_class(&class) as i32))
+    })
+}
+
+// ACC_SUPER is a class file detail and not a modifier
+fn modifiers(class: &LoadedClass) -> i32 {
+    (class.access_flags.bits() & !ClassFlags::ACC_SUPER.bits()) as i32
+}
+
+fn get_modifiers<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        returns(Value::Int(modifiers(&class)))
+    })
+}
+
+fn get_superclass<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        match &class.super_class {
+            Some(super_class) if !class.is_interface() => {
+                returns(mirror_of(vm, stack, Arc::clone(super_class)).await?)
+            }
+            _ => returns(Value::Reference(None)),
+        }
+    })
+}
+
+fn get_interfaces<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        let array = vm
+            .allocate_array(stack, "Ljava/lang/Class;", class.interfaces.len())
+            .await?;
+        if let Value::Reference(Some(array)) = &array {
+            for (i, interface) in class.interfaces.iter().enumerate() {
+                let mirror = mirror_of(vm, stack, Arc::clone(interface)).await?;
+                array.set_element(i, mirror)?;
+            }
+        }
+        returns(array)
+    })
+}
+
+// the frame that called getCallerClass is on top, its caller is the one asked for
+fn get_caller_class<'a>(vm: &'a VM, stack: &'a Stack, _args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        match stack.frames.iter().rev().nth(1) {
+            Some(frame) => returns(mirror_of(vm, stack, Arc::clone(&frame.class)).await?),
+            None => returns(Value::Reference(None)),
+        }
+    })
+}
+
+fn get_class_access_flags<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 0).await?;
+        returns(Value::Int(class.access_flags.bits() as i32))
+    })
+}
+
+// without NestHost attributes every class is its own nest, shared by top-level and nested
+// classes of the same source file
+fn are_nest_mates<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let host = |class: &LoadedClass| {
+            let name = &class.class_name;
+            name.split('$').next().unwrap_or(name).to_string()
+        };
+        let first = class_arg(vm, &args, 0).await?;
+        let second = class_arg(vm, &args, 1).await?;
+        returns(Value::Int((host(&first) == host(&second)) as i32))
+    })
+}
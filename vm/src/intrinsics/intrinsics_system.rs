@@ -0,0 +1,793 @@
+use super::intrinsics::{
+    int_arg, long_arg, receiver_arg, reference_arg, returns, string_array, throws, Intrinsic,
+    IntrinsicFuture, IntrinsicRegistry,
+};
+use crate::class_loader::java_home::MIN_FEATURE_VERSION;
+use crate::class_loader::loaded_class::{LoadedClass, NameDes};
+use crate::execute::execute::ExecutionResult;
+use crate::jvm_error::JVMError;
+use crate::native::{extract_string, find_library, library_path};
+use crate::object::{Object, ObjectKind};
+use crate::runtime::*;
+use crate::vm::VM;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+// natives behind System.initPhase1: system properties, the standard streams and the
+// handful of runtime queries made while the JDK starts up
+const RAW_PROPS: &str = "jdk/internal/util/SystemProps$Raw";
+const MISC_VM: &str = "jdk/internal/misc/VM";
+const FILE_DESCRIPTOR: &str = "java/io/FileDescriptor";
+const FILE_OUTPUT_STREAM: &str = "java/io/FileOutputStream";
+const FILE_INPUT_STREAM: &str = "java/io/FileInputStream";
+
+pub fn register(registry: &mut IntrinsicRegistry) {
+    registry.register(
+        RAW_PROPS,
+        "vmProperties",
+        "()[Ljava/lang/String;",
+        Intrinsic::Async(vm_properties),
+    );
+    registry.register(
+        RAW_PROPS,
+        "platformProperties",
+        "()[Ljava/lang/String;",
+        Intrinsic::Async(platform_properties),
+    );
+
+    registry.register(
+        "java/lang/System",
+        "setIn0",
+        "(Ljava/io/InputStream;)V",
+        Intrinsic::Async(|vm, _stack, args| Box::pin(set_stream(vm, args, "in"))),
+    );
+    registry.register(
+        "java/lang/System",
+        "setOut0",
+        "(Ljava/io/PrintStream;)V",
+        Intrinsic::Async(|vm, _stack, args| Box::pin(set_stream(vm, args, "out"))),
+    );
+    registry.register(
+        "java/lang/System",
+        "setErr0",
+        "(Ljava/io/PrintStream;)V",
+        Intrinsic::Async(|vm, _stack, args| Box::pin(set_stream(vm, args, "err"))),
+    );
+    registry.register(
+        "java/lang/System",
+        "mapLibraryName",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        Intrinsic::Async(map_library_name),
+    );
+
+    // the bundled class library has no Properties, getProperty asks the VM each time
+    registry.register(
+        "java/lang/System",
+        "getProperty",
+        "(Ljava/lang/String;)Ljava/lang/String;",
+        Intrinsic::Async(system_get_property),
+    );
+    registry.register(MISC_VM, "initialize", "()V", Intrinsic::Sync(returns_void));
+    registry.register(
+        MISC_VM,
+        "latestUserDefinedLoader0",
+        "()Ljava/lang/ClassLoader;",
+        Intrinsic::Sync(|_| returns(Value::Reference(None))),
+    );
+    for name in ["getuid", "geteuid", "getgid", "getegid"] {
+        registry.register(MISC_VM, name, "()J", Intrinsic::Sync(user_id));
+    }
+    registry.register(
+        MISC_VM,
+        "getNanoTimeAdjustment",
+        "(J)J",
+        Intrinsic::Sync(nano_time_adjustment),
+    );
+    registry.register(
+        MISC_VM,
+        "getRuntimeArguments",
+        "()[Ljava/lang/String;",
+        Intrinsic::Async(runtime_arguments),
+    );
+
+    // descriptors wrap the process file descriptors directly, there are no handles
+    registry.register(
+        FILE_DESCRIPTOR,
+        "getHandle",
+        "(I)J",
+        Intrinsic::Sync(|_| returns(Value::Long(-1))),
+    );
+    registry.register(
+        FILE_DESCRIPTOR,
+        "getAppend",
+        "(I)Z",
+        Intrinsic::Sync(|_| returns(Value::Int(0))),
+    );
+    registry.register(FILE_DESCRIPTOR, "close0", "()V", Intrinsic::Sync(returns_void));
+    registry.register(FILE_DESCRIPTOR, "sync", "()V", Intrinsic::Sync(returns_void));
+    registry.register(
+        FILE_OUTPUT_STREAM,
+        "writeBytes",
+        "([BIIZ)V",
+        Intrinsic::Sync(file_output_write_bytes),
+    );
+    registry.register(
+        FILE_OUTPUT_STREAM,
+        "write",
+        "(IZ)V",
+        Intrinsic::Sync(file_output_write),
+    );
+    registry.register(
+        FILE_INPUT_STREAM,
+        "readBytes",
+        "([BII)I",
+        Intrinsic::Sync(file_input_read_bytes),
+    );
+    registry.register(FILE_INPUT_STREAM, "read0", "()I", Intrinsic::Sync(file_input_read));
+    registry.register(
+        FILE_INPUT_STREAM,
+        "available0",
+        "()I",
+        Intrinsic::Sync(|_| returns(Value::Int(0))),
+    );
+
+    // signal handlers are accepted but never run, the default action stays in place
+    registry.register(
+        "jdk/internal/misc/Signal",
+        "findSignal0",
+        "(Ljava/lang/String;)I",
+        Intrinsic::Sync(find_signal),
+    );
+    registry.register(
+        "jdk/internal/misc/Signal",
+        "handle0",
+        "(IJ)J",
+        Intrinsic::Sync(|_| returns(Value::Long(0))),
+    );
+
+    registry.register(
+        "java/lang/StringUTF16",
+        "isBigEndian",
+        "()Z",
+        Intrinsic::Sync(|_| returns(Value::Int(cfg!(target_endian = "big") as i32))),
+    );
+    registry.register(
+        "java/lang/Runtime",
+        "freeMemory",
+        "()J",
+        Intrinsic::Async(runtime_free_memory),
+    );
+    for name in ["totalMemory", "maxMemory"] {
+        registry.register(
+            "java/lang/Runtime",
+            name,
+            "()J",
+            Intrinsic::Async(runtime_total_memory),
+        );
+    }
+    registry.register("java/lang/Runtime", "gc", "()V", Intrinsic::Sync(returns_void));
+    // System.load and System.loadLibrary end up here, the JDK would only search the boot
+    // library path for classes of the boot loader, which all classes are here
+    registry.replace(
+        "java/lang/Runtime",
+        "load0",
+        "(Ljava/lang/Class;Ljava/lang/String;)V",
+        Intrinsic::Async(runtime_load),
+    );
+    registry.replace(
+        "java/lang/Runtime",
+        "loadLibrary0",
+        "(Ljava/lang/Class;Ljava/lang/String;)V",
+        Intrinsic::Async(runtime_load_library),
+    );
+    registry.register(
+        "java/lang/Shutdown",
+        "beforeHalt",
+        "()V",
+        Intrinsic::Sync(returns_void),
+    );
+    registry.register("java/lang/Shutdown", "halt0", "(I)V", Intrinsic::Sync(halt));
+
+    // a single thread with no security manager, so there is no access control context
+    for (name, descriptor) in [
+        (
+            "getStackAccessControlContext",
+            "()Ljava/security/AccessControlContext;",
+        ),
+        (
+            "getInheritedAccessControlContext",
+            "()Ljava/security/AccessControlContext;",
+        ),
+        (
+            "getProtectionDomain",
+            "(Ljava/lang/Class;)Ljava/security/ProtectionDomain;",
+        ),
+    ] {
+        registry.register(
+            "java/security/AccessController",
+            name,
+            descriptor,
+            Intrinsic::Sync(|_| returns(Value::Reference(None))),
+        );
+    }
+    registry.register(
+        "java/security/AccessController",
+        "ensureMaterializedForStackWalk",
+        "(Ljava/lang/Object;)V",
+        Intrinsic::Sync(returns_void),
+    );
+    for (name, descriptor) in [
+        ("setPriority0", "(I)V"),
+        ("setNativeName", "(Ljava/lang/String;)V"),
+        ("interrupt0", "()V"),
+        ("yield", "()V"),
+    ] {
+        registry.register(
+            "java/lang/Thread",
+            name,
+            descriptor,
+            Intrinsic::Sync(returns_void),
+        );
+    }
+    registry.register("java/lang/Thread", "start0", "()V", Intrinsic::Async(thread_start));
+    // a thread has finished by the time start0 returns
+    registry.register(
+        "java/lang/Thread",
+        "isAlive",
+        "()Z",
+        Intrinsic::Sync(|_| returns(Value::Int(0))),
+    );
+    registry.register(
+        "java/lang/Thread",
+        "holdsLock",
+        "(Ljava/lang/Object;)Z",
+        Intrinsic::Sync(|_| returns(Value::Int(1))),
+    );
+    registry.register("java/lang/Thread", "sleep", "(J)V", Intrinsic::Sync(thread_sleep));
+    registry.register(
+        "java/lang/ref/Reference",
+        "hasReferencePendingList",
+        "()Z",
+        Intrinsic::Sync(|_| returns(Value::Int(0))),
+    );
+    registry.register(
+        "java/lang/ref/Reference",
+        "refersTo0",
+        "(Ljava/lang/Object;)Z",
+        Intrinsic::Sync(reference_refers_to),
+    );
+    registry.register(
+        "java/lang/ref/Reference",
+        "clear0",
+        "()V",
+        Intrinsic::Sync(reference_clear),
+    );
+}
+
+fn returns_void(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    Ok(ExecutionResult::Return(None))
+}
+
+// the properties HotSpot itself defines followed by those set from outside, as key/value
+// pairs. SystemProps puts them before the platform properties, which only fill in the gaps.
+fn vm_property_pairs(vm: &VM) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    if let Some(java_home) = &vm.java_home {
+        let java_home = java_home.path.to_string_lossy().into_owned();
+        pairs.push(("java.home".to_string(), java_home.clone()));
+        pairs.push(("sun.boot.library.path".to_string(), format!("{}/lib", java_home)));
+    } else {
+        // java.base reports its version from VersionProps, the bundled class library has none
+        // and passes for the oldest release the VM supports
+        pairs.extend([
+            ("java.version".to_string(), MIN_FEATURE_VERSION.to_string()),
+            ("java.specification.version".to_string(), MIN_FEATURE_VERSION.to_string()),
+            ("java.vendor".to_string(), "crate".to_string()),
+        ]);
+    }
+    // the bundled class library is compiled for the oldest JDK the VM supports
+    let specification_version = vm
+        .java_home
+        .as_ref()
+        .map_or(MIN_FEATURE_VERSION, |java_home| java_home.feature_version);
+    pairs.extend([
+        ("java.library.path".to_string(), library_path(vm)),
+        ("java.class.path".to_string(), ".".to_string()),
+        ("java.vm.specification.name".to_string(), "Java Virtual Machine Specification".to_string()),
+        ("java.vm.specification.vendor".to_string(), "Oracle Corporation".to_string()),
+        (
+            "java.vm.specification.version".to_string(),
+            specification_version.to_string(),
+        ),
+        ("java.vm.name".to_string(), "crate".to_string()),
+        ("java.vm.vendor".to_string(), "crate".to_string()),
+        ("java.vm.version".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+        ("java.vm.info".to_string(), "interpreted mode".to_string()),
+        ("jdk.debug".to_string(), "release".to_string()),
+    ]);
+    pairs.extend(vm.property_overrides.iter().cloned());
+    pairs
+}
+
+fn vm_properties<'a>(vm: &'a VM, stack: &'a Stack, _args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let strings = vm_property_pairs(vm)
+            .into_iter()
+            .flat_map(|(key, value)| [Some(key), Some(value)])
+            .collect::<Vec<_>>();
+        returns(string_array(vm, stack, &strings).await?)
+    })
+}
+
+// platform properties by the name of their index constant in SystemProps.Raw, the indices and
+// names change between JDK versions so they are looked up in the class itself
+fn platform_property_values() -> Vec<(&'static str, Option<String>)> {
+    // LANG looks like en_US.UTF-8
+    let locale = std::env::var("LANG").unwrap_or_default();
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (language, country) = match locale.split_once('_') {
+        Some((language, country)) => (language, country),
+        None => ("en", ""),
+    };
+    let language = if language == "C" || language == "POSIX" {
+        "en"
+    } else {
+        language
+    };
+    let os_arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        arch => arch,
+    };
+    let os_name = match std::env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Mac OS X",
+        "windows" => "Windows",
+        os => os,
+    };
+    let (endian, unicode_encoding) = if cfg!(target_endian = "big") {
+        ("big", "UnicodeBig")
+    } else {
+        ("little", "UnicodeLittle")
+    };
+    let utf8 = || Some("UTF-8".to_string());
+    vec![
+        ("_display_language_NDX", Some(language.to_string())),
+        (
+            "_display_country_NDX",
+            Some(country.to_string()).filter(|country| !country.is_empty()),
+        ),
+        ("_file_encoding_NDX", utf8()),
+        ("_native_encoding_NDX", utf8()),
+        ("_sun_jnu_encoding_NDX", utf8()),
+        ("_stdout_encoding_NDX", utf8()),
+        ("_stderr_encoding_NDX", utf8()),
+        ("_sun_stdout_encoding_NDX", utf8()),
+        ("_sun_stderr_encoding_NDX", utf8()),
+        ("_file_separator_NDX", Some(std::path::MAIN_SEPARATOR.to_string())),
+        (
+            "_path_separator_NDX",
+            Some(if cfg!(windows) { ";" } else { ":" }.to_string()),
+        ),
+        (
+            "_line_separator_NDX",
+            Some(if cfg!(windows) { "\r\n" } else { "\n" }.to_string()),
+        ),
+        (
+            "_java_io_tmpdir_NDX",
+            Some(std::env::temp_dir().to_string_lossy().into_owned()),
+        ),
+        ("_os_arch_NDX", Some(os_arch.to_string())),
+        ("_os_name_NDX", Some(os_name.to_string())),
+        (
+            "_os_version_NDX",
+            std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .ok()
+                .map(|version| version.trim().to_string()),
+        ),
+        ("_sun_arch_data_model_NDX", Some(usize::BITS.to_string())),
+        ("_sun_cpu_endian_NDX", Some(endian.to_string())),
+        ("_sun_io_unicode_encoding_NDX", Some(unicode_encoding.to_string())),
+        (
+            "_user_dir_NDX",
+            std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().into_owned()),
+        ),
+        (
+            "_user_home_NDX",
+            std::env::var("HOME").ok().or_else(|| password_entry(|entry| entry.pw_dir)),
+        ),
+        (
+            "_user_name_NDX",
+            std::env::var("USER").ok().or_else(|| password_entry(|entry| entry.pw_name)),
+        ),
+    ]
+}
+
+// a field of the password database entry of the user running the VM, for when the
+// environment does not say. SystemProps reports "?" for a missing user.name or user.home.
+fn password_entry(field: fn(&libc::passwd) -> *mut libc::c_char) -> Option<String> {
+    let mut entry = std::mem::MaybeUninit::<libc::passwd>::uninit();
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+    // getpwuid_r fills entry with pointers into buffer, both outlive their use here
+    unsafe {
+        let status = libc::getpwuid_r(
+            libc::getuid(),
+            entry.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if status != 0 || result.is_null() {
+            return None;
+        }
+        let value = field(&entry.assume_init());
+        if value.is_null() {
+            return None;
+        }
+        Some(std::ffi::CStr::from_ptr(value).to_string_lossy().into_owned())
+    }
+}
+
+fn platform_properties<'a>(
+    vm: &'a VM,
+    stack: &'a Stack,
+    _args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let fut = Box::pin(vm.class_loader.load_class(RAW_PROPS, vm));
+        let raw = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+        let length = match raw.get_static_field("FIXED_LENGTH")? {
+            Value::Int(length) => length as usize,
+            _ => return Err(JVMError::Other("Invalid SystemProps.Raw.FIXED_LENGTH".to_string())),
+        };
+        let mut values = vec![None; length];
+        for (index_name, value) in platform_property_values() {
+            if let Ok(Value::Int(index)) = raw.get_static_field(index_name) {
+                values[index as usize] = value;
+            }
+        }
+        returns(string_array(vm, stack, &values).await?)
+    })
+}
+
+// the property as vm_property_pairs has it, otherwise the platform property whose index
+// constant in SystemProps.Raw the key turns into, _user_dir_NDX for user.dir
+fn system_get_property<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let Some(key) = reference_arg(&args, 0)? else {
+            return throws("java/lang/NullPointerException");
+        };
+        let key = extract_string(&key).map_err(JVMError::Other)?;
+        if key.is_empty() {
+            return throws("java/lang/IllegalArgumentException");
+        }
+        let index_name = format!("_{}_NDX", key.replace('.', "_"));
+        let value = vm_property_pairs(vm)
+            .into_iter()
+            .rev()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+            .or_else(|| {
+                platform_property_values()
+                    .into_iter()
+                    .find(|(name, _)| *name == index_name)
+                    .and_then(|(_, value)| value)
+            });
+        match value {
+            Some(value) => {
+                let mut heap = vm.heap.write().await;
+                returns(heap.allocate_string(stack, vm, &value).await?)
+            }
+            None => returns(Value::Reference(None)),
+        }
+    })
+}
+
+// System.in, out and err are final, Java code cannot assign them after <clinit>
+async fn set_stream(
+    vm: &VM,
+    args: Vec<Value>,
+    field: &str,
+) -> Result<ExecutionResult, JVMError> {
+    let stream = Value::Reference(reference_arg(&args, 0)?);
+    let fut = Box::pin(vm.class_loader.load_class("java/lang/System", vm));
+    let system = fut.await.map_err(|e| JVMError::Other(e.to_string()))?;
+    system.set_static_field(field, stream)?;
+    Ok(ExecutionResult::Return(None))
+}
+
+fn map_library_name<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let name = reference_arg(&args, 0)?.ok_or(JVMError::NullReference)?;
+        let name = extract_string(&name).map_err(JVMError::Other)?;
+        let mut heap = vm.heap.write().await;
+        returns(heap.allocate_string(stack, vm, &format!("lib{}.so", name)).await?)
+    })
+}
+
+fn user_id(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    // only consulted to decide whether the process runs setuid
+    returns(Value::Long(0))
+}
+
+// the offset of the current time from offset_in_seconds, in nanoseconds
+fn nano_time_adjustment(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let offset_in_seconds = long_arg(args, 0)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| JVMError::Other(e.to_string()))?;
+    let seconds = now.as_secs() as i64 - offset_in_seconds;
+    // HotSpot reports -1 when the adjustment does not fit in a long
+    match seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|nanos| nanos.checked_add(now.subsec_nanos() as i64))
+    {
+        Some(adjustment) => returns(Value::Long(adjustment)),
+        None => returns(Value::Long(-1)),
+    }
+}
+
+fn runtime_arguments<'a>(vm: &'a VM, stack: &'a Stack, _args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move { returns(string_array(vm, stack, &[]).await?) })
+}
+
+// the fd field of the FileDescriptor held by a file stream
+fn stream_fd(stream: &Arc<Object>) -> Result<i32, JVMError> {
+    let descriptor = match stream.get_field("fd")? {
+        Value::Reference(Some(descriptor)) => descriptor,
+        _ => return Err(JVMError::NullReference),
+    };
+    match descriptor.get_field("fd")? {
+        Value::Int(fd) => Ok(fd),
+        _ => Err(JVMError::TypeMismatch {
+            expected: "int".to_string(),
+            found: "other".to_string(),
+        }),
+    }
+}
+
+fn write_fd(fd: i32, bytes: &[u8]) -> Result<ExecutionResult, JVMError> {
+    let written = match fd {
+        1 => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(bytes).and_then(|_| stdout.flush())
+        }
+        2 => std::io::stderr().lock().write_all(bytes),
+        // files are not opened yet, the standard streams are all there is
+        _ => return throws("java/io/IOException"),
+    };
+    match written {
+        Ok(()) => Ok(ExecutionResult::Return(None)),
+        Err(_) => throws("java/io/IOException"),
+    }
+}
+
+fn file_output_write_bytes(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let fd = stream_fd(&receiver_arg(args)?)?;
+    let array = reference_arg(args, 1)?.ok_or(JVMError::NullReference)?;
+    let (offset, len) = (int_arg(args, 2)?, int_arg(args, 3)?);
+    if offset < 0 || len < 0 {
+        return throws("java/lang/IndexOutOfBoundsException");
+    }
+    let bytes = match &array.kind {
+        ObjectKind::ArrayInstance { elements, .. } => elements
+            .borrow()
+            .read_bytes(offset as usize, len as usize),
+        _ => None,
+    };
+    match bytes {
+        Some(bytes) => write_fd(fd, &bytes),
+        None => throws("java/lang/IndexOutOfBoundsException"),
+    }
+}
+
+fn file_output_write(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let fd = stream_fd(&receiver_arg(args)?)?;
+    write_fd(fd, &[int_arg(args, 1)? as u8])
+}
+
+fn read_fd(fd: i32, buffer: &mut [u8]) -> Result<Option<usize>, JVMError> {
+    if fd != 0 {
+        return Ok(None);
+    }
+    std::io::stdin()
+        .lock()
+        .read(buffer)
+        .map(Some)
+        .map_err(|e| JVMError::Other(e.to_string()))
+}
+
+fn file_input_read_bytes(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let fd = stream_fd(&receiver_arg(args)?)?;
+    let array = reference_arg(args, 1)?.ok_or(JVMError::NullReference)?;
+    let (offset, len) = (int_arg(args, 2)?, int_arg(args, 3)?);
+    if offset < 0 || len < 0 {
+        return throws("java/lang/IndexOutOfBoundsException");
+    }
+    if len == 0 {
+        return returns(Value::Int(0));
+    }
+    let ObjectKind::ArrayInstance { elements, .. } = &array.kind else {
+        return Err(JVMError::Other("readBytes into a non-array".to_string()));
+    };
+    let mut buffer = vec![0; len as usize];
+    match read_fd(fd, &mut buffer)? {
+        // end of stream
+        Some(0) => returns(Value::Int(-1)),
+        Some(read) => {
+            elements
+                .borrow_mut()
+                .write_bytes(offset as usize, &buffer[..read])?;
+            returns(Value::Int(read as i32))
+        }
+        None => throws("java/io/IOException"),
+    }
+}
+
+fn file_input_read(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let fd = stream_fd(&receiver_arg(args)?)?;
+    let mut buffer = [0];
+    match read_fd(fd, &mut buffer)? {
+        Some(0) => returns(Value::Int(-1)),
+        Some(_) => returns(Value::Int(buffer[0] as i32)),
+        None => throws("java/io/IOException"),
+    }
+}
+
+// Linux signal numbers, Signal's constructor rejects names that are missing here
+fn find_signal(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let name = reference_arg(args, 0)?.ok_or(JVMError::NullReference)?;
+    let number = match extract_string(&name).map_err(JVMError::Other)?.as_str() {
+        "HUP" => 1,
+        "INT" => 2,
+        "QUIT" => 3,
+        "KILL" => 9,
+        "TERM" => 15,
+        _ => -1,
+    };
+    returns(Value::Int(number))
+}
+
+fn runtime_free_memory<'a>(vm: &'a VM, _stack: &'a Stack, _args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let heap = vm.heap.read().await;
+        returns(Value::Long((heap.size - heap.young_count - heap.old_count) as i64))
+    })
+}
+
+fn runtime_total_memory<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    _args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let heap = vm.heap.read().await;
+        returns(Value::Long(heap.size as i64))
+    })
+}
+
+// the file name argument of load0 and loadLibrary0, after the receiver and the caller class
+fn library_arg(args: &[Value]) -> Result<Option<String>, JVMError> {
+    reference_arg(args, 2)?
+        .map(|name| extract_string(&name).map_err(JVMError::Other))
+        .transpose()
+}
+
+fn runtime_load<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let Some(file_name) = library_arg(&args)? else {
+            return throws("java/lang/NullPointerException");
+        };
+        let path = Path::new(&file_name);
+        if !path.is_absolute() {
+            return throws("java/lang/UnsatisfiedLinkError");
+        }
+        vm.native_stack.load_library(vm, stack, path)
+    })
+}
+
+fn runtime_load_library<'a>(
+    vm: &'a VM,
+    stack: &'a Stack,
+    args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let Some(lib_name) = library_arg(&args)? else {
+            return throws("java/lang/NullPointerException");
+        };
+        if lib_name.contains('/') {
+            return throws("java/lang/UnsatisfiedLinkError");
+        }
+        match find_library(vm, &lib_name) {
+            Some(path) => vm.native_stack.load_library(vm, stack, &path),
+            None => throws("java/lang/UnsatisfiedLinkError"),
+        }
+    })
+}
+
+fn halt(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let _ = std::io::stdout().flush();
+    Err(JVMError::Exit(int_arg(args, 0)?))
+}
+
+// the JDK's own service threads, which wait for work forever
+const SERVICE_THREADS: [&str; 3] = [
+    "java/lang/ref/Reference$ReferenceHandler",
+    "java/lang/ref/Finalizer$FinalizerThread",
+    "jdk/internal/misc/InnocuousThread",
+];
+
+// the interpreter runs one thread, so start0 runs the thread's run() to completion before it
+// returns and join() finds it finished. currentThread() stays the caller meanwhile. Service
+// threads are accepted but never scheduled.
+fn thread_start<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let thread = receiver_arg(&args)?;
+        let class = thread.class.clone().ok_or(JVMError::NullReference)?;
+        // RUNNABLE, Thread.start throws IllegalThreadStateException on a second start
+        thread.set_field("threadStatus", Value::Int(5))?;
+        if SERVICE_THREADS.contains(&class.class_name.as_str()) {
+            return Ok(ExecutionResult::Return(None));
+        }
+        let receiver = vec![Value::Reference(Some(Arc::clone(&thread)))];
+        let run = NameDes {
+            name: "run".to_string(),
+            des: "()V".to_string(),
+        };
+        let resolved = LoadedClass::resolve_method(&class, &run)?;
+        let method = LoadedClass::select_method(&class, &resolved)?;
+        let mut thread_stack = Stack::new();
+        match vm.call_method_on(&mut thread_stack, &method, receiver.clone()).await {
+            Ok(_) => {}
+            // the thread dies as it would on its own, the thread that started it (or the other
+            // shutdown hooks) carries on
+            Err(JVMError::UncaughtException(exception)) => {
+                let name = match thread.get_field("name")? {
+                    Value::Reference(Some(name)) => extract_string(&name).unwrap_or_default(),
+                    _ => String::new(),
+                };
+                thread_stack.print_uncaught(&name, &exception);
+            }
+            Err(error) => return Err(error),
+        }
+        // Thread.exit takes the thread out of its group, as the JVM calls it when a thread ends
+        let exit = NameDes {
+            name: "exit".to_string(),
+            des: "()V".to_string(),
+        };
+        if let Ok(exit) = LoadedClass::resolve_method(&class, &exit) {
+            vm.call_method(&exit, receiver).await?;
+        }
+        // TERMINATED
+        thread.set_field("threadStatus", Value::Int(2))?;
+        Ok(ExecutionResult::Return(None))
+    })
+}
+
+fn thread_sleep(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let millis = long_arg(args, 0)?;
+    if millis < 0 {
+        return throws("java/lang/IllegalArgumentException");
+    }
+    std::thread::sleep(std::time::Duration::from_millis(millis as u64));
+    Ok(ExecutionResult::Return(None))
+}
+
+// referents are strong, nothing is ever cleared by the collector
+fn reference_refers_to(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let referent = receiver_arg(args)?.get_field("referent")?;
+    let same = match (referent, reference_arg(args, 1)?) {
+        (Value::Reference(Some(referent)), Some(other)) => Arc::ptr_eq(&referent, &other),
+        (Value::Reference(None), None) => true,
+        _ => false,
+    };
+    returns(Value::Int(same as i32))
+}
+
+fn reference_clear(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    receiver_arg(args)?.set_field("referent", Value::Reference(None))?;
+    Ok(ExecutionResult::Return(None))
+}
@@ -0,0 +1,395 @@
+use super::intrinsics::{
+    class_arg, long_arg, reference_arg, returns, throws, Intrinsic, IntrinsicFuture,
+    IntrinsicRegistry, SyncIntrinsic,
+};
+use crate::class_loader::loaded_class::LoadedClass;
+use crate::execute::execute::ExecutionResult;
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::object::{Object, ObjectKind};
+use crate::runtime::*;
+use crate::vm::VM;
+use parser::access_flag::ClassFlags;
+use std::sync::Arc;
+
+// jdk.internal.misc.Unsafe over the interpreter's object model. A field offset is the field's
+// slot in the object, an array offset is a byte offset from element 0 and arrays of references
+// use 4 byte elements. Off-heap memory is not supported.
+const UNSAFE: &str = "jdk/internal/misc/Unsafe";
+
+pub fn register(registry: &mut IntrinsicRegistry) {
+    let accessors: [(&str, &str, SyncIntrinsic, SyncIntrinsic); 9] = [
+        (
+            "Reference",
+            "Ljava/lang/Object;",
+            |a| get(a, 'L'),
+            |a| put(a, 'L'),
+        ),
+        ("Boolean", "Z", |a| get(a, 'Z'), |a| put(a, 'Z')),
+        ("Byte", "B", |a| get(a, 'B'), |a| put(a, 'B')),
+        ("Short", "S", |a| get(a, 'S'), |a| put(a, 'S')),
+        ("Char", "C", |a| get(a, 'C'), |a| put(a, 'C')),
+        ("Int", "I", |a| get(a, 'I'), |a| put(a, 'I')),
+        ("Long", "J", |a| get(a, 'J'), |a| put(a, 'J')),
+        ("Float", "F", |a| get(a, 'F'), |a| put(a, 'F')),
+        ("Double", "D", |a| get(a, 'D'), |a| put(a, 'D')),
+    ];
+    // a single interpreter thread makes every access volatile
+    for (name, descriptor, getter, setter) in accessors {
+        for suffix in ["", "Volatile"] {
+            registry.register(
+                UNSAFE,
+                &format!("get{}{}", name, suffix),
+                &format!("(Ljava/lang/Object;J){}", descriptor),
+                Intrinsic::Sync(getter),
+            );
+            registry.register(
+                UNSAFE,
+                &format!("put{}{}", name, suffix),
+                &format!("(Ljava/lang/Object;J{})V", descriptor),
+                Intrinsic::Sync(setter),
+            );
+        }
+    }
+
+    let swaps: [(&str, &str, SyncIntrinsic, SyncIntrinsic); 3] = [
+        (
+            "Reference",
+            "Ljava/lang/Object;",
+            |a| compare_and_set(a, 'L'),
+            |a| compare_and_exchange(a, 'L'),
+        ),
+        (
+            "Int",
+            "I",
+            |a| compare_and_set(a, 'I'),
+            |a| compare_and_exchange(a, 'I'),
+        ),
+        (
+            "Long",
+            "J",
+            |a| compare_and_set(a, 'J'),
+            |a| compare_and_exchange(a, 'J'),
+        ),
+    ];
+    for (name, descriptor, set, exchange) in swaps {
+        registry.register(
+            UNSAFE,
+            &format!("compareAndSet{}", name),
+            &format!("(Ljava/lang/Object;J{0}{0})Z", descriptor),
+            Intrinsic::Sync(set),
+        );
+        registry.register(
+            UNSAFE,
+            &format!("compareAndExchange{}", name),
+            &format!("(Ljava/lang/Object;J{0}{0}){0}", descriptor),
+            Intrinsic::Sync(exchange),
+        );
+    }
+
+    for fence in ["loadFence", "storeFence", "fullFence"] {
+        registry.register(UNSAFE, fence, "()V", Intrinsic::Sync(no_op));
+    }
+    registry.register(UNSAFE, "park", "(ZJ)V", Intrinsic::Sync(no_op));
+    registry.register(
+        UNSAFE,
+        "unpark",
+        "(Ljava/lang/Object;)V",
+        Intrinsic::Sync(no_op),
+    );
+
+    registry.register(
+        UNSAFE,
+        "arrayBaseOffset0",
+        "(Ljava/lang/Class;)I",
+        Intrinsic::Sync(array_base_offset),
+    );
+    registry.register(
+        UNSAFE,
+        "arrayIndexScale0",
+        "(Ljava/lang/Class;)I",
+        Intrinsic::Async(array_index_scale),
+    );
+    registry.register(
+        UNSAFE,
+        "objectFieldOffset1",
+        "(Ljava/lang/Class;Ljava/lang/String;)J",
+        Intrinsic::Async(object_field_offset),
+    );
+    registry.register(
+        UNSAFE,
+        "objectFieldOffset0",
+        "(Ljava/lang/reflect/Field;)J",
+        Intrinsic::Async(reflected_field_offset),
+    );
+    registry.register(
+        UNSAFE,
+        "shouldBeInitialized0",
+        "(Ljava/lang/Class;)Z",
+        Intrinsic::Async(should_be_initialized),
+    );
+    registry.register(
+        UNSAFE,
+        "ensureClassInitialized0",
+        "(Ljava/lang/Class;)V",
+        Intrinsic::Async(ensure_class_initialized),
+    );
+    registry.register(
+        UNSAFE,
+        "allocateInstance",
+        "(Ljava/lang/Class;)Ljava/lang/Object;",
+        Intrinsic::Async(allocate_instance),
+    );
+    registry.register(
+        UNSAFE,
+        "throwException",
+        "(Ljava/lang/Throwable;)V",
+        Intrinsic::Sync(throw_exception),
+    );
+}
+
+fn no_op(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    Ok(ExecutionResult::Return(None))
+}
+
+// the object and offset every accessor takes after the Unsafe receiver
+fn target(args: &[Value]) -> Result<(Arc<Object>, usize), JVMError> {
+    let obj = reference_arg(args, 1)?.ok_or_else(|| {
+        JVMError::Other("Unsafe access to off-heap memory is not supported".to_string())
+    })?;
+    let offset = long_arg(args, 2)?;
+    if offset < 0 {
+        return Err(JVMError::InvalidOffset(offset as i32));
+    }
+    Ok((obj, offset as usize))
+}
+
+fn size_of(kind: char) -> usize {
+    match kind {
+        'Z' | 'B' => 1,
+        'S' | 'C' => 2,
+        'I' | 'F' => 4,
+        _ => 8,
+    }
+}
+
+fn to_bytes(kind: char, value: &Value) -> Result<Vec<u8>, JVMError> {
+    Ok(match (kind, value) {
+        ('Z' | 'B', Value::Int(i)) => vec![*i as u8],
+        ('S' | 'C', Value::Int(i)) => (*i as u16).to_ne_bytes().to_vec(),
+        ('I', Value::Int(i)) => i.to_ne_bytes().to_vec(),
+        ('J', Value::Long(l)) => l.to_ne_bytes().to_vec(),
+        ('F', Value::Float(f)) => f.to_ne_bytes().to_vec(),
+        ('D', Value::Double(d)) => d.to_ne_bytes().to_vec(),
+        _ => {
+            return Err(JVMError::TypeMismatch {
+                expected: kind.to_string(),
+                found: format!("{:?}", value),
+            })
+        }
+    })
+}
+
+fn from_bytes(kind: char, bytes: &[u8]) -> Value {
+    match kind {
+        'Z' => Value::Int((bytes[0] != 0) as i32),
+        'B' => Value::Int(bytes[0] as i8 as i32),
+        'S' => Value::Int(i16::from_ne_bytes([bytes[0], bytes[1]]) as i32),
+        'C' => Value::Int(u16::from_ne_bytes([bytes[0], bytes[1]]) as i32),
+        'I' => Value::Int(i32::from_ne_bytes(bytes.try_into().unwrap())),
+        'F' => Value::Float(f32::from_ne_bytes(bytes.try_into().unwrap())),
+        'J' => Value::Long(i64::from_ne_bytes(bytes.try_into().unwrap())),
+        _ => Value::Double(f64::from_ne_bytes(bytes.try_into().unwrap())),
+    }
+}
+
+fn read(obj: &Object, offset: usize, kind: char) -> Result<Value, JVMError> {
+    match &obj.kind {
+        ObjectKind::ClassInstance { .. } => obj.get_field_at(offset),
+        ObjectKind::ArrayInstance { elements, .. } => {
+            let elements = elements.borrow();
+            let out_of_bounds = JVMError::IndexOutOfBounds {
+                index: offset,
+                max: elements.len() * elements.element_size(),
+            };
+            if kind == 'L' {
+                return elements.get(offset / 4).ok_or(out_of_bounds);
+            }
+            let bytes = elements
+                .read_bytes(offset, size_of(kind))
+                .ok_or(out_of_bounds)?;
+            Ok(from_bytes(kind, &bytes))
+        }
+    }
+}
+
+fn write(obj: &Object, offset: usize, kind: char, value: Value) -> Result<(), JVMError> {
+    match &obj.kind {
+        ObjectKind::ClassInstance { .. } => obj.set_field_at(offset, value),
+        ObjectKind::ArrayInstance { elements, .. } => {
+            let mut elements = elements.borrow_mut();
+            if kind == 'L' {
+                return elements.set(offset / 4, value);
+            }
+            elements.write_bytes(offset, &to_bytes(kind, &value)?)
+        }
+    }
+}
+
+fn get(args: &[Value], kind: char) -> Result<ExecutionResult, JVMError> {
+    let (obj, offset) = target(args)?;
+    returns(read(&obj, offset, kind)?)
+}
+
+fn put(args: &[Value], kind: char) -> Result<ExecutionResult, JVMError> {
+    let (obj, offset) = target(args)?;
+    let value = args.get(3).cloned().ok_or(JVMError::InsufficientOperands {
+        required: 4,
+        found: args.len(),
+    })?;
+    write(&obj, offset, kind, value)?;
+    Ok(ExecutionResult::Return(None))
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Reference(Some(a)), Value::Reference(Some(b))) => Arc::ptr_eq(a, b),
+        (Value::Reference(None), Value::Reference(None)) => true,
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Long(a), Value::Long(b)) => a == b,
+        _ => false,
+    }
+}
+
+// swaps in args[4] when the current value is args[3], returns the value seen before
+fn swap(args: &[Value], kind: char) -> Result<(Value, bool), JVMError> {
+    let (obj, offset) = target(args)?;
+    let (expected, new_value) = match (args.get(3), args.get(4)) {
+        (Some(expected), Some(new_value)) => (expected, new_value),
+        _ => {
+            return Err(JVMError::InsufficientOperands {
+                required: 5,
+                found: args.len(),
+            })
+        }
+    };
+    let current = read(&obj, offset, kind)?;
+    let swapped = same_value(&current, expected);
+    if swapped {
+        write(&obj, offset, kind, new_value.clone())?;
+    }
+    Ok((current, swapped))
+}
+
+fn compare_and_set(args: &[Value], kind: char) -> Result<ExecutionResult, JVMError> {
+    let (_, swapped) = swap(args, kind)?;
+    returns(Value::Int(swapped as i32))
+}
+
+fn compare_and_exchange(args: &[Value], kind: char) -> Result<ExecutionResult, JVMError> {
+    let (witness, _) = swap(args, kind)?;
+    returns(witness)
+}
+
+fn array_base_offset(_args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    returns(Value::Int(0))
+}
+
+fn array_index_scale<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 1).await?;
+        let scale = match class.class_name.as_str() {
+            "[Z" | "[B" => 1,
+            "[S" | "[C" => 2,
+            "[J" | "[D" => 8,
+            name if name.starts_with('[') => 4,
+            _ => return throws("java/lang/IllegalArgumentException"),
+        };
+        returns(Value::Int(scale))
+    })
+}
+
+fn field_slot(class: &Arc<LoadedClass>, name: &str) -> Option<usize> {
+    class
+        .declared_instance_fields
+        .iter()
+        .find(|(name_des, _)| name_des.name == name)
+        .map(|(_, &slot)| slot)
+}
+
+fn object_field_offset<'a>(vm: &'a VM, _stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 1).await?;
+        let name = reference_arg(&args, 2)?.ok_or(JVMError::NullReference)?;
+        let name = extract_string(&name).map_err(JVMError::Other)?;
+        match field_slot(&class, &name) {
+            Some(slot) => returns(Value::Long(slot as i64)),
+            None => throws("java/lang/InternalError"),
+        }
+    })
+}
+
+fn reflected_field_offset<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let field = reference_arg(&args, 1)?.ok_or(JVMError::NullReference)?;
+        let class = class_arg(vm, &[field.get_field("clazz")?], 0).await?;
+        let name = match field.get_field("name")? {
+            Value::Reference(Some(name)) => extract_string(&name).map_err(JVMError::Other)?,
+            _ => return Err(JVMError::NullReference),
+        };
+        match field_slot(&class, &name) {
+            Some(slot) => returns(Value::Long(slot as i64)),
+            None => throws("java/lang/InternalError"),
+        }
+    })
+}
+
+fn should_be_initialized<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 1).await?;
+        returns(Value::Int(!class.is_initialized() as i32))
+    })
+}
+
+fn ensure_class_initialized<'a>(
+    vm: &'a VM,
+    _stack: &'a Stack,
+    args: Vec<Value>,
+) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 1).await?;
+        Box::pin(LoadedClass::initialize(class, vm)).await?;
+        Ok(ExecutionResult::Return(None))
+    })
+}
+
+fn allocate_instance<'a>(vm: &'a VM, stack: &'a Stack, args: Vec<Value>) -> IntrinsicFuture<'a> {
+    Box::pin(async move {
+        let class = class_arg(vm, &args, 1).await?;
+        if class.is_interface()
+            || class.is_array()
+            || class.access_flags.contains(ClassFlags::ACC_ABSTRACT)
+        {
+            return throws("java/lang/InstantiationException");
+        }
+        vm.allocate_object(stack, &class.class_name)
+            .await
+            .map(|obj| ExecutionResult::Return(Some(obj)))
+    })
+}
+
+fn throw_exception(args: &[Value]) -> Result<ExecutionResult, JVMError> {
+    let exception = reference_arg(args, 1)?.ok_or(JVMError::NullReference)?;
+    match &exception.class {
+        Some(class) => throws(&class.class_name),
+        None => Err(JVMError::Other("Exception object has no class".to_string())),
+    }
+}
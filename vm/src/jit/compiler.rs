@@ -0,0 +1,1083 @@
+use crate::class_loader::loaded_class::{LoadedClass, NameDes};
+use crate::parse_des::parse_descriptor;
+use crate::vm::VM;
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{
+    types, AbiParam, Block, FuncRef, Function, InstBuilder, MemFlags, Signature, StackSlotData,
+    StackSlotKind, Type, UserFuncName, Value as ClifValue,
+};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, FuncId, Linkage, Module};
+use parser::access_flag::MethodFlags;
+use parser::attribute::Code;
+use parser::constant_pool::{
+    ConstantInfo, ConstantIntegerInfo, ConstantInterfaceMethodRefInfo, ConstantLongInfo,
+    ConstantMethodRefInfo, ConstantPool,
+};
+use parser::instruction::{Instruction, Operation};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+// compiled frames deeper than this report a StackOverflowError instead of exhausting the native stack
+pub const MAX_DEPTH: i64 = 1024;
+// upper bound on the number of methods pulled in through static calls by a single compilation
+const MAX_UNIT_SIZE: usize = 64;
+
+pub const STATUS_OK: u32 = 0;
+pub const STATUS_ARITHMETIC: u32 = 1;
+pub const STATUS_STACK_OVERFLOW: u32 = 2;
+
+// Shared between the interpreter and compiled code, compiled code reports exceptions through `status`.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct JitContext {
+    pub status: u32,
+    pub depth: u32,
+}
+
+// Every compiled method takes its arguments as an array of i64 slots (ints are sign extended)
+// and returns its result the same way, so the interpreter needs a single entry point type.
+pub type CompiledFn = unsafe extern "C" fn(*mut JitContext, *const i64) -> i64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JitType {
+    Int,
+    Long,
+}
+
+impl JitType {
+    fn from_descriptor(descriptor: &str) -> Option<Self> {
+        match descriptor {
+            "I" | "Z" | "B" | "C" | "S" => Some(JitType::Int),
+            "J" => Some(JitType::Long),
+            _ => None,
+        }
+    }
+
+    fn clif(self) -> Type {
+        match self {
+            JitType::Int => types::I32,
+            JitType::Long => types::I64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JitSignature {
+    pub params: Vec<JitType>,
+    pub ret: Option<JitType>,
+}
+
+impl JitSignature {
+    pub fn parse(descriptor: &str) -> Option<Self> {
+        let parsed = parse_descriptor(descriptor).ok()?;
+        let params = parsed
+            .arg_types
+            .iter()
+            .map(|arg| JitType::from_descriptor(arg))
+            .collect::<Option<Vec<_>>>()?;
+        let ret = match parsed.return_type.as_str() {
+            "V" => None,
+            other => Some(JitType::from_descriptor(other)?),
+        };
+        Some(JitSignature { params, ret })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodKey {
+    pub class_name: String,
+    pub name_des: NameDes,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledMethod {
+    pub entry: CompiledFn,
+    pub signature: JitSignature,
+}
+
+struct Candidate {
+    key: MethodKey,
+    class: Arc<LoadedClass>,
+    code: Arc<Code>,
+    signature: JitSignature,
+    // invokestatic instruction index -> callee
+    calls: HashMap<usize, MethodKey>,
+}
+
+pub struct Compiler {
+    module: JITModule,
+    builder_ctx: FunctionBuilderContext,
+    functions: HashMap<MethodKey, (FuncId, JitSignature)>,
+}
+
+impl Compiler {
+    pub fn new() -> Result<Self, String> {
+        let mut flag_builder = settings::builder();
+        flag_builder
+            .set("use_colocated_libcalls", "false")
+            .map_err(|e| e.to_string())?;
+        flag_builder
+            .set("is_pic", "false")
+            .map_err(|e| e.to_string())?;
+        flag_builder
+            .set("opt_level", "speed")
+            .map_err(|e| e.to_string())?;
+        let isa = cranelift_native::builder()
+            .map_err(|e| e.to_string())?
+            .finish(settings::Flags::new(flag_builder))
+            .map_err(|e| e.to_string())?;
+        let module = JITModule::new(JITBuilder::with_isa(isa, default_libcall_names()));
+        Ok(Compiler {
+            module,
+            builder_ctx: FunctionBuilderContext::new(),
+            functions: HashMap::new(),
+        })
+    }
+
+    // Compiles the method together with every static callee that is not compiled yet.
+    // Either the whole unit compiles or nothing is emitted.
+    pub fn compile(
+        &mut self,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+        vm: &VM,
+    ) -> Result<Vec<(MethodKey, CompiledMethod)>, String> {
+        let unit = self.collect_unit(class, name_des, vm)?;
+
+        let mut ids = HashMap::new();
+        for candidate in &unit {
+            let id = self
+                .module
+                .declare_function(
+                    &format!(
+                        "{}.{}{}",
+                        candidate.key.class_name,
+                        candidate.key.name_des.name,
+                        candidate.key.name_des.des
+                    ),
+                    Linkage::Local,
+                    &self.entry_signature(),
+                )
+                .map_err(|e| e.to_string())?;
+            ids.insert(candidate.key.clone(), (id, candidate.signature.clone()));
+        }
+
+        let mut functions = Vec::with_capacity(unit.len());
+        for candidate in &unit {
+            let (id, _) = ids[&candidate.key];
+            let func = self.translate(candidate, id, &ids)?;
+            functions.push((id, func));
+        }
+
+        let mut ctx = self.module.make_context();
+        for (id, func) in functions {
+            ctx.func = func;
+            self.module
+                .define_function(id, &mut ctx)
+                .map_err(|e| format!("{:?}", e))?;
+            self.module.clear_context(&mut ctx);
+        }
+        self.module
+            .finalize_definitions()
+            .map_err(|e| e.to_string())?;
+
+        let mut compiled = Vec::with_capacity(unit.len());
+        for candidate in unit {
+            let (id, signature) = ids.remove(&candidate.key).unwrap();
+            let code = self.module.get_finalized_function(id);
+            let entry = unsafe { std::mem::transmute::<*const u8, CompiledFn>(code) };
+            self.functions
+                .insert(candidate.key.clone(), (id, signature.clone()));
+            compiled.push((candidate.key, CompiledMethod { entry, signature }));
+        }
+        Ok(compiled)
+    }
+
+    fn entry_signature(&self) -> Signature {
+        let ptr = self.module.target_config().pointer_type();
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(ptr));
+        sig.params.push(AbiParam::new(ptr));
+        sig.returns.push(AbiParam::new(types::I64));
+        sig
+    }
+
+    fn collect_unit(
+        &self,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+        vm: &VM,
+    ) -> Result<Vec<Candidate>, String> {
+        let mut unit = Vec::new();
+        let mut seen = HashSet::new();
+        let mut worklist = vec![(Arc::clone(class), name_des.clone())];
+        while let Some((class, name_des)) = worklist.pop() {
+            let key = MethodKey {
+                class_name: class.class_name.clone(),
+                name_des,
+            };
+            if self.functions.contains_key(&key) || !seen.insert(key.clone()) {
+                continue;
+            }
+            if unit.len() >= MAX_UNIT_SIZE {
+                return Err("compilation unit too large".to_string());
+            }
+            let mut candidate = Self::candidate(&class, key)?;
+            for (index, Instruction(_, operation)) in candidate.code.code.iter().enumerate() {
+                if let Operation::Invokestatic(index1, index2) = operation {
+                    let (callee_class, callee_name_des) = resolve_static_call(
+                        &class.constant_pool,
+                        ((*index1 as u16) << 8) | *index2 as u16,
+                        vm,
+                    )?;
+                    candidate.calls.insert(
+                        index,
+                        MethodKey {
+                            class_name: callee_class.class_name.clone(),
+                            name_des: callee_name_des.clone(),
+                        },
+                    );
+                    worklist.push((callee_class, callee_name_des));
+                } else {
+                    check_operation(operation, &class.constant_pool)?;
+                }
+            }
+            unit.push(candidate);
+        }
+        Ok(unit)
+    }
+
+    fn candidate(class: &Arc<LoadedClass>, key: MethodKey) -> Result<Candidate, String> {
+        let method_info = class
+            .get_method_info_from_name_and_descriptor(&key.name_des)
+            .ok_or_else(|| format!("method {} not found", key.name_des.name))?;
+        if !method_info.access_flags.contains(MethodFlags::ACC_STATIC) {
+            return Err("not a static method".to_string());
+        }
+        if method_info
+            .access_flags
+            .intersects(MethodFlags::ACC_NATIVE | MethodFlags::ACC_SYNCHRONIZED)
+        {
+            return Err("native or synchronized method".to_string());
+        }
+        let code = class
+            .get_code_from_method(&key.name_des)
+            .ok_or_else(|| "method has no code".to_string())?;
+        if !code.exception_table.is_empty() {
+            return Err("method has exception handlers".to_string());
+        }
+        let signature = JitSignature::parse(&key.name_des.des)
+            .ok_or_else(|| format!("unsupported descriptor {}", key.name_des.des))?;
+        Ok(Candidate {
+            key,
+            class: Arc::clone(class),
+            code,
+            signature,
+            calls: HashMap::new(),
+        })
+    }
+
+    fn translate(
+        &mut self,
+        candidate: &Candidate,
+        id: FuncId,
+        unit: &HashMap<MethodKey, (FuncId, JitSignature)>,
+    ) -> Result<Function, String> {
+        let mut func = Function::with_name_signature(
+            UserFuncName::user(0, id.as_u32()),
+            self.entry_signature(),
+        );
+        let mut calls = HashMap::new();
+        for (index, key) in &candidate.calls {
+            let (callee_id, signature) = unit
+                .get(key)
+                .or_else(|| self.functions.get(key))
+                .ok_or_else(|| {
+                    format!(
+                        "callee {}.{} not compiled",
+                        key.class_name, key.name_des.name
+                    )
+                })?;
+            let func_ref = self.module.declare_func_in_func(*callee_id, &mut func);
+            calls.insert(*index, (func_ref, signature.clone()));
+        }
+
+        let ptr_ty = self.module.target_config().pointer_type();
+        {
+            let builder = FunctionBuilder::new(&mut func, &mut self.builder_ctx);
+            let mut translator = Translator::new(builder, candidate, &calls, ptr_ty);
+            let result = translator.translate();
+            translator.builder.seal_all_blocks();
+            translator.builder.finalize();
+            result?;
+        }
+        Ok(func)
+    }
+}
+
+fn resolve_static_call(
+    cp: &ConstantPool,
+    index: u16,
+    vm: &VM,
+) -> Result<(Arc<LoadedClass>, NameDes), String> {
+    let (class_index, name_and_type_index, is_interface) = match cp.get_entry(index) {
+        Some(ConstantInfo::Methodref(ConstantMethodRefInfo {
+            class_index,
+            name_and_type_index,
+        })) => (*class_index, *name_and_type_index, false),
+        Some(ConstantInfo::InterfaceMethodRef(ConstantInterfaceMethodRefInfo {
+            class_index,
+            name_and_type_index,
+        })) => (*class_index, *name_and_type_index, true),
+        _ => return Err(format!("invalid Methodref at {}", index)),
+    };
+    let class_name = cp
+        .get_underlying_string_from_constant_class_info_index(class_index)
+        .ok_or_else(|| format!("invalid class at {}", class_index))?;
+    let name_des = match cp.get_entry(name_and_type_index) {
+        Some(ConstantInfo::NameAndType(name_and_type)) => NameDes::new(name_and_type, cp),
+        _ => return Err(format!("invalid NameAndType at {}", name_and_type_index)),
+    };
+    // only classes the interpreter already loaded and initialized are called directly
+    let class = vm
+        .class_loader
+        .find_loaded_class(class_name)
+        .ok_or_else(|| format!("class {} not loaded yet", class_name))?;
+    let resolved = if is_interface {
+        LoadedClass::resolve_interface_method(&class, &name_des)
+    } else {
+        LoadedClass::resolve_method(&class, &name_des)
+    }
+    .map_err(|e| format!("{:?}", e))?;
+    if !resolved.class.is_initialized() {
+        return Err(format!("class {} not initialized yet", resolved.class.class_name));
+    }
+    Ok((resolved.class, name_des))
+}
+
+fn check_operation(operation: &Operation, cp: &ConstantPool) -> Result<(), String> {
+    match operation {
+        Operation::Ldc(index) => check_constant(cp, *index as u16, false),
+        Operation::Ldcw(index1, index2) => {
+            check_constant(cp, ((*index1 as u16) << 8) | *index2 as u16, false)
+        }
+        Operation::Ldc2w(index1, index2) => {
+            check_constant(cp, ((*index1 as u16) << 8) | *index2 as u16, true)
+        }
+        Operation::Nop
+        | Operation::Iconstm1
+        | Operation::Iconst0
+        | Operation::Iconst1
+        | Operation::Iconst2
+        | Operation::Iconst3
+        | Operation::Iconst4
+        | Operation::Iconst5
+        | Operation::Lconst0
+        | Operation::Lconst1
+        | Operation::Bipush(_)
+        | Operation::Sipush(_, _)
+        | Operation::Iload(_)
+        | Operation::Iload0
+        | Operation::Iload1
+        | Operation::Iload2
+        | Operation::Iload3
+        | Operation::Lload(_)
+        | Operation::Lload0
+        | Operation::Lload1
+        | Operation::Lload2
+        | Operation::Lload3
+        | Operation::Istore(_)
+        | Operation::Istore0
+        | Operation::Istore1
+        | Operation::Istore2
+        | Operation::Istore3
+        | Operation::Lstore(_)
+        | Operation::Lstore0
+        | Operation::Lstore1
+        | Operation::Lstore2
+        | Operation::Lstore3
+        | Operation::Iinc(_, _)
+        | Operation::Iadd
+        | Operation::Isub
+        | Operation::Imul
+        | Operation::Idiv
+        | Operation::Irem
+        | Operation::Ineg
+        | Operation::Iand
+        | Operation::Ior
+        | Operation::Ixor
+        | Operation::Ishl
+        | Operation::Ishr
+        | Operation::Iushr
+        | Operation::Ladd
+        | Operation::Lsub
+        | Operation::Lmul
+        | Operation::Ldiv
+        | Operation::Lrem
+        | Operation::Lneg
+        | Operation::Land
+        | Operation::Lor
+        | Operation::Lxor
+        | Operation::Lshl
+        | Operation::Lshr
+        | Operation::Lushr
+        | Operation::Lcmp
+        | Operation::I2l
+        | Operation::L2i
+        | Operation::I2b
+        | Operation::I2c
+        | Operation::I2s
+        | Operation::Dup
+        | Operation::Pop
+        | Operation::Pop2
+        | Operation::Ifeq(_, _)
+        | Operation::Ifne(_, _)
+        | Operation::Iflt(_, _)
+        | Operation::Ifge(_, _)
+        | Operation::Ifgt(_, _)
+        | Operation::Ifle(_, _)
+        | Operation::Ificmpeq(_, _)
+        | Operation::Ificmpne(_, _)
+        | Operation::Ificmplt(_, _)
+        | Operation::Ificmpge(_, _)
+        | Operation::Ificmpgt(_, _)
+        | Operation::Ificmple(_, _)
+        | Operation::Goto(_, _)
+        | Operation::Gotow(_, _, _, _)
+        | Operation::Ireturn
+        | Operation::Lreturn
+        | Operation::Return => Ok(()),
+        other => Err(format!("unsupported instruction {:?}", other)),
+    }
+}
+
+fn check_constant(cp: &ConstantPool, index: u16, wide: bool) -> Result<(), String> {
+    match (cp.get_entry(index), wide) {
+        (Some(ConstantInfo::Integer(_)), false) | (Some(ConstantInfo::Long(_)), true) => Ok(()),
+        _ => Err(format!("unsupported constant at {}", index)),
+    }
+}
+
+fn branch_offset(operation: &Operation) -> Option<i32> {
+    match operation {
+        Operation::Ifeq(b1, b2)
+        | Operation::Ifne(b1, b2)
+        | Operation::Iflt(b1, b2)
+        | Operation::Ifge(b1, b2)
+        | Operation::Ifgt(b1, b2)
+        | Operation::Ifle(b1, b2)
+        | Operation::Ificmpeq(b1, b2)
+        | Operation::Ificmpne(b1, b2)
+        | Operation::Ificmplt(b1, b2)
+        | Operation::Ificmpge(b1, b2)
+        | Operation::Ificmpgt(b1, b2)
+        | Operation::Ificmple(b1, b2)
+        | Operation::Goto(b1, b2) => Some((((*b1 as u16) << 8) | *b2 as u16) as i16 as i32),
+        Operation::Gotow(b1, b2, b3, b4) => Some(i32::from_be_bytes([*b1, *b2, *b3, *b4])),
+        _ => None,
+    }
+}
+
+fn ends_block(operation: &Operation) -> bool {
+    branch_offset(operation).is_some()
+        || matches!(
+            operation,
+            Operation::Ireturn | Operation::Lreturn | Operation::Return
+        )
+}
+
+struct Translator<'a> {
+    builder: FunctionBuilder<'a>,
+    code: &'a Code,
+    cp: &'a ConstantPool,
+    signature: &'a JitSignature,
+    calls: &'a HashMap<usize, (FuncRef, JitSignature)>,
+    ptr_ty: Type,
+    ctx_ptr: Option<ClifValue>,
+    exit_block: Option<Block>,
+    max_locals: usize,
+    stack: Vec<JitType>,
+    blocks: HashMap<usize, Block>,
+    block_stacks: HashMap<usize, Vec<JitType>>,
+}
+
+impl<'a> Translator<'a> {
+    fn new(
+        builder: FunctionBuilder<'a>,
+        candidate: &'a Candidate,
+        calls: &'a HashMap<usize, (FuncRef, JitSignature)>,
+        ptr_ty: Type,
+    ) -> Self {
+        Translator {
+            builder,
+            code: &candidate.code,
+            cp: &candidate.class.constant_pool,
+            signature: &candidate.signature,
+            calls,
+            ptr_ty,
+            ctx_ptr: None,
+            exit_block: None,
+            max_locals: candidate.code.max_locals as usize,
+            stack: Vec::new(),
+            blocks: HashMap::new(),
+            block_stacks: HashMap::new(),
+        }
+    }
+
+    // locals and operand stack slots become cranelift variables, one per slot and type,
+    // so merges at branch targets are left to the SSA construction of the frontend
+    fn local_var(&self, index: usize, ty: JitType) -> Variable {
+        Variable::from_u32((index * 2 + (ty == JitType::Long) as usize) as u32)
+    }
+
+    fn stack_var(&self, depth: usize, ty: JitType) -> Variable {
+        self.local_var(self.max_locals + depth, ty)
+    }
+
+    fn ctx(&self) -> ClifValue {
+        self.ctx_ptr.unwrap()
+    }
+
+    fn translate(&mut self) -> Result<(), String> {
+        let entry = self.builder.create_block();
+        self.builder.append_block_params_for_function_params(entry);
+        self.builder.switch_to_block(entry);
+        let ctx_ptr = self.builder.block_params(entry)[0];
+        let args_ptr = self.builder.block_params(entry)[1];
+        self.ctx_ptr = Some(ctx_ptr);
+
+        let slots = self.max_locals + self.code.max_stack as usize;
+        for slot in 0..slots {
+            for ty in [JitType::Int, JitType::Long] {
+                let var = self.local_var(slot, ty);
+                self.builder.declare_var(var, ty.clif());
+                let zero = self.builder.ins().iconst(ty.clif(), 0);
+                self.builder.def_var(var, zero);
+            }
+        }
+
+        let mut local = 0;
+        for (i, ty) in self.signature.params.iter().enumerate() {
+            let arg =
+                self.builder
+                    .ins()
+                    .load(types::I64, MemFlags::trusted(), args_ptr, (i * 8) as i32);
+            let value = match ty {
+                JitType::Int => self.builder.ins().ireduce(types::I32, arg),
+                JitType::Long => arg,
+            };
+            if local >= self.max_locals {
+                return Err("arguments exceed max_locals".to_string());
+            }
+            let var = self.local_var(local, *ty);
+            self.builder.def_var(var, value);
+            local += if *ty == JitType::Long { 2 } else { 1 };
+        }
+
+        let exit_block = self.builder.create_block();
+        self.builder.append_block_param(exit_block, types::I64);
+        self.exit_block = Some(exit_block);
+
+        // depth check on entry, the exit block undoes the increment
+        let depth = self
+            .builder
+            .ins()
+            .load(types::I32, MemFlags::trusted(), ctx_ptr, 4);
+        let depth = self.builder.ins().iadd_imm(depth, 1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), depth, ctx_ptr, 4);
+        let too_deep = self
+            .builder
+            .ins()
+            .icmp_imm(IntCC::UnsignedGreaterThan, depth, MAX_DEPTH);
+
+        let code = self.code;
+        for (index, Instruction(_, operation)) in code.code.iter().enumerate() {
+            if index == 0 {
+                self.blocks.insert(0, self.builder.create_block());
+            }
+            if let Some(offset) = branch_offset(operation) {
+                let target = self.target_index(index, offset)?;
+                self.blocks
+                    .entry(target)
+                    .or_insert_with(|| self.builder.create_block());
+            }
+            if ends_block(operation) && index + 1 < self.code.code.len() {
+                self.blocks
+                    .entry(index + 1)
+                    .or_insert_with(|| self.builder.create_block());
+            }
+        }
+
+        let overflow_block = self.builder.create_block();
+        let body = self.blocks[&0];
+        self.builder
+            .ins()
+            .brif(too_deep, overflow_block, &[], body, &[]);
+        self.block_stacks.insert(0, Vec::new());
+        self.builder.switch_to_block(overflow_block);
+        self.throw(STATUS_STACK_OVERFLOW);
+
+        let mut terminated = true;
+        for (index, Instruction(_, operation)) in code.code.iter().enumerate() {
+            if let Some(&block) = self.blocks.get(&index) {
+                if !terminated {
+                    self.record_stack(index)?;
+                    self.builder.ins().jump(block, &[]);
+                }
+                self.builder.switch_to_block(block);
+                self.stack = self.block_stacks.get(&index).cloned().unwrap_or_default();
+            } else if terminated {
+                continue;
+            }
+            terminated = self.translate_operation(index, operation)?;
+        }
+        if !terminated {
+            return Err("control falls off the end of the method".to_string());
+        }
+
+        self.builder.switch_to_block(exit_block);
+        let result = self.builder.block_params(exit_block)[0];
+        let depth = self
+            .builder
+            .ins()
+            .load(types::I32, MemFlags::trusted(), ctx_ptr, 4);
+        let depth = self.builder.ins().iadd_imm(depth, -1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), depth, ctx_ptr, 4);
+        self.builder.ins().return_(&[result]);
+        Ok(())
+    }
+
+    fn target_index(&self, index: usize, offset: i32) -> Result<usize, String> {
+        let address = self.code.get_address_at_index(index) as i32 + offset;
+        self.code
+            .address_to_index
+            .get(&(address as u32))
+            .copied()
+            .ok_or_else(|| format!("invalid branch target {}", address))
+    }
+
+    fn record_stack(&mut self, target: usize) -> Result<(), String> {
+        match self.block_stacks.get(&target) {
+            Some(existing) if *existing != self.stack => {
+                Err(format!("inconsistent stack at instruction {}", target))
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.block_stacks.insert(target, self.stack.clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn push(&mut self, ty: JitType, value: ClifValue) {
+        let var = self.stack_var(self.stack.len(), ty);
+        self.builder.def_var(var, value);
+        self.stack.push(ty);
+    }
+
+    fn pop(&mut self, expected: JitType) -> Result<ClifValue, String> {
+        match self.stack.pop() {
+            Some(ty) if ty == expected => {
+                let var = self.stack_var(self.stack.len(), ty);
+                Ok(self.builder.use_var(var))
+            }
+            Some(ty) => Err(format!("expected {:?} on stack, found {:?}", expected, ty)),
+            None => Err("operand stack underflow".to_string()),
+        }
+    }
+
+    fn load_local(&mut self, index: usize, ty: JitType) -> Result<(), String> {
+        if index >= self.max_locals {
+            return Err(format!("local {} out of range", index));
+        }
+        let value = self.builder.use_var(self.local_var(index, ty));
+        self.push(ty, value);
+        Ok(())
+    }
+
+    fn store_local(&mut self, index: usize, ty: JitType) -> Result<(), String> {
+        if index >= self.max_locals {
+            return Err(format!("local {} out of range", index));
+        }
+        let value = self.pop(ty)?;
+        let var = self.local_var(index, ty);
+        self.builder.def_var(var, value);
+        Ok(())
+    }
+
+    fn iconst(&mut self, ty: JitType, value: i64) {
+        let value = self.builder.ins().iconst(ty.clif(), value);
+        self.push(ty, value);
+    }
+
+    fn binary(
+        &mut self,
+        ty: JitType,
+        op: fn(&mut FunctionBuilder<'a>, ClifValue, ClifValue) -> ClifValue,
+    ) -> Result<(), String> {
+        let v2 = self.pop(ty)?;
+        let v1 = self.pop(ty)?;
+        let result = op(&mut self.builder, v1, v2);
+        self.push(ty, result);
+        Ok(())
+    }
+
+    fn shift(
+        &mut self,
+        ty: JitType,
+        op: fn(&mut FunctionBuilder<'a>, ClifValue, ClifValue) -> ClifValue,
+    ) -> Result<(), String> {
+        // cranelift masks the shift amount by the type width exactly like the JVM does
+        let amount = self.pop(JitType::Int)?;
+        let value = self.pop(ty)?;
+        let result = op(&mut self.builder, value, amount);
+        self.push(ty, result);
+        Ok(())
+    }
+
+    // idiv/irem/ldiv/lrem: a zero divisor throws ArithmeticException, MIN / -1 wraps around
+    fn division(&mut self, ty: JitType, remainder: bool) -> Result<(), String> {
+        let v2 = self.pop(ty)?;
+        let v1 = self.pop(ty)?;
+        let zero = self.builder.ins().icmp_imm(IntCC::Equal, v2, 0);
+        let throw_block = self.builder.create_block();
+        let continue_block = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(zero, throw_block, &[], continue_block, &[]);
+        self.builder.switch_to_block(throw_block);
+        self.throw(STATUS_ARITHMETIC);
+        self.builder.switch_to_block(continue_block);
+
+        let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, v2, -1);
+        let one = self.builder.ins().iconst(ty.clif(), 1);
+        let divisor = self.builder.ins().select(minus_one, one, v2);
+        let result = if remainder {
+            let rem = self.builder.ins().srem(v1, divisor);
+            let zero = self.builder.ins().iconst(ty.clif(), 0);
+            self.builder.ins().select(minus_one, zero, rem)
+        } else {
+            let quotient = self.builder.ins().sdiv(v1, divisor);
+            let negated = self.builder.ins().ineg(v1);
+            self.builder.ins().select(minus_one, negated, quotient)
+        };
+        self.push(ty, result);
+        Ok(())
+    }
+
+    fn throw(&mut self, status: u32) {
+        let ctx = self.ctx();
+        let status = self.builder.ins().iconst(types::I32, status as i64);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), status, ctx, 0);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().jump(self.exit_block.unwrap(), &[zero]);
+    }
+
+    fn compare_branch(
+        &mut self,
+        index: usize,
+        operation: &Operation,
+        cc: IntCC,
+        with_zero: bool,
+    ) -> Result<bool, String> {
+        let v2 = if with_zero {
+            self.builder.ins().iconst(types::I32, 0)
+        } else {
+            self.pop(JitType::Int)?
+        };
+        let v1 = self.pop(JitType::Int)?;
+        let condition = self.builder.ins().icmp(cc, v1, v2);
+        let target = self.target_index(index, branch_offset(operation).unwrap())?;
+        self.record_stack(target)?;
+        self.record_stack(index + 1)?;
+        let taken = self.blocks[&target];
+        let fallthrough = *self
+            .blocks
+            .get(&(index + 1))
+            .ok_or_else(|| "branch at the end of the method".to_string())?;
+        self.builder
+            .ins()
+            .brif(condition, taken, &[], fallthrough, &[]);
+        Ok(true)
+    }
+
+    fn leave(&mut self, value: Option<JitType>) -> Result<bool, String> {
+        if value != self.signature.ret {
+            return Err("return type does not match the descriptor".to_string());
+        }
+        let result = match value {
+            Some(JitType::Int) => {
+                let value = self.pop(JitType::Int)?;
+                self.builder.ins().sextend(types::I64, value)
+            }
+            Some(JitType::Long) => self.pop(JitType::Long)?,
+            None => self.builder.ins().iconst(types::I64, 0),
+        };
+        self.builder.ins().jump(self.exit_block.unwrap(), &[result]);
+        Ok(true)
+    }
+
+    fn invoke(&mut self, index: usize) -> Result<(), String> {
+        let (func_ref, signature) = self
+            .calls
+            .get(&index)
+            .cloned()
+            .ok_or_else(|| "unresolved static call".to_string())?;
+        let args_ptr = if signature.params.is_empty() {
+            self.builder.ins().iconst(self.ptr_ty, 0)
+        } else {
+            let slot = self.builder.create_sized_stack_slot(StackSlotData::new(
+                StackSlotKind::ExplicitSlot,
+                (signature.params.len() * 8) as u32,
+                3,
+            ));
+            for (i, ty) in signature.params.iter().enumerate().rev() {
+                let value = self.pop(*ty)?;
+                let value = match ty {
+                    JitType::Int => self.builder.ins().sextend(types::I64, value),
+                    JitType::Long => value,
+                };
+                self.builder.ins().stack_store(value, slot, (i * 8) as i32);
+            }
+            self.builder.ins().stack_addr(self.ptr_ty, slot, 0)
+        };
+        let ctx = self.ctx();
+        let call = self.builder.ins().call(func_ref, &[ctx, args_ptr]);
+        let result = self.builder.inst_results(call)[0];
+
+        // an exception in the callee unwinds through this frame as well
+        let status = self
+            .builder
+            .ins()
+            .load(types::I32, MemFlags::trusted(), ctx, 0);
+        let propagate_block = self.builder.create_block();
+        let continue_block = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(status, propagate_block, &[], continue_block, &[]);
+        self.builder.switch_to_block(propagate_block);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().jump(self.exit_block.unwrap(), &[zero]);
+        self.builder.switch_to_block(continue_block);
+
+        match signature.ret {
+            Some(JitType::Int) => {
+                let value = self.builder.ins().ireduce(types::I32, result);
+                self.push(JitType::Int, value);
+            }
+            Some(JitType::Long) => self.push(JitType::Long, result),
+            None => {}
+        }
+        Ok(())
+    }
+
+    // returns true when the instruction terminates the current block
+    fn translate_operation(&mut self, index: usize, operation: &Operation) -> Result<bool, String> {
+        use JitType::{Int, Long};
+        match operation {
+            Operation::Nop => {}
+            Operation::Iconstm1 => self.iconst(Int, -1),
+            Operation::Iconst0 => self.iconst(Int, 0),
+            Operation::Iconst1 => self.iconst(Int, 1),
+            Operation::Iconst2 => self.iconst(Int, 2),
+            Operation::Iconst3 => self.iconst(Int, 3),
+            Operation::Iconst4 => self.iconst(Int, 4),
+            Operation::Iconst5 => self.iconst(Int, 5),
+            Operation::Lconst0 => self.iconst(Long, 0),
+            Operation::Lconst1 => self.iconst(Long, 1),
+            Operation::Bipush(byte) => self.iconst(Int, *byte as i8 as i64),
+            Operation::Sipush(b1, b2) => {
+                self.iconst(Int, (((*b1 as u16) << 8) | *b2 as u16) as i16 as i64)
+            }
+            Operation::Ldc(index) => self.constant(*index as u16)?,
+            Operation::Ldcw(b1, b2) | Operation::Ldc2w(b1, b2) => {
+                self.constant(((*b1 as u16) << 8) | *b2 as u16)?
+            }
+            Operation::Iload(n) => self.load_local(*n as usize, Int)?,
+            Operation::Iload0 => self.load_local(0, Int)?,
+            Operation::Iload1 => self.load_local(1, Int)?,
+            Operation::Iload2 => self.load_local(2, Int)?,
+            Operation::Iload3 => self.load_local(3, Int)?,
+            Operation::Lload(n) => self.load_local(*n as usize, Long)?,
+            Operation::Lload0 => self.load_local(0, Long)?,
+            Operation::Lload1 => self.load_local(1, Long)?,
+            Operation::Lload2 => self.load_local(2, Long)?,
+            Operation::Lload3 => self.load_local(3, Long)?,
+            Operation::Istore(n) => self.store_local(*n as usize, Int)?,
+            Operation::Istore0 => self.store_local(0, Int)?,
+            Operation::Istore1 => self.store_local(1, Int)?,
+            Operation::Istore2 => self.store_local(2, Int)?,
+            Operation::Istore3 => self.store_local(3, Int)?,
+            Operation::Lstore(n) => self.store_local(*n as usize, Long)?,
+            Operation::Lstore0 => self.store_local(0, Long)?,
+            Operation::Lstore1 => self.store_local(1, Long)?,
+            Operation::Lstore2 => self.store_local(2, Long)?,
+            Operation::Lstore3 => self.store_local(3, Long)?,
+            Operation::Iinc(n, constant) => {
+                let var = self.local_var(*n as usize, Int);
+                let value = self.builder.use_var(var);
+                let value = self.builder.ins().iadd_imm(value, *constant as i8 as i64);
+                self.builder.def_var(var, value);
+            }
+            Operation::Iadd => self.binary(Int, |b, x, y| b.ins().iadd(x, y))?,
+            Operation::Isub => self.binary(Int, |b, x, y| b.ins().isub(x, y))?,
+            Operation::Imul => self.binary(Int, |b, x, y| b.ins().imul(x, y))?,
+            Operation::Iand => self.binary(Int, |b, x, y| b.ins().band(x, y))?,
+            Operation::Ior => self.binary(Int, |b, x, y| b.ins().bor(x, y))?,
+            Operation::Ixor => self.binary(Int, |b, x, y| b.ins().bxor(x, y))?,
+            Operation::Idiv => self.division(Int, false)?,
+            Operation::Irem => self.division(Int, true)?,
+            Operation::Ladd => self.binary(Long, |b, x, y| b.ins().iadd(x, y))?,
+            Operation::Lsub => self.binary(Long, |b, x, y| b.ins().isub(x, y))?,
+            Operation::Lmul => self.binary(Long, |b, x, y| b.ins().imul(x, y))?,
+            Operation::Land => self.binary(Long, |b, x, y| b.ins().band(x, y))?,
+            Operation::Lor => self.binary(Long, |b, x, y| b.ins().bor(x, y))?,
+            Operation::Lxor => self.binary(Long, |b, x, y| b.ins().bxor(x, y))?,
+            Operation::Ldiv => self.division(Long, false)?,
+            Operation::Lrem => self.division(Long, true)?,
+            Operation::Ishl => self.shift(Int, |b, x, y| b.ins().ishl(x, y))?,
+            Operation::Ishr => self.shift(Int, |b, x, y| b.ins().sshr(x, y))?,
+            Operation::Iushr => self.shift(Int, |b, x, y| b.ins().ushr(x, y))?,
+            Operation::Lshl => self.shift(Long, |b, x, y| b.ins().ishl(x, y))?,
+            Operation::Lshr => self.shift(Long, |b, x, y| b.ins().sshr(x, y))?,
+            Operation::Lushr => self.shift(Long, |b, x, y| b.ins().ushr(x, y))?,
+            Operation::Ineg | Operation::Lneg => {
+                let ty = if matches!(operation, Operation::Ineg) {
+                    Int
+                } else {
+                    Long
+                };
+                let value = self.pop(ty)?;
+                let value = self.builder.ins().ineg(value);
+                self.push(ty, value);
+            }
+            Operation::Lcmp => {
+                let v2 = self.pop(Long)?;
+                let v1 = self.pop(Long)?;
+                let greater = self.builder.ins().icmp(IntCC::SignedGreaterThan, v1, v2);
+                let less = self.builder.ins().icmp(IntCC::SignedLessThan, v1, v2);
+                let greater = self.builder.ins().uextend(types::I32, greater);
+                let less = self.builder.ins().uextend(types::I32, less);
+                let result = self.builder.ins().isub(greater, less);
+                self.push(Int, result);
+            }
+            Operation::I2l => {
+                let value = self.pop(Int)?;
+                let value = self.builder.ins().sextend(types::I64, value);
+                self.push(Long, value);
+            }
+            Operation::L2i => {
+                let value = self.pop(Long)?;
+                let value = self.builder.ins().ireduce(types::I32, value);
+                self.push(Int, value);
+            }
+            Operation::I2b | Operation::I2s => {
+                let narrow = if matches!(operation, Operation::I2b) {
+                    types::I8
+                } else {
+                    types::I16
+                };
+                let value = self.pop(Int)?;
+                let value = self.builder.ins().ireduce(narrow, value);
+                let value = self.builder.ins().sextend(types::I32, value);
+                self.push(Int, value);
+            }
+            Operation::I2c => {
+                let value = self.pop(Int)?;
+                let value = self.builder.ins().band_imm(value, 0xFFFF);
+                self.push(Int, value);
+            }
+            Operation::Dup => {
+                let value = self.pop(Int)?;
+                self.push(Int, value);
+                self.push(Int, value);
+            }
+            Operation::Pop => {
+                self.pop(Int)?;
+            }
+            Operation::Pop2 => match self.stack.last() {
+                Some(Long) => {
+                    self.pop(Long)?;
+                }
+                _ => {
+                    self.pop(Int)?;
+                    self.pop(Int)?;
+                }
+            },
+            Operation::Ifeq(_, _) => {
+                return self.compare_branch(index, operation, IntCC::Equal, true)
+            }
+            Operation::Ifne(_, _) => {
+                return self.compare_branch(index, operation, IntCC::NotEqual, true)
+            }
+            Operation::Iflt(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedLessThan, true)
+            }
+            Operation::Ifge(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedGreaterThanOrEqual, true)
+            }
+            Operation::Ifgt(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedGreaterThan, true)
+            }
+            Operation::Ifle(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedLessThanOrEqual, true)
+            }
+            Operation::Ificmpeq(_, _) => {
+                return self.compare_branch(index, operation, IntCC::Equal, false)
+            }
+            Operation::Ificmpne(_, _) => {
+                return self.compare_branch(index, operation, IntCC::NotEqual, false)
+            }
+            Operation::Ificmplt(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedLessThan, false)
+            }
+            Operation::Ificmpge(_, _) => {
+                return self.compare_branch(
+                    index,
+                    operation,
+                    IntCC::SignedGreaterThanOrEqual,
+                    false,
+                )
+            }
+            Operation::Ificmpgt(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedGreaterThan, false)
+            }
+            Operation::Ificmple(_, _) => {
+                return self.compare_branch(index, operation, IntCC::SignedLessThanOrEqual, false)
+            }
+            Operation::Goto(_, _) | Operation::Gotow(_, _, _, _) => {
+                let target = self.target_index(index, branch_offset(operation).unwrap())?;
+                self.record_stack(target)?;
+                let block = self.blocks[&target];
+                self.builder.ins().jump(block, &[]);
+                return Ok(true);
+            }
+            Operation::Ireturn => return self.leave(Some(Int)),
+            Operation::Lreturn => return self.leave(Some(Long)),
+            Operation::Return => return self.leave(None),
+            Operation::Invokestatic(_, _) => self.invoke(index)?,
+            other => return Err(format!("unsupported instruction {:?}", other)),
+        }
+        Ok(false)
+    }
+
+    fn constant(&mut self, index: u16) -> Result<(), String> {
+        match self.cp.get_entry(index) {
+            Some(ConstantInfo::Integer(ConstantIntegerInfo(value))) => {
+                self.iconst(JitType::Int, *value as i64)
+            }
+            Some(ConstantInfo::Long(ConstantLongInfo(value))) => self.iconst(JitType::Long, *value),
+            _ => return Err(format!("unsupported constant at {}", index)),
+        }
+        Ok(())
+    }
+}
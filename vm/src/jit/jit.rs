@@ -0,0 +1,131 @@
+use super::compiler::{CompiledMethod, Compiler};
+use crate::class_loader::loaded_class::{LoadedClass, NameDes};
+use crate::state::{Header, MessageData, GLOBAL_BOOL, JIT_TRACE, SERVER_STATE};
+use crate::vm::VM;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// a static method is compiled on the invocation after either counter crosses its threshold
+pub const INVOCATION_THRESHOLD: u32 = 100;
+pub const BACKEDGE_THRESHOLD: u32 = 1000;
+
+#[derive(Debug, Default)]
+enum Tier {
+    #[default]
+    Interpreted,
+    Compiled(CompiledMethod),
+    // compilation bailed out, the method stays in the interpreter for good
+    Rejected,
+}
+
+#[derive(Debug, Default)]
+struct MethodProfile {
+    invocations: u32,
+    backedges: u32,
+    tier: Tier,
+}
+
+// Baseline tier: counts invocations and loop backedges per method and hands hot static
+// methods to the Cranelift compiler. There is no on-stack replacement, a method that got
+// hot in a loop switches to compiled code on its next invocation.
+#[derive(Default)]
+pub struct Jit {
+    compiler: Option<Compiler>,
+    profiles: HashMap<String, HashMap<NameDes, MethodProfile>>,
+}
+
+impl Jit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn profile(&mut self, class_name: &str, name_des: &NameDes) -> &mut MethodProfile {
+        if !self.profiles.contains_key(class_name) {
+            self.profiles.insert(class_name.to_string(), HashMap::new());
+        }
+        let methods = self.profiles.get_mut(class_name).unwrap();
+        if !methods.contains_key(name_des) {
+            methods.insert(name_des.clone(), MethodProfile::default());
+        }
+        methods.get_mut(name_des).unwrap()
+    }
+
+    pub fn record_backedge(&mut self, class_name: &str, name_des: &NameDes) {
+        let profile = self.profile(class_name, name_des);
+        profile.backedges = profile.backedges.saturating_add(1);
+    }
+
+    // Counts the invocation and returns the compiled code of the method if there is (or now is) any.
+    pub fn on_invoke(
+        &mut self,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+        vm: &VM,
+    ) -> Option<CompiledMethod> {
+        let profile = self.profile(&class.class_name, name_des);
+        match &profile.tier {
+            Tier::Compiled(method) => return Some(method.clone()),
+            Tier::Rejected => return None,
+            Tier::Interpreted => {}
+        }
+        profile.invocations = profile.invocations.saturating_add(1);
+        if profile.invocations < INVOCATION_THRESHOLD && profile.backedges < BACKEDGE_THRESHOLD {
+            return None;
+        }
+        let (invocations, backedges) = (profile.invocations, profile.backedges);
+
+        let result = match self.compiler.as_mut() {
+            Some(compiler) => compiler.compile(class, name_des, vm),
+            None => Compiler::new()
+                .and_then(|compiler| self.compiler.insert(compiler).compile(class, name_des, vm)),
+        };
+        match result {
+            Ok(methods) => {
+                for (key, method) in methods {
+                    trace(
+                        "compile",
+                        &key.class_name,
+                        &key.name_des,
+                        json!({"invocations": invocations, "backedges": backedges}),
+                    );
+                    self.profile(&key.class_name, &key.name_des).tier = Tier::Compiled(method);
+                }
+                match &self.profile(&class.class_name, name_des).tier {
+                    Tier::Compiled(method) => Some(method.clone()),
+                    _ => None,
+                }
+            }
+            Err(reason) => {
+                trace(
+                    "bailout",
+                    &class.class_name,
+                    name_des,
+                    json!({"reason": reason}),
+                );
+                self.profile(&class.class_name, name_des).tier = Tier::Rejected;
+                None
+            }
+        }
+    }
+}
+
+pub fn trace(action: &str, class_name: &str, name_des: &NameDes, details: JsonValue) {
+    if *JIT_TRACE.lock().unwrap() {
+        eprintln!(
+            "[jit] {:<8} {}.{}{} {}",
+            action, class_name, name_des.name, name_des.des, details
+        );
+    }
+    let flag = GLOBAL_BOOL.lock().unwrap();
+    if *flag {
+        let json_jit = MessageData {
+            header: Header::DATA,
+            json: json!({"header": "jit", "action": action, "class": class_name, "name": name_des.name, "descriptor": name_des.des, "details": details}).to_string(),
+        };
+        {
+            let mut queue = SERVER_STATE.lock().unwrap();
+            queue.push_back(json_jit);
+        }
+    }
+}
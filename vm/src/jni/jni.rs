@@ -0,0 +1,488 @@
+use super::jni_functions::{function_table, invoke_table};
+use crate::class_loader::loaded_class::{FieldSlot, LoadedClass, NameDes, ResolvedMethod};
+use crate::execute::execute::ExecutionResult;
+use crate::intrinsics::intrinsics::mirror_of;
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::object::Object;
+use crate::parse_des::parse_descriptor;
+use crate::runtime::{Stack, Value};
+use crate::vm::VM;
+use once_cell::sync::Lazy;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CString};
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+// jobject and the types derived from it (jclass, jstring, jarray...): the address of an
+// Object, kept alive by the local or global reference native code received it as
+pub type JObject = *const Object;
+
+pub const JNI_OK: i32 = 0;
+pub const JNI_ERR: i32 = -1;
+pub const JNI_EDETACHED: i32 = -2;
+pub const JNI_EVERSION: i32 = -3;
+// JNI_VERSION_21, the interface version GetVersion reports
+pub const JNI_VERSION: i32 = 0x0015_0000;
+// every version jni.h has defined, JNI_OnLoad and GetEnv accept any of them
+const JNI_VERSIONS: [i32; 10] = [
+    0x0001_0001,
+    0x0001_0002,
+    0x0001_0004,
+    0x0001_0006,
+    0x0001_0008,
+    0x0009_0000,
+    0x000a_0000,
+    0x0013_0000,
+    0x0014_0000,
+    JNI_VERSION,
+];
+// entries of the JNIInvokeInterface_ table
+pub const INVOKE_FUNCTION_COUNT: usize = 8;
+// entries of the JNINativeInterface_ table up to IsVirtualThread
+pub const JNI_FUNCTION_COUNT: usize = 235;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union JValue {
+    pub z: u8,
+    pub b: i8,
+    pub c: u16,
+    pub s: i16,
+    pub i: i32,
+    pub j: i64,
+    pub f: f32,
+    pub d: f64,
+    pub l: JObject,
+}
+
+// the function table of every JNIEnv, laid out like JNINativeInterface_ in jni.h
+#[repr(transparent)]
+pub struct JniFunctions(pub [*const c_void; JNI_FUNCTION_COUNT]);
+
+// the table only holds function pointers and is never written after it is built
+unsafe impl Send for JniFunctions {}
+unsafe impl Sync for JniFunctions {}
+
+static FUNCTIONS: Lazy<JniFunctions> = Lazy::new(function_table);
+
+// the function table of the JavaVM, laid out like JNIInvokeInterface_ in jni.h
+#[repr(transparent)]
+pub struct InvokeFunctions(pub [*const c_void; INVOKE_FUNCTION_COUNT]);
+
+unsafe impl Send for InvokeFunctions {}
+unsafe impl Sync for InvokeFunctions {}
+
+static INVOKE_FUNCTIONS: Lazy<InvokeFunctions> = Lazy::new(invoke_table);
+
+// What a JavaVM* points to. Native code keeps it around to get a JNIEnv later, which only
+// exists while a native method runs on the thread asking, so one JavaVM serves every VM.
+#[repr(C)]
+pub struct JavaVm {
+    functions: &'static InvokeFunctions,
+}
+
+static JAVA_VM: Lazy<JavaVm> = Lazy::new(|| JavaVm {
+    functions: &INVOKE_FUNCTIONS,
+});
+
+thread_local! {
+    // the JNIEnv* of the innermost native call running on this thread, null outside of one
+    static CURRENT_ENV: Cell<*const c_void> = const { Cell::new(std::ptr::null()) };
+}
+
+// makes an earlier JNIEnv current again once a native call returns
+pub struct CurrentEnv(*const c_void);
+
+impl Drop for CurrentEnv {
+    fn drop(&mut self) {
+        CURRENT_ENV.with(|current| current.set(self.0));
+    }
+}
+
+// the JavaVM* handed to JNI_OnLoad and GetJavaVM
+pub fn java_vm() -> *const c_void {
+    &*JAVA_VM as *const JavaVm as *const c_void
+}
+
+// the JNIEnv* GetEnv answers with
+pub fn current_env() -> *const c_void {
+    CURRENT_ENV.with(Cell::get)
+}
+
+pub fn is_supported_version(version: i32) -> bool {
+    JNI_VERSIONS.contains(&version)
+}
+
+// What a JNIEnv* points to during one native call. C code only reads the first field, the
+// function table, the rest is the state the JNI functions work on.
+#[repr(C)]
+pub struct JniEnv<'a> {
+    functions: &'static JniFunctions,
+    pub(crate) vm: &'a VM,
+    pub(crate) stack: &'a Stack,
+    // local references, released when the native method returns
+    locals: RefCell<Vec<Arc<Object>>>,
+    // number of local references at each PushLocalFrame
+    frames: RefCell<Vec<usize>>,
+    // the Throwable native code threw or a Java method it called left uncaught
+    exception: RefCell<Option<Arc<Object>>>,
+    // a VM failure inside a JNI function, reported once the native method returns
+    error: RefCell<Option<JVMError>>,
+}
+
+// what a jmethodID points to
+#[repr(C)]
+pub struct JniMethod {
+    // read by jni_varargs.c, one type character per argument
+    arg_shape: *const c_char,
+    shape: CString,
+    pub(crate) method: ResolvedMethod,
+    pub(crate) arg_types: Vec<String>,
+}
+
+// what a jfieldID points to
+pub struct JniField {
+    pub(crate) slot: FieldSlot,
+}
+
+// JNI state that outlives a native call. Method and field IDs are interned so they stay
+// valid for the lifetime of the VM, as native code caches them.
+#[derive(Default)]
+pub struct JniState {
+    globals: RefCell<Vec<Arc<Object>>>,
+    // the Throwable the last native method returned with, until the interpreter takes it
+    thrown: RefCell<Option<Arc<Object>>>,
+    methods: RefCell<HashMap<(String, NameDes), Box<JniMethod>>>,
+    fields: RefCell<HashMap<(String, NameDes), Box<JniField>>>,
+}
+
+impl JniMethod {
+    fn new(method: ResolvedMethod) -> Result<Self, JVMError> {
+        let arg_types = parse_descriptor(&method.name_des.des)
+            .map_err(JVMError::Other)?
+            .arg_types;
+        let shape = arg_types
+            .iter()
+            .map(|arg_type| arg_type.chars().next().unwrap_or('L'))
+            .collect::<String>();
+        let shape = CString::new(shape).map_err(|e| JVMError::Other(e.to_string()))?;
+        Ok(JniMethod {
+            // the CString's buffer does not move when the CString itself does
+            arg_shape: shape.as_ptr(),
+            shape,
+            method,
+            arg_types,
+        })
+    }
+}
+
+impl JniState {
+    pub(crate) fn method_id(
+        &self,
+        class: &LoadedClass,
+        method: ResolvedMethod,
+    ) -> Result<*const JniMethod, JVMError> {
+        let key = (class.class_name.clone(), method.name_des.clone());
+        let mut methods = self.methods.borrow_mut();
+        if let Some(id) = methods.get(&key) {
+            return Ok(id.as_ref());
+        }
+        let id = Box::new(JniMethod::new(method)?);
+        let pointer = id.as_ref() as *const JniMethod;
+        methods.insert(key, id);
+        Ok(pointer)
+    }
+
+    pub(crate) fn field_id(
+        &self,
+        class: &LoadedClass,
+        name_des: NameDes,
+        slot: FieldSlot,
+    ) -> *const JniField {
+        let key = (class.class_name.clone(), name_des);
+        self.fields
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| Box::new(JniField { slot }))
+            .as_ref()
+    }
+
+    pub(crate) fn new_global(&self, object: Arc<Object>) -> JObject {
+        let handle = Arc::as_ptr(&object);
+        self.globals.borrow_mut().push(object);
+        handle
+    }
+
+    pub(crate) fn delete_global(&self, handle: JObject) {
+        let mut globals = self.globals.borrow_mut();
+        if let Some(index) = globals.iter().position(|obj| Arc::as_ptr(obj) == handle) {
+            globals.swap_remove(index);
+        }
+    }
+
+    pub(crate) fn set_thrown(&self, throwable: Arc<Object>) {
+        *self.thrown.borrow_mut() = Some(throwable);
+    }
+
+    // the object a native method threw as class_name, exceptions thrown by name have none
+    pub(crate) fn take_thrown(&self, class_name: &str) -> Option<Arc<Object>> {
+        self.thrown.borrow_mut().take().filter(|throwable| {
+            throwable.class.as_ref().map(|class| class.class_name.as_str()) == Some(class_name)
+        })
+    }
+
+    pub(crate) fn is_global(&self, handle: JObject) -> bool {
+        self.globals
+            .borrow()
+            .iter()
+            .any(|obj| Arc::as_ptr(obj) == handle)
+    }
+}
+
+impl<'a> JniEnv<'a> {
+    pub fn new(vm: &'a VM, stack: &'a Stack) -> Self {
+        JniEnv {
+            functions: &FUNCTIONS,
+            vm,
+            stack,
+            locals: RefCell::new(Vec::new()),
+            frames: RefCell::new(Vec::new()),
+            exception: RefCell::new(None),
+            error: RefCell::new(None),
+        }
+    }
+
+    // the JNIEnv* native code receives
+    pub fn as_ptr(&self) -> *const c_void {
+        self as *const JniEnv as *const c_void
+    }
+
+    // the env GetEnv hands out until the returned guard is dropped
+    pub fn make_current(&self) -> CurrentEnv {
+        CurrentEnv(CURRENT_ENV.with(|current| current.replace(self.as_ptr())))
+    }
+
+    pub fn new_local(&self, object: Arc<Object>) -> JObject {
+        let handle = Arc::as_ptr(&object);
+        self.locals.borrow_mut().push(object);
+        handle
+    }
+
+    // a local reference to a reference value, null for null and non-references
+    pub fn local_ref(&self, value: &Value) -> JObject {
+        match value {
+            Value::Reference(Some(object)) => self.new_local(Arc::clone(object)),
+            _ => std::ptr::null(),
+        }
+    }
+
+    pub fn delete_local(&self, handle: JObject) {
+        let mut locals = self.locals.borrow_mut();
+        if let Some(index) = locals.iter().rposition(|obj| Arc::as_ptr(obj) == handle) {
+            locals.remove(index);
+        }
+    }
+
+    pub fn is_local(&self, handle: JObject) -> bool {
+        self.locals
+            .borrow()
+            .iter()
+            .any(|obj| Arc::as_ptr(obj) == handle)
+    }
+
+    pub fn push_frame(&self) {
+        let count = self.locals.borrow().len();
+        self.frames.borrow_mut().push(count);
+    }
+
+    // frees the local references of the innermost frame, result moves to the enclosing one
+    pub fn pop_frame(&self, result: JObject) -> JObject {
+        let result = self.object(result);
+        if let Some(count) = self.frames.borrow_mut().pop() {
+            self.locals.borrow_mut().truncate(count);
+        }
+        result.map_or(std::ptr::null(), |obj| self.new_local(obj))
+    }
+
+    // the object behind a local or global reference, None for null
+    pub(crate) fn object(&self, handle: JObject) -> Option<Arc<Object>> {
+        if handle.is_null() {
+            return None;
+        }
+        // handles come from Arc::as_ptr and a reference keeps the allocation alive
+        unsafe {
+            Arc::increment_strong_count(handle);
+            Some(Arc::from_raw(handle))
+        }
+    }
+
+    pub fn value(&self, handle: JObject) -> Value {
+        Value::Reference(self.object(handle))
+    }
+
+    // the class behind a jclass
+    pub fn class(&self, handle: JObject) -> Option<Arc<LoadedClass>> {
+        let Some(mirror) = self.object(handle) else {
+            self.throw("java/lang/NullPointerException");
+            return None;
+        };
+        let class = block_on(self.vm.heap.read()).class_of_mirror(&mirror);
+        if class.is_none() {
+            self.fail(JVMError::Other(
+                "Class object without a loaded class".to_string(),
+            ));
+        }
+        class
+    }
+
+    // a local reference to the java/lang/Class object of a class
+    pub fn mirror(&self, class: Arc<LoadedClass>) -> JObject {
+        self.check(block_on(mirror_of(self.vm, self.stack, class)))
+            .map_or(std::ptr::null(), |mirror| self.local_ref(&mirror))
+    }
+
+    // a local reference to a new java/lang/String
+    pub fn new_string(&self, string: &str) -> JObject {
+        let allocated = block_on(async {
+            let mut heap = self.vm.heap.write().await;
+            heap.allocate_string(self.stack, self.vm, string).await
+        });
+        self.check(allocated)
+            .map_or(std::ptr::null(), |string| self.local_ref(&string))
+    }
+
+    // the text of the java/lang/String behind a jstring
+    pub fn string(&self, handle: JObject) -> Option<String> {
+        let Some(string) = self.object(handle) else {
+            self.throw("java/lang/NullPointerException");
+            return None;
+        };
+        self.check(extract_string(&string).map_err(JVMError::Other))
+    }
+
+    // the Java value of a jvalue holding an argument of the given type
+    pub fn argument(&self, arg_type: &str, value: JValue) -> Value {
+        unsafe {
+            match arg_type {
+                "Z" => Value::Int(value.z as i32),
+                "B" => Value::Int(value.b as i32),
+                "C" => Value::Int(value.c as i32),
+                "S" => Value::Int(value.s as i32),
+                "I" => Value::Int(value.i),
+                "J" => Value::Long(value.j),
+                "F" => Value::Float(value.f),
+                "D" => Value::Double(value.d),
+                _ => self.value(value.l),
+            }
+        }
+    }
+
+    // makes a new exception of class_name pending, built by its no-argument constructor
+    pub fn throw(&self, class_name: &str) {
+        self.throw_new(class_name, None);
+    }
+
+    // makes a new exception of class_name pending, built by its (Ljava/lang/String;)V
+    // constructor when there is a message
+    pub fn throw_new(&self, class_name: &str, message: Option<&str>) {
+        match block_on(self.vm.new_throwable(self.stack, class_name, message)) {
+            Ok(throwable) => self.throw_object(throwable),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    pub fn throw_object(&self, throwable: Arc<Object>) {
+        *self.exception.borrow_mut() = Some(throwable);
+    }
+
+    pub fn exception(&self) -> Option<Arc<Object>> {
+        self.exception.borrow().clone()
+    }
+
+    pub fn clear_exception(&self) {
+        *self.exception.borrow_mut() = None;
+    }
+
+    pub fn fail(&self, error: JVMError) {
+        *self.error.borrow_mut() = Some(error);
+    }
+
+    // runs a Java method for native code, the exception it leaves uncaught becomes the
+    // pending one
+    pub fn call_method(&self, method: &ResolvedMethod, args: Vec<Value>) -> Option<Option<Value>> {
+        let mut stack = Stack::new();
+        match block_on(self.vm.call_method_on(&mut stack, method, args)) {
+            Err(JVMError::UncaughtException(class_name)) => {
+                match stack.take_exception(&class_name) {
+                    Some(throwable) => self.throw_object(throwable),
+                    None => self.throw(&class_name),
+                }
+                None
+            }
+            result => self.check(result),
+        }
+    }
+
+    // the result of a VM operation, an uncaught exception becomes the pending one
+    pub fn check<T>(&self, result: Result<T, JVMError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(JVMError::UncaughtException(exception)) => {
+                self.throw(&exception);
+                None
+            }
+            Err(error) => {
+                self.fail(error);
+                None
+            }
+        }
+    }
+
+    // what the native method amounts to once it returned result
+    pub fn finish(self, result: Option<Value>) -> Result<ExecutionResult, JVMError> {
+        if let Some(error) = self.error.into_inner() {
+            return Err(error);
+        }
+        Ok(match self.exception.into_inner() {
+            // the interpreter picks the object up again where the exception lands
+            Some(throwable) => {
+                let class_name = throwable
+                    .class
+                    .as_ref()
+                    .map(|class| class.class_name.clone())
+                    .ok_or_else(|| JVMError::Other("Throwable has no class".to_string()))?;
+                self.vm.native_stack.jni.set_thrown(throwable);
+                ExecutionResult::Throw(class_name)
+            }
+            None => ExecutionResult::Return(result),
+        })
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Drives a VM future to completion on the current thread. JNI functions are called from C
+// and cannot await, the interpreter futures they run only wait for the heap lock. The
+// future opts out of tokio's cooperative budget, which only refills when the task yields to
+// the scheduler and would otherwise leave the heap lock pending forever.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(tokio::task::unconstrained(future));
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
@@ -0,0 +1,1120 @@
+use super::jni::{
+    block_on, current_env, is_supported_version, java_vm, InvokeFunctions, JObject, JValue, JniEnv,
+    JniField, JniFunctions, JniMethod, JNI_EDETACHED, JNI_ERR, JNI_EVERSION, JNI_FUNCTION_COUNT,
+    JNI_OK, JNI_VERSION,
+};
+use crate::class_loader::loaded_class::{FieldSlot, LoadedClass, NameDes, ResolvedMethod};
+use crate::jvm_error::JVMError;
+use crate::native::extract_string;
+use crate::object::{ArrayStorage, Object, ObjectKind};
+use crate::runtime::Value;
+use parser::access_flag::{ClassFlags, MethodFlags};
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::Arc;
+
+// JNIInvalidRefType, JNILocalRefType, JNIGlobalRefType
+const INVALID_REF: i32 = 0;
+const LOCAL_REF: i32 = 1;
+const GLOBAL_REF: i32 = 2;
+
+// modes of Release<type>ArrayElements
+const JNI_COMMIT: i32 = 1;
+const JNI_ABORT: i32 = 2;
+
+// an entry of the RegisterNatives array
+#[repr(C)]
+struct JniNativeMethod {
+    name: *const c_char,
+    signature: *const c_char,
+    function: *const c_void,
+}
+
+extern "C" {
+    // fills in the variadic and va_list forms of Call*Method and NewObject
+    fn jni_install_varargs(table: *mut *const c_void);
+}
+
+// A C type Java values of one type travel to and from native code as. Void has none, so
+// a method returning nothing yields (), and a failed call yields zero or null.
+trait JniType: Copy {
+    fn from_value(env: &JniEnv, value: Option<Value>) -> Self;
+    fn into_value(self, env: &JniEnv) -> Value;
+}
+
+impl JniType for () {
+    fn from_value(_env: &JniEnv, _value: Option<Value>) -> Self {}
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Default
+    }
+}
+
+impl JniType for JObject {
+    fn from_value(env: &JniEnv, value: Option<Value>) -> Self {
+        value.map_or(std::ptr::null(), |value| env.local_ref(&value))
+    }
+
+    fn into_value(self, env: &JniEnv) -> Value {
+        env.value(self)
+    }
+}
+
+impl JniType for u8 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Int(i)) => (i != 0) as u8,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Int((self != 0) as i32)
+    }
+}
+
+impl JniType for i8 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Int(i)) => i as i8,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Int(self as i32)
+    }
+}
+
+impl JniType for u16 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Int(i)) => i as u16,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Int(self as i32)
+    }
+}
+
+impl JniType for i16 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Int(i)) => i as i16,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Int(self as i32)
+    }
+}
+
+impl JniType for i32 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Int(i)) => i,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Int(self)
+    }
+}
+
+impl JniType for i64 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Long(l)) => l,
+            _ => 0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Long(self)
+    }
+}
+
+impl JniType for f32 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Float(f)) => f,
+            _ => 0.0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Float(self)
+    }
+}
+
+impl JniType for f64 {
+    fn from_value(_env: &JniEnv, value: Option<Value>) -> Self {
+        match value {
+            Some(Value::Double(d)) => d,
+            _ => 0.0,
+        }
+    }
+
+    fn into_value(self, _env: &JniEnv) -> Value {
+        Value::Double(self)
+    }
+}
+
+// The JNINativeInterface_ table. Slots this VM does not implement abort with a message
+// rather than jump to garbage.
+pub fn function_table() -> JniFunctions {
+    let mut table = [unsupported as *const c_void; JNI_FUNCTION_COUNT];
+    // reserved0 to reserved3
+    table[..4].fill(std::ptr::null());
+    let functions: [(usize, *const c_void); 61] = [
+        (4, get_version as *const c_void),
+        (6, find_class as *const c_void),
+        (10, get_superclass as *const c_void),
+        (11, is_assignable_from as *const c_void),
+        (13, throw as *const c_void),
+        (14, throw_new as *const c_void),
+        (15, exception_occurred as *const c_void),
+        (16, exception_describe as *const c_void),
+        (17, exception_clear as *const c_void),
+        (18, fatal_error as *const c_void),
+        (19, push_local_frame as *const c_void),
+        (20, pop_local_frame as *const c_void),
+        (21, new_global_ref as *const c_void),
+        (22, delete_global_ref as *const c_void),
+        (23, delete_local_ref as *const c_void),
+        (24, is_same_object as *const c_void),
+        (25, new_local_ref as *const c_void),
+        (26, ensure_local_capacity as *const c_void),
+        (27, alloc_object as *const c_void),
+        (30, new_object_a as *const c_void),
+        (31, get_object_class as *const c_void),
+        (32, is_instance_of as *const c_void),
+        (33, get_method_id as *const c_void),
+        (94, get_field_id as *const c_void),
+        (113, get_static_method_id as *const c_void),
+        (144, get_static_field_id as *const c_void),
+        (163, new_string as *const c_void),
+        (164, get_string_length as *const c_void),
+        (165, get_string_chars as *const c_void),
+        (166, release_string_chars as *const c_void),
+        (167, new_string_utf as *const c_void),
+        (168, get_string_utf_length as *const c_void),
+        (169, get_string_utf_chars as *const c_void),
+        (170, release_string_utf_chars as *const c_void),
+        (171, get_array_length as *const c_void),
+        (172, new_object_array as *const c_void),
+        (173, get_object_array_element as *const c_void),
+        (174, set_object_array_element as *const c_void),
+        (175, new_array::<'Z'> as *const c_void),
+        (176, new_array::<'B'> as *const c_void),
+        (177, new_array::<'C'> as *const c_void),
+        (178, new_array::<'S'> as *const c_void),
+        (179, new_array::<'I'> as *const c_void),
+        (180, new_array::<'J'> as *const c_void),
+        (181, new_array::<'F'> as *const c_void),
+        (182, new_array::<'D'> as *const c_void),
+        (215, register_natives as *const c_void),
+        (216, unregister_natives as *const c_void),
+        (217, monitor_enter as *const c_void),
+        (218, monitor_exit as *const c_void),
+        (219, get_java_vm as *const c_void),
+        (220, get_string_region as *const c_void),
+        (221, get_string_utf_region as *const c_void),
+        (222, get_array_elements as *const c_void),
+        (223, release_array_elements as *const c_void),
+        (224, get_string_chars as *const c_void),
+        (225, release_string_chars as *const c_void),
+        (226, new_global_ref as *const c_void),
+        (227, delete_global_ref as *const c_void),
+        (228, exception_check as *const c_void),
+        (232, get_object_ref_type as *const c_void),
+    ];
+    for (index, function) in functions {
+        table[index] = function;
+    }
+    // the functions that exist once per Java type, in the order Object, Boolean, Byte, Char,
+    // Short, Int, Long, Float, Double and, for calls only, Void. The array forms of the calls
+    // follow their variadic and va_list forms, which jni_varargs.c fills in.
+    let typed: [[*const c_void; 7]; 9] = [
+        typed_functions::<JObject>(),
+        typed_functions::<u8>(),
+        typed_functions::<i8>(),
+        typed_functions::<u16>(),
+        typed_functions::<i16>(),
+        typed_functions::<i32>(),
+        typed_functions::<i64>(),
+        typed_functions::<f32>(),
+        typed_functions::<f64>(),
+    ];
+    for (i, [call, nonvirtual, call_static, get, set, get_static, set_static]) in
+        typed.into_iter().enumerate()
+    {
+        table[36 + 3 * i] = call;
+        table[66 + 3 * i] = nonvirtual;
+        table[116 + 3 * i] = call_static;
+        table[95 + i] = get;
+        table[104 + i] = set;
+        table[145 + i] = get_static;
+        table[154 + i] = set_static;
+    }
+    table[63] = call_method_a::<()> as *const c_void;
+    table[93] = call_nonvirtual_method_a::<()> as *const c_void;
+    table[143] = call_static_method_a::<()> as *const c_void;
+    // the primitive array functions only depend on the element size
+    for i in 0..8 {
+        table[183 + i] = get_array_elements as *const c_void;
+        table[191 + i] = release_array_elements as *const c_void;
+        table[199 + i] = get_array_region as *const c_void;
+        table[207 + i] = set_array_region as *const c_void;
+    }
+    unsafe { jni_install_varargs(table.as_mut_ptr()) };
+    JniFunctions(table)
+}
+
+// Call<type>MethodA, CallNonvirtual<type>MethodA, CallStatic<type>MethodA,
+// Get<type>Field, Set<type>Field, GetStatic<type>Field and SetStatic<type>Field
+fn typed_functions<T: JniType>() -> [*const c_void; 7] {
+    [
+        call_method_a::<T> as *const c_void,
+        call_nonvirtual_method_a::<T> as *const c_void,
+        call_static_method_a::<T> as *const c_void,
+        get_field::<T> as *const c_void,
+        set_field::<T> as *const c_void,
+        get_static_field::<T> as *const c_void,
+        set_static_field::<T> as *const c_void,
+    ]
+}
+
+// The JNIInvokeInterface_ table. There is no invocation API, so the VM cannot be destroyed
+// and the only thread native code can attach is one already running a native method.
+pub fn invoke_table() -> InvokeFunctions {
+    InvokeFunctions([
+        std::ptr::null(),
+        std::ptr::null(),
+        std::ptr::null(),
+        destroy_java_vm as *const c_void,
+        attach_current_thread as *const c_void,
+        detach_current_thread as *const c_void,
+        get_env as *const c_void,
+        attach_current_thread as *const c_void,
+    ])
+}
+
+extern "C" fn unsupported() -> ! {
+    eprintln!("FATAL ERROR: native code called a JNI function this VM does not implement");
+    std::process::abort();
+}
+
+// a NUL-terminated C string from native code, names and descriptors are ASCII in practice
+// the text of a NUL-terminated modified UTF-8 string, malformed bytes are taken as UTF-8
+fn modified_utf8_string(pointer: *const c_char) -> String {
+    let bytes = unsafe { CStr::from_ptr(pointer) }.to_bytes();
+    match cesu8::from_java_cesu8(bytes) {
+        Ok(string) => string.into_owned(),
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn c_string(pointer: *const c_char) -> String {
+    if pointer.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(pointer) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+// a copy of elements in memory from malloc, native code hands it back to a Release function
+fn malloc_copy<T: Copy>(elements: &[T]) -> *mut T {
+    let size = std::mem::size_of_val(elements).max(1);
+    unsafe {
+        let buffer = libc::malloc(size) as *mut T;
+        if !buffer.is_null() {
+            std::ptr::copy_nonoverlapping(elements.as_ptr(), buffer, elements.len());
+        }
+        buffer
+    }
+}
+
+fn set_is_copy(is_copy: *mut u8) {
+    if !is_copy.is_null() {
+        unsafe { *is_copy = 1 };
+    }
+}
+
+extern "C" fn get_version(_env: &JniEnv) -> i32 {
+    JNI_VERSION
+}
+
+extern "C" fn find_class(env: &JniEnv, name: *const c_char) -> JObject {
+    let name = c_string(name);
+    let class = match block_on(env.vm.class_loader.load_class(&name, env.vm)) {
+        Ok(class) => class,
+        Err(_) => {
+            env.throw("java/lang/NoClassDefFoundError");
+            return std::ptr::null();
+        }
+    };
+    // like HotSpot, FindClass initializes the class it finds
+    match env.check(block_on(LoadedClass::initialize(
+        Arc::clone(&class),
+        env.vm,
+    ))) {
+        Some(()) => env.mirror(class),
+        None => std::ptr::null(),
+    }
+}
+
+extern "C" fn get_superclass(env: &JniEnv, clazz: JObject) -> JObject {
+    match env.class(clazz) {
+        Some(class) if !class.is_interface() => match &class.super_class {
+            Some(super_class) => env.mirror(Arc::clone(super_class)),
+            None => std::ptr::null(),
+        },
+        _ => std::ptr::null(),
+    }
+}
+
+extern "C" fn is_assignable_from(env: &JniEnv, sub: JObject, sup: JObject) -> u8 {
+    match (env.class(sub), env.class(sup)) {
+        (Some(sub), Some(sup)) => sub.is_subtype_of(&sup) as u8,
+        _ => 0,
+    }
+}
+
+extern "C" fn throw(env: &JniEnv, throwable: JObject) -> i32 {
+    match env.object(throwable) {
+        Some(throwable) => {
+            env.throw_object(throwable);
+            JNI_OK
+        }
+        None => JNI_ERR,
+    }
+}
+
+extern "C" fn throw_new(env: &JniEnv, clazz: JObject, message: *const c_char) -> i32 {
+    let Some(class) = env.class(clazz) else {
+        return JNI_ERR;
+    };
+    let message = (!message.is_null()).then(|| modified_utf8_string(message));
+    env.throw_new(&class.class_name, message.as_deref());
+    if env.exception().is_some() {
+        JNI_OK
+    } else {
+        JNI_ERR
+    }
+}
+
+extern "C" fn exception_occurred(env: &JniEnv) -> JObject {
+    env.exception()
+        .map_or(std::ptr::null(), |throwable| env.new_local(throwable))
+}
+
+extern "C" fn exception_describe(env: &JniEnv) {
+    if let Some(throwable) = env.exception() {
+        let class_name = throwable
+            .class
+            .as_ref()
+            .map(|class| class.class_name.replace('/', "."))
+            .unwrap_or_default();
+        // as Throwable.toString puts it
+        match throwable.get_field("detailMessage") {
+            Ok(Value::Reference(Some(message))) => eprintln!(
+                "Exception in native method {}: {}",
+                class_name,
+                extract_string(&message).unwrap_or_default()
+            ),
+            _ => eprintln!("Exception in native method {}", class_name),
+        }
+        env.clear_exception();
+    }
+}
+
+extern "C" fn exception_clear(env: &JniEnv) {
+    env.clear_exception();
+}
+
+extern "C" fn exception_check(env: &JniEnv) -> u8 {
+    env.exception().is_some() as u8
+}
+
+extern "C" fn fatal_error(_env: &JniEnv, message: *const c_char) -> ! {
+    eprintln!("FATAL ERROR in native method: {}", c_string(message));
+    std::process::abort();
+}
+
+extern "C" fn push_local_frame(env: &JniEnv, _capacity: i32) -> i32 {
+    env.push_frame();
+    JNI_OK
+}
+
+extern "C" fn pop_local_frame(env: &JniEnv, result: JObject) -> JObject {
+    env.pop_frame(result)
+}
+
+// weak global references are strong here, the collector does not reclaim reachable objects
+extern "C" fn new_global_ref(env: &JniEnv, obj: JObject) -> JObject {
+    env.object(obj).map_or(std::ptr::null(), |obj| {
+        env.vm.native_stack.jni.new_global(obj)
+    })
+}
+
+extern "C" fn delete_global_ref(env: &JniEnv, obj: JObject) {
+    env.vm.native_stack.jni.delete_global(obj);
+}
+
+extern "C" fn delete_local_ref(env: &JniEnv, obj: JObject) {
+    env.delete_local(obj);
+}
+
+// references are object addresses, so equal references mean the same object
+extern "C" fn is_same_object(_env: &JniEnv, a: JObject, b: JObject) -> u8 {
+    (a == b) as u8
+}
+
+extern "C" fn new_local_ref(env: &JniEnv, obj: JObject) -> JObject {
+    env.object(obj)
+        .map_or(std::ptr::null(), |obj| env.new_local(obj))
+}
+
+extern "C" fn ensure_local_capacity(_env: &JniEnv, _capacity: i32) -> i32 {
+    JNI_OK
+}
+
+extern "C" fn get_object_ref_type(env: &JniEnv, obj: JObject) -> i32 {
+    if env.is_local(obj) {
+        LOCAL_REF
+    } else if env.vm.native_stack.jni.is_global(obj) {
+        GLOBAL_REF
+    } else {
+        INVALID_REF
+    }
+}
+
+fn allocate(env: &JniEnv, clazz: JObject) -> Option<Arc<Object>> {
+    let class = env.class(clazz)?;
+    if class.is_interface() || class.access_flags.contains(ClassFlags::ACC_ABSTRACT) {
+        env.throw("java/lang/InstantiationException");
+        return None;
+    }
+    match env.check(block_on(
+        env.vm.allocate_object(env.stack, &class.class_name),
+    ))? {
+        Value::Reference(object) => object,
+        _ => None,
+    }
+}
+
+extern "C" fn alloc_object(env: &JniEnv, clazz: JObject) -> JObject {
+    allocate(env, clazz).map_or(std::ptr::null(), |obj| env.new_local(obj))
+}
+
+extern "C" fn new_object_a(
+    env: &JniEnv,
+    clazz: JObject,
+    method: &JniMethod,
+    args: *const JValue,
+) -> JObject {
+    let Some(object) = allocate(env, clazz) else {
+        return std::ptr::null();
+    };
+    call::<()>(env, &method.method, Some(Arc::clone(&object)), method, args);
+    if env.exception().is_some() {
+        return std::ptr::null();
+    }
+    env.new_local(object)
+}
+
+extern "C" fn get_object_class(env: &JniEnv, obj: JObject) -> JObject {
+    match env.object(obj).and_then(|obj| obj.class.clone()) {
+        Some(class) => env.mirror(class),
+        None => std::ptr::null(),
+    }
+}
+
+extern "C" fn is_instance_of(env: &JniEnv, obj: JObject, clazz: JObject) -> u8 {
+    let Some(class) = env.class(clazz) else {
+        return 0;
+    };
+    match env.object(obj) {
+        Some(obj) => obj
+            .class
+            .as_ref()
+            .is_some_and(|obj_class| obj_class.is_subtype_of(&class)) as u8,
+        // null is an instance of every class
+        None => 1,
+    }
+}
+
+// the class behind clazz, initialized as HotSpot does before handing out IDs
+fn initialized_class(env: &JniEnv, clazz: JObject) -> Option<Arc<LoadedClass>> {
+    let class = env.class(clazz)?;
+    env.check(block_on(LoadedClass::initialize(
+        Arc::clone(&class),
+        env.vm,
+    )))?;
+    Some(class)
+}
+
+fn method_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+    is_static: bool,
+) -> *const JniMethod {
+    let Some(class) = initialized_class(env, clazz) else {
+        return std::ptr::null();
+    };
+    let name_des = NameDes {
+        name: c_string(name),
+        des: c_string(signature),
+    };
+    let resolved = if class.is_interface() {
+        LoadedClass::resolve_interface_method(&class, &name_des)
+    } else {
+        LoadedClass::resolve_method(&class, &name_des)
+    };
+    match resolved {
+        Ok(method) if method.is_static() == is_static => env
+            .check(env.vm.native_stack.jni.method_id(&class, method))
+            .unwrap_or(std::ptr::null()),
+        _ => {
+            env.throw("java/lang/NoSuchMethodError");
+            std::ptr::null()
+        }
+    }
+}
+
+extern "C" fn get_method_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+) -> *const JniMethod {
+    method_id(env, clazz, name, signature, false)
+}
+
+extern "C" fn get_static_method_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+) -> *const JniMethod {
+    method_id(env, clazz, name, signature, true)
+}
+
+// runs target with the receiver, if any, and the jvalue arguments of method
+fn call<T: JniType>(
+    env: &JniEnv,
+    target: &ResolvedMethod,
+    receiver: Option<Arc<Object>>,
+    method: &JniMethod,
+    args: *const JValue,
+) -> T {
+    let mut values = Vec::with_capacity(method.arg_types.len() + 1);
+    if let Some(receiver) = receiver {
+        values.push(Value::Reference(Some(receiver)));
+    }
+    for (i, arg_type) in method.arg_types.iter().enumerate() {
+        values.push(env.argument(arg_type, unsafe { *args.add(i) }));
+    }
+    let result = env.call_method(target, values);
+    T::from_value(env, result.flatten())
+}
+
+fn receiver(env: &JniEnv, obj: JObject) -> Option<Arc<Object>> {
+    let receiver = env.object(obj);
+    if receiver.is_none() {
+        env.throw("java/lang/NullPointerException");
+    }
+    receiver
+}
+
+extern "C" fn call_method_a<T: JniType>(
+    env: &JniEnv,
+    obj: JObject,
+    method: &JniMethod,
+    args: *const JValue,
+) -> T {
+    let Some(receiver) = receiver(env, obj) else {
+        return T::from_value(env, None);
+    };
+    let selected = match &receiver.class {
+        Some(class) => LoadedClass::select_method(class, &method.method),
+        None => Err(JVMError::NullReference),
+    };
+    match env.check(selected) {
+        Some(selected) => call(env, &selected, Some(receiver), method, args),
+        None => T::from_value(env, None),
+    }
+}
+
+extern "C" fn call_nonvirtual_method_a<T: JniType>(
+    env: &JniEnv,
+    obj: JObject,
+    _clazz: JObject,
+    method: &JniMethod,
+    args: *const JValue,
+) -> T {
+    match receiver(env, obj) {
+        Some(receiver) => call(env, &method.method, Some(receiver), method, args),
+        None => T::from_value(env, None),
+    }
+}
+
+extern "C" fn call_static_method_a<T: JniType>(
+    env: &JniEnv,
+    _clazz: JObject,
+    method: &JniMethod,
+    args: *const JValue,
+) -> T {
+    call(env, &method.method, None, method, args)
+}
+
+fn field_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+    is_static: bool,
+) -> *const JniField {
+    let Some(class) = initialized_class(env, clazz) else {
+        return std::ptr::null();
+    };
+    let name_des = NameDes {
+        name: c_string(name),
+        des: c_string(signature),
+    };
+    match LoadedClass::resolve_field(&class, &name_des) {
+        Some(slot @ FieldSlot::Instance(_)) if !is_static => {
+            env.vm.native_stack.jni.field_id(&class, name_des, slot)
+        }
+        Some(slot @ FieldSlot::Static(..)) if is_static => {
+            env.vm.native_stack.jni.field_id(&class, name_des, slot)
+        }
+        _ => {
+            env.throw("java/lang/NoSuchFieldError");
+            std::ptr::null()
+        }
+    }
+}
+
+extern "C" fn get_field_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+) -> *const JniField {
+    field_id(env, clazz, name, signature, false)
+}
+
+extern "C" fn get_static_field_id(
+    env: &JniEnv,
+    clazz: JObject,
+    name: *const c_char,
+    signature: *const c_char,
+) -> *const JniField {
+    field_id(env, clazz, name, signature, true)
+}
+
+extern "C" fn get_field<T: JniType>(env: &JniEnv, obj: JObject, field: &JniField) -> T {
+    let value = match (receiver(env, obj), &field.slot) {
+        (Some(obj), FieldSlot::Instance(slot)) => env.check(obj.get_field_at(*slot)),
+        _ => None,
+    };
+    T::from_value(env, value)
+}
+
+extern "C" fn set_field<T: JniType>(env: &JniEnv, obj: JObject, field: &JniField, value: T) {
+    if let (Some(obj), FieldSlot::Instance(slot)) = (receiver(env, obj), &field.slot) {
+        env.check(obj.set_field_at(*slot, value.into_value(env)));
+    }
+}
+
+extern "C" fn get_static_field<T: JniType>(env: &JniEnv, _clazz: JObject, field: &JniField) -> T {
+    let value = match &field.slot {
+        FieldSlot::Static(class, index) => class.static_values.borrow().get(*index).cloned(),
+        FieldSlot::Instance(_) => None,
+    };
+    T::from_value(env, value)
+}
+
+extern "C" fn set_static_field<T: JniType>(
+    env: &JniEnv,
+    _clazz: JObject,
+    field: &JniField,
+    value: T,
+) {
+    if let FieldSlot::Static(class, index) = &field.slot {
+        let value = value.into_value(env);
+        if let Some(slot) = class.static_values.borrow_mut().get_mut(*index) {
+            *slot = value;
+        }
+    }
+}
+
+extern "C" fn new_string(env: &JniEnv, chars: *const u16, length: i32) -> JObject {
+    let chars = if chars.is_null() || length <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(chars, length as usize) }
+    };
+    env.new_string(&String::from_utf16_lossy(chars))
+}
+
+extern "C" fn get_string_length(env: &JniEnv, string: JObject) -> i32 {
+    env.string(string)
+        .map_or(0, |string| string.encode_utf16().count() as i32)
+}
+
+extern "C" fn get_string_chars(env: &JniEnv, string: JObject, is_copy: *mut u8) -> *const u16 {
+    let Some(string) = env.string(string) else {
+        return std::ptr::null();
+    };
+    set_is_copy(is_copy);
+    malloc_copy(&string.encode_utf16().collect::<Vec<_>>())
+}
+
+extern "C" fn release_string_chars(_env: &JniEnv, _string: JObject, chars: *const u16) {
+    unsafe { libc::free(chars as *mut c_void) };
+}
+
+extern "C" fn new_string_utf(env: &JniEnv, bytes: *const c_char) -> JObject {
+    if bytes.is_null() {
+        return std::ptr::null();
+    }
+    env.new_string(&modified_utf8_string(bytes))
+}
+
+// strings cross in the JVM's modified UTF-8: NUL is C0 80 and characters outside the Basic
+// Multilingual Plane are the 3-byte encodings of their two surrogates
+extern "C" fn get_string_utf_length(env: &JniEnv, string: JObject) -> i32 {
+    env.string(string)
+        .map_or(0, |string| cesu8::to_java_cesu8(&string).len() as i32)
+}
+
+extern "C" fn get_string_utf_chars(
+    env: &JniEnv,
+    string: JObject,
+    is_copy: *mut u8,
+) -> *const c_char {
+    let Some(string) = env.string(string) else {
+        return std::ptr::null();
+    };
+    set_is_copy(is_copy);
+    let mut bytes = cesu8::to_java_cesu8(&string).into_owned();
+    bytes.push(0);
+    malloc_copy(&bytes) as *const c_char
+}
+
+extern "C" fn release_string_utf_chars(_env: &JniEnv, _string: JObject, chars: *const c_char) {
+    unsafe { libc::free(chars as *mut c_void) };
+}
+
+// the UTF-16 units start..start + length of a string, None throws
+fn string_region(env: &JniEnv, string: JObject, start: i32, length: i32) -> Option<Vec<u16>> {
+    let chars = env.string(string)?.encode_utf16().collect::<Vec<_>>();
+    let (start, length) = (start as usize, length as usize);
+    match chars.get(start..start.checked_add(length)?) {
+        Some(region) => Some(region.to_vec()),
+        None => {
+            env.throw("java/lang/StringIndexOutOfBoundsException");
+            None
+        }
+    }
+}
+
+extern "C" fn get_string_region(
+    env: &JniEnv,
+    string: JObject,
+    start: i32,
+    length: i32,
+    buffer: *mut u16,
+) {
+    if let Some(region) = string_region(env, string, start, length) {
+        unsafe { std::ptr::copy_nonoverlapping(region.as_ptr(), buffer, region.len()) };
+    }
+}
+
+extern "C" fn get_string_utf_region(
+    env: &JniEnv,
+    string: JObject,
+    start: i32,
+    length: i32,
+    buffer: *mut c_char,
+) {
+    if let Some(region) = string_region(env, string, start, length) {
+        let mut bytes = cesu8::to_java_cesu8(&String::from_utf16_lossy(&region)).into_owned();
+        bytes.push(0);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr() as *const c_char, buffer, bytes.len())
+        };
+    }
+}
+
+// runs f on the elements of the array behind a jarray
+fn with_elements<R>(
+    env: &JniEnv,
+    array: JObject,
+    f: impl FnOnce(&mut ArrayStorage) -> R,
+) -> Option<R> {
+    let array = receiver(env, array)?;
+    match &array.kind {
+        ObjectKind::ArrayInstance { elements, .. } => Some(f(&mut elements.borrow_mut())),
+        ObjectKind::ClassInstance { .. } => {
+            env.fail(JVMError::Other(
+                "JNI array function on a non-array".to_string(),
+            ));
+            None
+        }
+    }
+}
+
+extern "C" fn get_array_length(env: &JniEnv, array: JObject) -> i32 {
+    with_elements(env, array, |elements| elements.len() as i32).unwrap_or(0)
+}
+
+extern "C" fn new_object_array(
+    env: &JniEnv,
+    length: i32,
+    element_class: JObject,
+    initial: JObject,
+) -> JObject {
+    let Some(class) = env.class(element_class) else {
+        return std::ptr::null();
+    };
+    if length < 0 {
+        env.throw("java/lang/NegativeArraySizeException");
+        return std::ptr::null();
+    }
+    let element_type = if class.is_array() {
+        class.class_name.clone()
+    } else {
+        format!("L{};", class.class_name)
+    };
+    let Some(array) = env.check(block_on(env.vm.allocate_array(
+        env.stack,
+        &element_type,
+        length as usize,
+    ))) else {
+        return std::ptr::null();
+    };
+    let initial = env.value(initial);
+    if let (Value::Reference(Some(array)), Value::Reference(Some(_))) = (&array, &initial) {
+        for i in 0..length as usize {
+            env.check(array.set_element(i, initial.clone()));
+        }
+    }
+    env.local_ref(&array)
+}
+
+// index checked against the length of the array, None throws
+fn array_index(env: &JniEnv, elements: &ArrayStorage, index: i32) -> Option<usize> {
+    let index = usize::try_from(index).ok().filter(|&i| i < elements.len());
+    if index.is_none() {
+        env.throw("java/lang/ArrayIndexOutOfBoundsException");
+    }
+    index
+}
+
+extern "C" fn get_object_array_element(env: &JniEnv, array: JObject, index: i32) -> JObject {
+    let element = with_elements(env, array, |elements| {
+        array_index(env, elements, index).and_then(|index| elements.get(index))
+    });
+    element
+        .flatten()
+        .map_or(std::ptr::null(), |element| env.local_ref(&element))
+}
+
+extern "C" fn set_object_array_element(env: &JniEnv, array: JObject, index: i32, value: JObject) {
+    let value = env.value(value);
+    with_elements(env, array, |elements| {
+        if let Some(index) = array_index(env, elements, index) {
+            env.check(elements.set(index, value));
+        }
+    });
+}
+
+extern "C" fn new_array<const TYPE: char>(env: &JniEnv, length: i32) -> JObject {
+    if length < 0 {
+        env.throw("java/lang/NegativeArraySizeException");
+        return std::ptr::null();
+    }
+    env.check(block_on(env.vm.allocate_array(
+        env.stack,
+        &TYPE.to_string(),
+        length as usize,
+    )))
+    .map_or(std::ptr::null(), |array| env.local_ref(&array))
+}
+
+// Get<type>ArrayElements and GetPrimitiveArrayCritical hand out a copy of the elements,
+// the Java heap has no stable addresses to pin
+extern "C" fn get_array_elements(env: &JniEnv, array: JObject, is_copy: *mut u8) -> *mut c_void {
+    let bytes = with_elements(env, array, |elements| {
+        elements.read_bytes(0, elements.len() * elements.element_size())
+    });
+    match bytes.flatten() {
+        Some(bytes) => {
+            set_is_copy(is_copy);
+            malloc_copy(&bytes) as *mut c_void
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+extern "C" fn release_array_elements(env: &JniEnv, array: JObject, buffer: *mut c_void, mode: i32) {
+    if mode != JNI_ABORT {
+        with_elements(env, array, |elements| {
+            let length = elements.len() * elements.element_size();
+            let bytes = unsafe { std::slice::from_raw_parts(buffer as *const u8, length) };
+            env.check(elements.write_bytes(0, bytes));
+        });
+    }
+    if mode != JNI_COMMIT {
+        unsafe { libc::free(buffer) };
+    }
+}
+
+// byte offset and length of elements start..start + length, None throws
+fn array_region(
+    env: &JniEnv,
+    elements: &ArrayStorage,
+    start: i32,
+    length: i32,
+) -> Option<(usize, usize)> {
+    let size = elements.element_size();
+    let region = usize::try_from(start)
+        .ok()
+        .zip(usize::try_from(length).ok())
+        .filter(|&(start, length)| start + length <= elements.len());
+    if region.is_none() {
+        env.throw("java/lang/ArrayIndexOutOfBoundsException");
+    }
+    region.map(|(start, length)| (start * size, length * size))
+}
+
+extern "C" fn get_array_region(
+    env: &JniEnv,
+    array: JObject,
+    start: i32,
+    length: i32,
+    buffer: *mut c_void,
+) {
+    with_elements(env, array, |elements| {
+        let (offset, length) = array_region(env, elements, start, length)?;
+        let bytes = elements.read_bytes(offset, length)?;
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer as *mut u8, length) };
+        Some(())
+    });
+}
+
+extern "C" fn set_array_region(
+    env: &JniEnv,
+    array: JObject,
+    start: i32,
+    length: i32,
+    buffer: *const c_void,
+) {
+    with_elements(env, array, |elements| {
+        let (offset, length) = array_region(env, elements, start, length)?;
+        let bytes = unsafe { std::slice::from_raw_parts(buffer as *const u8, length) };
+        env.check(elements.write_bytes(offset, bytes))
+    });
+}
+
+extern "C" fn register_natives(
+    env: &JniEnv,
+    clazz: JObject,
+    methods: *const JniNativeMethod,
+    count: i32,
+) -> i32 {
+    let Some(class) = env.class(clazz) else {
+        return JNI_ERR;
+    };
+    let methods = unsafe { std::slice::from_raw_parts(methods, count.max(0) as usize) };
+    for method in methods {
+        let name_des = NameDes {
+            name: c_string(method.name),
+            des: c_string(method.signature),
+        };
+        let is_native = class
+            .get_method_info_from_name_and_descriptor(&name_des)
+            .is_some_and(|info| info.access_flags.contains(MethodFlags::ACC_NATIVE));
+        if !is_native {
+            env.throw("java/lang/NoSuchMethodError");
+            return JNI_ERR;
+        }
+        env.vm
+            .native_stack
+            .register_function(&class.class_name, name_des, method.function);
+    }
+    JNI_OK
+}
+
+extern "C" fn unregister_natives(env: &JniEnv, clazz: JObject) -> i32 {
+    match env.class(clazz) {
+        Some(class) => {
+            env.vm.native_stack.unregister_functions(&class.class_name);
+            JNI_OK
+        }
+        None => JNI_ERR,
+    }
+}
+
+// the interpreter runs one Java thread, monitors never contend
+extern "C" fn monitor_enter(env: &JniEnv, obj: JObject) -> i32 {
+    match receiver(env, obj) {
+        Some(_) => JNI_OK,
+        None => JNI_ERR,
+    }
+}
+
+extern "C" fn monitor_exit(env: &JniEnv, obj: JObject) -> i32 {
+    monitor_enter(env, obj)
+}
+
+extern "C" fn get_java_vm(_env: &JniEnv, vm: *mut *const c_void) -> i32 {
+    unsafe { *vm = java_vm() };
+    JNI_OK
+}
+
+extern "C" fn destroy_java_vm(_vm: *const c_void) -> i32 {
+    JNI_ERR
+}
+
+extern "C" fn attach_current_thread(
+    _vm: *const c_void,
+    penv: *mut *const c_void,
+    _args: *const c_void,
+) -> i32 {
+    let env = current_env();
+    unsafe { *penv = env };
+    if env.is_null() {
+        JNI_ERR
+    } else {
+        JNI_OK
+    }
+}
+
+// a thread is attached exactly while it runs a native method, which detaching cannot end
+extern "C" fn detach_current_thread(_vm: *const c_void) -> i32 {
+    JNI_OK
+}
+
+extern "C" fn get_env(_vm: *const c_void, penv: *mut *const c_void, version: i32) -> i32 {
+    let env = current_env();
+    unsafe { *penv = std::ptr::null() };
+    if env.is_null() {
+        return JNI_EDETACHED;
+    }
+    if !is_supported_version(version) {
+        return JNI_EVERSION;
+    }
+    unsafe { *penv = env };
+    JNI_OK
+}
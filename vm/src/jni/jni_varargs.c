@@ -0,0 +1,192 @@
+// jni_varargs.c
+// The variadic and va_list forms of the JNI Call*Method and NewObject functions. Stable Rust
+// cannot define C variadic functions, so these read the arguments into a jvalue array as the
+// method's descriptor dictates and call the array form the VM put in the function table.
+#include <stdarg.h>
+#include <stdint.h>
+
+typedef union {
+    uint8_t z;
+    int8_t b;
+    uint16_t c;
+    int16_t s;
+    int32_t i;
+    int64_t j;
+    float f;
+    double d;
+    void* l;
+} jvalue;
+
+// leading field of what a jmethodID points to, one type character per argument
+typedef struct {
+    const char* arg_shape;
+} method_shape;
+
+// a method has at most 255 arguments
+#define MAX_ARGS 256
+
+// the table entry at index of the JNIEnv, the array form of the function being called
+#define ARRAY_FORM(env, index) ((*(void***) (env))[index])
+
+static void collect(const method_shape* method, va_list args, jvalue* values) {
+    for (int i = 0; method->arg_shape[i] != '\0' && i < MAX_ARGS; i++) {
+        switch (method->arg_shape[i]) {
+        case 'Z': values[i].z = (uint8_t) va_arg(args, int); break;
+        case 'B': values[i].b = (int8_t) va_arg(args, int); break;
+        case 'C': values[i].c = (uint16_t) va_arg(args, int); break;
+        case 'S': values[i].s = (int16_t) va_arg(args, int); break;
+        case 'I': values[i].i = va_arg(args, int32_t); break;
+        case 'J': values[i].j = va_arg(args, int64_t); break;
+        // floats are promoted to double when passed through ...
+        case 'F': values[i].f = (float) va_arg(args, double); break;
+        case 'D': values[i].d = va_arg(args, double); break;
+        default: values[i].l = va_arg(args, void*); break;
+        }
+    }
+}
+
+// Call<type>Method and CallStatic<type>Method, the receiver is an object or a class
+#define CALL(type, name, index)                                                              \
+    static type name##V(void* env, void* target, const method_shape* method, va_list args) { \
+        jvalue values[MAX_ARGS];                                                             \
+        collect(method, args, values);                                                       \
+        type (*array_form)(void*, void*, const method_shape*, const jvalue*) =               \
+            ARRAY_FORM(env, index);                                                          \
+        return array_form(env, target, method, values);                                      \
+    }                                                                                        \
+    static type name(void* env, void* target, const method_shape* method, ...) {            \
+        va_list args;                                                                        \
+        va_start(args, method);                                                              \
+        type result = name##V(env, target, method, args);                                    \
+        va_end(args);                                                                        \
+        return result;                                                                       \
+    }
+
+#define CALL_VOID(name, index)                                                               \
+    static void name##V(void* env, void* target, const method_shape* method, va_list args) { \
+        jvalue values[MAX_ARGS];                                                             \
+        collect(method, args, values);                                                       \
+        void (*array_form)(void*, void*, const method_shape*, const jvalue*) =               \
+            ARRAY_FORM(env, index);                                                          \
+        array_form(env, target, method, values);                                             \
+    }                                                                                        \
+    static void name(void* env, void* target, const method_shape* method, ...) {            \
+        va_list args;                                                                        \
+        va_start(args, method);                                                              \
+        name##V(env, target, method, args);                                                  \
+        va_end(args);                                                                        \
+    }
+
+// CallNonvirtual<type>Method, which also names the class to take the method from
+#define CALL_NONVIRTUAL(type, name, index)                                                   \
+    static type name##V(void* env, void* obj, void* clazz, const method_shape* method,       \
+                        va_list args) {                                                      \
+        jvalue values[MAX_ARGS];                                                             \
+        collect(method, args, values);                                                       \
+        type (*array_form)(void*, void*, void*, const method_shape*, const jvalue*) =        \
+            ARRAY_FORM(env, index);                                                          \
+        return array_form(env, obj, clazz, method, values);                                  \
+    }                                                                                        \
+    static type name(void* env, void* obj, void* clazz, const method_shape* method, ...) {  \
+        va_list args;                                                                        \
+        va_start(args, method);                                                              \
+        type result = name##V(env, obj, clazz, method, args);                                \
+        va_end(args);                                                                        \
+        return result;                                                                       \
+    }
+
+#define CALL_NONVIRTUAL_VOID(name, index)                                                    \
+    static void name##V(void* env, void* obj, void* clazz, const method_shape* method,       \
+                        va_list args) {                                                      \
+        jvalue values[MAX_ARGS];                                                             \
+        collect(method, args, values);                                                       \
+        void (*array_form)(void*, void*, void*, const method_shape*, const jvalue*) =        \
+            ARRAY_FORM(env, index);                                                          \
+        array_form(env, obj, clazz, method, values);                                         \
+    }                                                                                        \
+    static void name(void* env, void* obj, void* clazz, const method_shape* method, ...) {  \
+        va_list args;                                                                        \
+        va_start(args, method);                                                              \
+        name##V(env, obj, clazz, method, args);                                              \
+        va_end(args);                                                                        \
+    }
+
+CALL(void*, NewObject, 30)
+
+CALL(void*, CallObjectMethod, 36)
+CALL(uint8_t, CallBooleanMethod, 39)
+CALL(int8_t, CallByteMethod, 42)
+CALL(uint16_t, CallCharMethod, 45)
+CALL(int16_t, CallShortMethod, 48)
+CALL(int32_t, CallIntMethod, 51)
+CALL(int64_t, CallLongMethod, 54)
+CALL(float, CallFloatMethod, 57)
+CALL(double, CallDoubleMethod, 60)
+CALL_VOID(CallVoidMethod, 63)
+
+CALL_NONVIRTUAL(void*, CallNonvirtualObjectMethod, 66)
+CALL_NONVIRTUAL(uint8_t, CallNonvirtualBooleanMethod, 69)
+CALL_NONVIRTUAL(int8_t, CallNonvirtualByteMethod, 72)
+CALL_NONVIRTUAL(uint16_t, CallNonvirtualCharMethod, 75)
+CALL_NONVIRTUAL(int16_t, CallNonvirtualShortMethod, 78)
+CALL_NONVIRTUAL(int32_t, CallNonvirtualIntMethod, 81)
+CALL_NONVIRTUAL(int64_t, CallNonvirtualLongMethod, 84)
+CALL_NONVIRTUAL(float, CallNonvirtualFloatMethod, 87)
+CALL_NONVIRTUAL(double, CallNonvirtualDoubleMethod, 90)
+CALL_NONVIRTUAL_VOID(CallNonvirtualVoidMethod, 93)
+
+CALL(void*, CallStaticObjectMethod, 116)
+CALL(uint8_t, CallStaticBooleanMethod, 119)
+CALL(int8_t, CallStaticByteMethod, 122)
+CALL(uint16_t, CallStaticCharMethod, 125)
+CALL(int16_t, CallStaticShortMethod, 128)
+CALL(int32_t, CallStaticIntMethod, 131)
+CALL(int64_t, CallStaticLongMethod, 134)
+CALL(float, CallStaticFloatMethod, 137)
+CALL(double, CallStaticDoubleMethod, 140)
+CALL_VOID(CallStaticVoidMethod, 143)
+
+// fills the slots of the variadic and va_list forms, each pair precedes its array form
+void jni_install_varargs(void** table) {
+    struct {
+        void* call;
+        void* call_v;
+        int index;
+    } forms[] = {
+        {(void*) NewObject, (void*) NewObjectV, 28},
+        {(void*) CallObjectMethod, (void*) CallObjectMethodV, 34},
+        {(void*) CallBooleanMethod, (void*) CallBooleanMethodV, 37},
+        {(void*) CallByteMethod, (void*) CallByteMethodV, 40},
+        {(void*) CallCharMethod, (void*) CallCharMethodV, 43},
+        {(void*) CallShortMethod, (void*) CallShortMethodV, 46},
+        {(void*) CallIntMethod, (void*) CallIntMethodV, 49},
+        {(void*) CallLongMethod, (void*) CallLongMethodV, 52},
+        {(void*) CallFloatMethod, (void*) CallFloatMethodV, 55},
+        {(void*) CallDoubleMethod, (void*) CallDoubleMethodV, 58},
+        {(void*) CallVoidMethod, (void*) CallVoidMethodV, 61},
+        {(void*) CallNonvirtualObjectMethod, (void*) CallNonvirtualObjectMethodV, 64},
+        {(void*) CallNonvirtualBooleanMethod, (void*) CallNonvirtualBooleanMethodV, 67},
+        {(void*) CallNonvirtualByteMethod, (void*) CallNonvirtualByteMethodV, 70},
+        {(void*) CallNonvirtualCharMethod, (void*) CallNonvirtualCharMethodV, 73},
+        {(void*) CallNonvirtualShortMethod, (void*) CallNonvirtualShortMethodV, 76},
+        {(void*) CallNonvirtualIntMethod, (void*) CallNonvirtualIntMethodV, 79},
+        {(void*) CallNonvirtualLongMethod, (void*) CallNonvirtualLongMethodV, 82},
+        {(void*) CallNonvirtualFloatMethod, (void*) CallNonvirtualFloatMethodV, 85},
+        {(void*) CallNonvirtualDoubleMethod, (void*) CallNonvirtualDoubleMethodV, 88},
+        {(void*) CallNonvirtualVoidMethod, (void*) CallNonvirtualVoidMethodV, 91},
+        {(void*) CallStaticObjectMethod, (void*) CallStaticObjectMethodV, 114},
+        {(void*) CallStaticBooleanMethod, (void*) CallStaticBooleanMethodV, 117},
+        {(void*) CallStaticByteMethod, (void*) CallStaticByteMethodV, 120},
+        {(void*) CallStaticCharMethod, (void*) CallStaticCharMethodV, 123},
+        {(void*) CallStaticShortMethod, (void*) CallStaticShortMethodV, 126},
+        {(void*) CallStaticIntMethod, (void*) CallStaticIntMethodV, 129},
+        {(void*) CallStaticLongMethod, (void*) CallStaticLongMethodV, 132},
+        {(void*) CallStaticFloatMethod, (void*) CallStaticFloatMethodV, 135},
+        {(void*) CallStaticDoubleMethod, (void*) CallStaticDoubleMethodV, 138},
+        {(void*) CallStaticVoidMethod, (void*) CallStaticVoidMethodV, 141},
+    };
+    for (unsigned i = 0; i < sizeof(forms) / sizeof(forms[0]); i++) {
+        table[forms[i].index] = forms[i].call;
+        table[forms[i].index + 1] = forms[i].call_v;
+    }
+}
@@ -49,6 +49,12 @@ pub enum JVMError {
         expected: String,
         found: String,
     },
+    FieldNotFound {
+        class: String,
+        name: String,
+        descriptor: String,
+    },
+    IncompatibleClassChange(String),
     AbstractMethodCall {
         class: String,
         name: String,
@@ -56,6 +62,9 @@ pub enum JVMError {
     },
 
     UncaughtException(String),
+    // Shutdown.halt0 stops the VM with this status, every frame unwinds so whoever started
+    // the VM decides what exiting means
+    Exit(i32),
 
     ClassCastException(String),
 
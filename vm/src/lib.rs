@@ -1,7 +1,10 @@
 pub mod class_loader {
+    pub mod bundled;
     pub mod class_loader;
     pub mod class_loading_error;
     pub mod classpath_entry;
+    pub mod java_home;
+    pub mod jimage;
     pub mod loaded_class;
 }
 pub mod execute {
@@ -21,14 +24,33 @@ pub mod execute {
     pub mod execute_shift;
     pub mod execute_cast;
     pub mod execute_monitor;
+    pub mod execute_native;
 }
 pub mod runtime;
 pub mod vm;
+pub mod embed;
+pub mod convert;
 pub mod jvm_error;
 pub mod object;
 pub mod heap;
 pub mod garbagge_collector;
 pub mod native;
+pub mod jni {
+    pub mod jni;
+    pub mod jni_functions;
+}
+pub mod intrinsics {
+    pub mod intrinsics;
+    pub mod intrinsics_class;
+    pub mod intrinsics_system;
+    pub mod intrinsics_unsafe;
+}
+pub mod inline_cache;
+#[cfg(feature = "jit")]
+pub mod jit {
+    pub mod compiler;
+    pub mod jit;
+}
 pub mod parse_des;
 pub mod state;
 pub mod vis;
@@ -1,303 +1,528 @@
 use super::class_loader::loaded_class::NameDes;
-use super::object::{Object, ObjectKind};
+use super::execute::execute::ExecutionResult;
+use super::jni::jni::{block_on, is_supported_version, java_vm, JObject, JniEnv, JniState};
+use super::jvm_error::JVMError;
+use super::object::{ArrayStorage, Object, ObjectKind};
 use super::parse_des::{parse_descriptor, parse_return_type};
-use super::runtime::Value;
+use super::runtime::{Stack, Value};
+use super::vm::VM;
 use libffi::middle::{Arg, Cif, CodePtr, Type};
-use libloading::{Library, Symbol};
+use libloading::Library;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ffi::{c_void, CString};
-use std::path::PathBuf;
+use std::ffi::c_void;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 
+// A shared library holding Java_<class>_<method> functions
 pub struct NativeMethodLoader {
     lib: Library,
-    functions: HashMap<String, *const ()>,
+    path: PathBuf,
 }
 
+// A native method implemented by the embedding program. It gets the arguments, the receiver
+// first for instance methods, and returns ExecutionResult::Return with the result (None for
+// void) or ExecutionResult::Throw.
+pub type RustNative = Rc<dyn Fn(&NativeContext, Vec<Value>) -> Result<ExecutionResult, JVMError>>;
+
+// What a Rust native can reach besides its arguments, the VM and the stack of the caller
+pub struct NativeContext<'a> {
+    pub vm: &'a VM,
+    pub stack: &'a Stack,
+}
+
+// The registered libraries, a native method binds to the first one exporting its symbol
 pub struct NativeStack {
-    native_loaders: HashMap<String, Arc<NativeMethodLoader>>, // Library name -> loader
-    native_method_map: HashMap<NameDes, String>,              // Method -> library name
+    native_loaders: RefCell<Vec<NativeMethodLoader>>,
+    // natives implemented by Rust closures, these win over every library
+    rust_natives: RefCell<HashMap<String, HashMap<NameDes, RustNative>>>,
+    // functions bound through the JNI RegisterNatives, these win over exported symbols
+    registered: RefCell<HashMap<String, HashMap<NameDes, *const c_void>>>,
+    // global references and the method and field IDs handed out to native code
+    pub(crate) jni: JniState,
+}
+
+// One C argument converted from a Java value, it lives until the call returns
+enum NativeArg {
+    Boolean(u8),
+    Byte(i8),
+    Char(u16),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Pointer(*const c_void),
+}
+
+impl NativeArg {
+    // JNI passes primitives by value and objects as local references
+    fn new(arg_type: &str, value: &Value, env: &JniEnv) -> Result<Self, JVMError> {
+        Ok(match (arg_type, value) {
+            ("Z", Value::Int(i)) => NativeArg::Boolean(*i as u8),
+            ("B", Value::Int(i)) => NativeArg::Byte(*i as i8),
+            ("C", Value::Int(i)) => NativeArg::Char(*i as u16),
+            ("S", Value::Int(i)) => NativeArg::Short(*i as i16),
+            ("I", Value::Int(i)) => NativeArg::Int(*i),
+            ("J", Value::Long(l)) => NativeArg::Long(*l),
+            ("F", Value::Float(f)) => NativeArg::Float(*f),
+            ("D", Value::Double(d)) => NativeArg::Double(*d),
+            (arg_type, Value::Reference(_)) if arg_type.starts_with(['L', '[']) => {
+                NativeArg::Pointer(env.local_ref(value) as *const c_void)
+            }
+            (arg_type, value) => {
+                return Err(JVMError::TypeMismatch {
+                    expected: arg_type.to_string(),
+                    found: format!("{:?}", value),
+                })
+            }
+        })
+    }
+
+    fn ffi_type(&self) -> Type {
+        match self {
+            NativeArg::Boolean(_) => Type::u8(),
+            NativeArg::Byte(_) => Type::i8(),
+            NativeArg::Char(_) => Type::u16(),
+            NativeArg::Short(_) => Type::i16(),
+            NativeArg::Int(_) => Type::i32(),
+            NativeArg::Long(_) => Type::i64(),
+            NativeArg::Float(_) => Type::f32(),
+            NativeArg::Double(_) => Type::f64(),
+            NativeArg::Pointer(_) => Type::pointer(),
+        }
+    }
+
+    fn as_arg(&self) -> Arg {
+        match self {
+            NativeArg::Boolean(v) => Arg::new(v),
+            NativeArg::Byte(v) => Arg::new(v),
+            NativeArg::Char(v) => Arg::new(v),
+            NativeArg::Short(v) => Arg::new(v),
+            NativeArg::Int(v) => Arg::new(v),
+            NativeArg::Long(v) => Arg::new(v),
+            NativeArg::Float(v) => Arg::new(v),
+            NativeArg::Double(v) => Arg::new(v),
+            NativeArg::Pointer(v) => Arg::new(v),
+        }
+    }
+}
+
+// the short symbol a native method is looked up by, Java_ followed by the class and method names
+pub fn native_symbol(class_name: &str, method_name: &str) -> String {
+    format!("Java_{}_{}", mangle(class_name), mangle(method_name))
+}
+
+// the long symbol of an overloaded native method, the short one followed by __ and the
+// argument types of its descriptor
+pub fn native_long_symbol(class_name: &str, name_des: &NameDes) -> String {
+    let arguments = name_des
+        .des
+        .strip_prefix('(')
+        .and_then(|des| des.split_once(')'))
+        .map_or("", |(arguments, _)| arguments);
+    format!(
+        "{}__{}",
+        native_symbol(class_name, &name_des.name),
+        mangle(arguments)
+    )
+}
+
+// escapes a name as the JNI specification's table of Unicode character translations has it
+fn mangle(name: &str) -> String {
+    let mut mangled = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' => mangled.push('_'),
+            '_' => mangled.push_str("_1"),
+            ';' => mangled.push_str("_2"),
+            '[' => mangled.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => mangled.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    let _ = write!(mangled, "_0{:04x}", unit);
+                }
+            }
+        }
+    }
+    mangled
+}
+
+// the directories System.loadLibrary searches: java.library.path if it was set or the usual
+// system directories
+pub fn library_path(vm: &VM) -> String {
+    match vm.property_override("java.library.path") {
+        Some(path) => path.to_string(),
+        None => "/usr/java/packages/lib:/usr/lib64:/lib64:/lib:/usr/lib".to_string(),
+    }
+}
+
+// the file System.loadLibrary loads for a library name, None if no directory has it
+pub fn find_library(vm: &VM, lib_name: &str) -> Option<PathBuf> {
+    let file_name = format!("lib{}.so", lib_name);
+    library_path(vm)
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| Path::new(dir).join(&file_name))
+        .find(|path| path.is_file())
 }
 
 impl NativeStack {
     pub fn new() -> Self {
         NativeStack {
-            native_loaders: HashMap::new(),
-            native_method_map: HashMap::new(),
+            native_loaders: RefCell::new(Vec::new()),
+            rust_natives: RefCell::new(HashMap::new()),
+            registered: RefCell::new(HashMap::new()),
+            jni: JniState::default(),
         }
     }
 
-    pub fn register_library(&mut self, lib_name: &str, lib_path: PathBuf) -> Result<(), String> {
-        let mut loader = NativeMethodLoader::new(lib_path)
+    pub fn register_library(&self, lib_name: &str, lib_path: PathBuf) -> Result<(), String> {
+        let loader = NativeMethodLoader::new(lib_path)
             .map_err(|e| format!("Failed to load library {}: {}", lib_name, e))?;
-        if lib_name == "native_io" {
-            loader.load_function("Java_ioTer_prints")?;
-            loader.load_function("Java_ioTer_printca")?;
-            loader.load_function("Java_ioTer_printd")?;
-            loader.load_function("Java_ioTer_printi")?;
-            loader.load_function("Java_ioTer_scani")?;
-            loader.load_function("Java_ioTer_scand")?;
-        } else if lib_name == "math" {
-            loader.load_function("Java_Math_add")?;
-        }
-        self.native_loaders
-            .insert(lib_name.to_string(), Arc::new(loader));
+        self.native_loaders.borrow_mut().push(loader);
         Ok(())
     }
 
-    pub fn register_method(&mut self, name_des: NameDes, lib_name: &str) -> Result<(), String> {
-        if self.native_loaders.contains_key(lib_name) {
-            self.native_method_map
-                .insert(name_des, lib_name.to_string());
-            Ok(())
-        } else {
-            Err(format!("Library {} not registered", lib_name))
+    // loads a library for System.load, running its JNI_OnLoad. A library loaded before is
+    // not loaded again, one that fails to load or asks for an unknown JNI version throws
+    // UnsatisfiedLinkError.
+    pub fn load_library(
+        &self,
+        vm: &VM,
+        stack: &Stack,
+        lib_path: &Path,
+    ) -> Result<ExecutionResult, JVMError> {
+        let unsatisfied = ExecutionResult::Throw("java/lang/UnsatisfiedLinkError".to_string());
+        let Ok(lib_path) = lib_path.canonicalize() else {
+            return Ok(unsatisfied);
+        };
+        if self
+            .native_loaders
+            .borrow()
+            .iter()
+            .any(|loader| loader.path == lib_path)
+        {
+            return Ok(ExecutionResult::Return(None));
+        }
+        let Ok(loader) = NativeMethodLoader::new(lib_path) else {
+            return Ok(unsatisfied);
+        };
+        if let Some(on_load) = loader.function("JNI_OnLoad") {
+            let env = JniEnv::new(vm, stack);
+            let current = env.make_current();
+            let version = unsafe {
+                let on_load: extern "C" fn(*const c_void, *const c_void) -> i32 =
+                    std::mem::transmute(on_load);
+                on_load(java_vm(), std::ptr::null())
+            };
+            drop(current);
+            match env.finish(None)? {
+                ExecutionResult::Return(_) if is_supported_version(version) => {}
+                ExecutionResult::Return(_) => return Ok(unsatisfied),
+                thrown => return Ok(thrown),
+            }
         }
+        self.native_loaders.borrow_mut().push(loader);
+        Ok(ExecutionResult::Return(None))
     }
 
-    pub fn invoke(
+    // binds a native method to a Rust closure
+    pub fn register_rust_native(
         &self,
-        name: &str,
         class_name: &str,
-        args: &[Value],
+        name: &str,
         descriptor: &str,
-    ) -> Result<Value, String> {
-
-        let lib_name = self
-            .native_method_map
-            .iter()
-            .find(|(key, _)| format!("Java_{}_{}", class_name, key.name) == name)
-            .map(|(_, lib)| lib.to_string())
-            .ok_or_else(|| format!("No library mapped for method: {}", name))?;
-
-        let loader = self
-            .native_loaders
-            .get(&lib_name)
-            .ok_or_else(|| format!("Library {} not found", lib_name))?;
-
-        loader.invoke(name, args, descriptor)
+        function: impl Fn(&NativeContext, Vec<Value>) -> Result<ExecutionResult, JVMError>
+            + 'static,
+    ) {
+        let name_des = NameDes {
+            name: name.to_string(),
+            des: descriptor.to_string(),
+        };
+        self.rust_natives
+            .borrow_mut()
+            .entry(class_name.to_string())
+            .or_default()
+            .insert(name_des, Rc::new(function));
     }
-}
 
-impl NativeMethodLoader {
-    pub fn new(lib_path: PathBuf) -> Result<Self, String> {
-        let lib = unsafe { Library::new(lib_path) }
-            .map_err(|e| format!("Failed to load library: {}", e))?;
-        Ok(NativeMethodLoader {
-            lib,
-            functions: HashMap::new(),
-        })
+    pub fn find_rust_native(&self, class_name: &str, name_des: &NameDes) -> Option<RustNative> {
+        self.rust_natives
+            .borrow()
+            .get(class_name)?
+            .get(name_des)
+            .cloned()
     }
 
-    pub fn load_function(&mut self, name: &str) -> Result<(), String> {
-        let symbol: Symbol<unsafe extern "C" fn()> = unsafe {
-            self.lib
-                .get(name.as_bytes())
-                .map_err(|e| format!("Failed to load symbol {}: {}", name, e))?
-        };
-        let raw_ptr = *symbol as *const ();
-        self.functions.insert(name.to_string(), raw_ptr);
-        Ok(())
+    // binds a native method to a function, as the JNI RegisterNatives does
+    pub fn register_function(&self, class_name: &str, name_des: NameDes, function: *const c_void) {
+        self.registered
+            .borrow_mut()
+            .entry(class_name.to_string())
+            .or_default()
+            .insert(name_des, function);
     }
 
-    pub fn invoke(&self, name: &str, args: &[Value], descriptor: &str) -> Result<Value, String> {
-        let func_ptr = self
-            .functions
-            .get(name)
-            .ok_or_else(|| format!("Native function {} not found", name))?;
+    pub fn unregister_functions(&self, class_name: &str) {
+        self.registered.borrow_mut().remove(class_name);
+    }
 
-        //println!("Invoking native function: {}, args: {:?}", name, args);
+    // the function implementing a native method, None if nothing binds it
+    pub fn find_function(&self, class_name: &str, name_des: &NameDes) -> Option<*const c_void> {
+        let registered = self
+            .registered
+            .borrow()
+            .get(class_name)
+            .and_then(|functions| functions.get(name_des).copied());
+        if registered.is_some() {
+            return registered;
+        }
+        // the short name first, the long one tells overloaded natives apart
+        let symbol = native_symbol(class_name, &name_des.name);
+        let long_symbol = native_long_symbol(class_name, name_des);
+        self.native_loaders.borrow().iter().find_map(|loader| {
+            loader
+                .function(&symbol)
+                .or_else(|| loader.function(&long_symbol))
+        })
+    }
 
-        let mut cif_args = Vec::new();
-        let mut call_args = Vec::new();
-        let mut c_strings = Vec::new();
+    // calls a JNI function with a JNIEnv, this (the receiver or the class of a static
+    // method) and args. An exception left pending by native code is thrown on return.
+    pub fn invoke(
+        &self,
+        vm: &VM,
+        stack: &Stack,
+        function: *const c_void,
+        this: Value,
+        args: &[Value],
+        descriptor: &str,
+    ) -> Result<ExecutionResult, JVMError> {
+        let descriptor = parse_descriptor(descriptor).map_err(JVMError::Other)?;
+        if descriptor.arg_types.len() != args.len() {
+            return Err(JVMError::Other(format!(
+                "Expected {} arguments, got {}",
+                descriptor.arg_types.len(),
+                args.len()
+            )));
+        }
+        let return_type = descriptor.return_type.as_str();
+        let env = JniEnv::new(vm, stack);
+        let current = env.make_current();
+        let mut native_args = vec![
+            NativeArg::Pointer(env.as_ptr()),
+            NativeArg::Pointer(env.local_ref(&this) as *const c_void),
+        ];
+        for (arg_type, value) in descriptor.arg_types.iter().zip(args) {
+            native_args.push(NativeArg::new(arg_type, value, &env)?);
+        }
 
-        let param_types = parse_descriptor(descriptor)?;
-        let return_type = param_types.return_type;
+        let cif = Cif::new(
+            native_args.iter().map(NativeArg::ffi_type),
+            parse_return_type(return_type).map_err(JVMError::Other)?,
+        );
+        let call_args = native_args.iter().map(NativeArg::as_arg).collect::<Vec<_>>();
+        let code_ptr = CodePtr::from_ptr(function);
 
-        match name {
-            "Java_ioTer_prints" => {
-                if args.len() != 1 {
-                    return Err(format!("Expected 1 arguments for add, got {}", args.len()));
+        // libffi widens integer results narrower than a register to a full one
+        let result = unsafe {
+            match return_type {
+                "V" => {
+                    cif.call::<()>(code_ptr, &call_args);
+                    None
                 }
-                match &args[0] {
-                    Value::Reference(Some(obj)) => {
-                        let string_value = extract_string(obj)?;
-                        let c_string = CString::new(string_value)
-                            .map_err(|e| format!("CString conversion failed: {}", e))?;
-                        c_strings.push(c_string); // Keep CString alive
-                        cif_args.push(Type::pointer());
-                        call_args.push(Arg::new(&c_strings.last().unwrap().as_ptr()));
-                    }
-                    _ => return Err("Expected string argument".to_string()),
-                }
-            }
-            "Java_ioTer_printca" => {
-                if args.len() != 1 {
-                    return Err(format!("Expected 1 arguments for add, got {}", args.len()));
+                "Z" => {
+                    let r = cif.call::<u64>(code_ptr, &call_args) as u8;
+                    Some(Value::Int((r != 0) as i32))
                 }
-                match &args[0] {
-                    Value::Reference(Some(obj)) => {
-                        let string_value = extract_from_char_array(obj)?;
-                        let c_string = CString::new(string_value)
-                            .map_err(|e| format!("CString conversion failed: {}", e))?;
-                        c_strings.push(c_string); // Keep CString alive
-                        cif_args.push(Type::pointer());
-                        call_args.push(Arg::new(&c_strings.last().unwrap().as_ptr()));
-                    }
-                    _ => return Err("Expected string argument".to_string()),
+                "B" => {
+                    let r = cif.call::<u64>(code_ptr, &call_args) as i8;
+                    Some(Value::Int(r as i32))
                 }
-            }
-            "Java_ioTer_printd" => {
-                if args.len() != 1 {
-                    return Err(format!(
-                        "Expected 1 argument for printNum, got {}",
-                        args.len()
-                    ));
+                "C" => {
+                    let r = cif.call::<u64>(code_ptr, &call_args) as u16;
+                    Some(Value::Int(r as i32))
                 }
-                let number = match &args[0] {
-                    Value::Int(i) => *i as f64,
-                    Value::Float(f) => *f as f64,
-                    Value::Double(d) => *d,
-                    _ => return Err("Argument must be a number (int, float, double)".to_string()),
-                };
-                cif_args.push(Type::f64());
-                call_args.push(Arg::new(&number));
-            }
-            "Java_ioTer_printi" => {
-                if args.len() != 1 {
-                    return Err(format!(
-                        "Expected 1 argument for printNum, got {}",
-                        args.len()
-                    ));
+                "S" => {
+                    let r = cif.call::<u64>(code_ptr, &call_args) as i16;
+                    Some(Value::Int(r as i32))
                 }
-                let number = match &args[0] {
-                    Value::Int(i) => *i,
-                    _ => return Err("Argument must be a number (int)".to_string()),
-                };
-                cif_args.push(Type::i32());
-                call_args.push(Arg::new(&number));
-            }
-            "Java_ioTer_scani" => {
-                if !args.is_empty() {
-                    return Err(format!(
-                        "Expected 0 arguments for scani, got {}",
-                        args.len()
-                    ));
+                "I" => {
+                    let r = cif.call::<u64>(code_ptr, &call_args) as i32;
+                    Some(Value::Int(r))
                 }
-                cif_args.clear();
-            }
-            "Java_ioTer_scans" => {
-                if !args.is_empty() {
-                    return Err(format!(
-                        "Expected 0 arguments for scans, got {}",
-                        args.len()
-                    ));
+                "J" => Some(Value::Long(cif.call(code_ptr, &call_args))),
+                "F" => Some(Value::Float(cif.call(code_ptr, &call_args))),
+                "D" => Some(Value::Double(cif.call(code_ptr, &call_args))),
+                _ => {
+                    // a local or global reference, whatever native code allocated for the
+                    // object is its own to free
+                    let handle = cif.call::<JObject>(code_ptr, &call_args);
+                    if !handle.is_null() && !env.is_local(handle) && !self.jni.is_global(handle) {
+                        return Err(JVMError::Other(format!(
+                            "Native method returned {:p}, which is not a JNI reference",
+                            handle
+                        )));
+                    }
+                    Some(env.value(handle))
                 }
-                cif_args.clear();
             }
-            "Java_ioTer_scand" => {
-                if !args.is_empty() {
-                    return Err(format!(
-                        "Expected 0 arguments for scand, got {}",
-                        args.len()
-                    ));
-                }
-                cif_args.clear();
+        };
+        drop(current);
+        env.finish(result)
+    }
+}
+
+// Allocation blocks until the heap is free, Rust natives run in the middle of the interpreter
+// like JNI functions do
+impl NativeContext<'_> {
+    pub fn new_string(&self, string: &str) -> Result<Value, JVMError> {
+        block_on(async {
+            let mut heap = self.vm.heap.write().await;
+            heap.allocate_string(self.stack, self.vm, string).await
+        })
+    }
+
+    // an instance of class_name with its fields zeroed, no constructor runs
+    pub fn new_object(&self, class_name: &str) -> Result<Value, JVMError> {
+        block_on(self.vm.allocate_object(self.stack, class_name))
+    }
+
+    // a zeroed array, element_type is a descriptor such as I or Ljava/lang/String;
+    pub fn new_array(&self, element_type: &str, length: usize) -> Result<Value, JVMError> {
+        block_on(self.vm.allocate_array(self.stack, element_type, length))
+    }
+
+    // the text of a java.lang.String argument, None for null
+    pub fn string(&self, value: &Value) -> Result<Option<String>, JVMError> {
+        match value {
+            Value::Reference(Some(string)) => {
+                extract_string(string).map(Some).map_err(JVMError::Other)
             }
-            _ => return Err(format!("Unknown native function: {}", name)),
+            Value::Reference(None) => Ok(None),
+            value => Err(JVMError::TypeMismatch {
+                expected: "Ljava/lang/String;".to_string(),
+                found: format!("{:?}", value),
+            }),
         }
+    }
+}
 
-        let cif = Cif::new(cif_args, parse_return_type(&return_type)?);
-        let code_ptr = CodePtr::from_ptr(*func_ptr as *const c_void);
+impl NativeMethodLoader {
+    pub fn new(lib_path: PathBuf) -> Result<Self, String> {
+        let lib = unsafe { Library::new(&lib_path) }
+            .map_err(|e| format!("Failed to load library: {}", e))?;
+        Ok(NativeMethodLoader {
+            lib,
+            path: lib_path,
+        })
+    }
 
+    fn function(&self, symbol: &str) -> Option<*const c_void> {
         unsafe {
-            match name {
-                "Java_ioTer_scani" => {
-                    let result: i32 = cif.call(code_ptr, &call_args);
-                    Ok(Value::Int(result))
-                }
-                /*
-                                "Java_ioTer_scans" => {
-                                    let result: *const c_char = cif.call(code_ptr, &call_args);
-                                    let c_str = CStr::from_ptr(result);
-                                    let str_slice = c_str
-                                        .to_str()
-                                        .map_err(|e| format!("Invalid string from scans: {}", e))?;
-                                    let string_obj = create_string_object(str_slice, vm)?;
-                                    std::ptr::drop_in_place(result as *mut c_char);
-                                    Ok(Value::Reference(Some(string_obj)))
-                                }
-                */
-                "Java_ioTer_scand" => {
-                    let result: f64 = cif.call(code_ptr, &call_args);
-                    Ok(Value::Double(result))
-                }
-                _ => {
-                    if return_type == "V" {
-                        cif.call::<()>(code_ptr, &call_args);
-                        Ok(Value::Int(0))
-                    } else {
-                        let result: i32 = cif.call(code_ptr, &call_args);
-                        Ok(Value::Int(result))
-                    }
-                }
-            }
+            self.lib
+                .get::<unsafe extern "C" fn()>(symbol.as_bytes())
+                .ok()
+                .map(|function| *function as *const c_void)
         }
     }
 }
-fn extract_string(obj: &Arc<Object>) -> Result<String, String> {
-    //println!("Extracting string from obj: kind={:?}, class={:?}", obj.kind, obj.class.as_ref().map(|c| &c.class_name));
-
-    if let ObjectKind::ClassInstance { fields } = &obj.kind {
-        let fields = fields.borrow();
-        //println!("Fields: {:?}", fields);
-        for field in fields.iter() {
-            if let Value::Reference(Some(char_array)) = field {
-                if let ObjectKind::ArrayInstance {
-                    elements,
-                    element_type,
-                    ..
-                } = &char_array.kind
-                {
-                    if element_type == "C" {
-                        let chars: String = elements
-                            .borrow()
-                            .iter()
-                            .map(|v| match v {
-                                Value::Int(c) => *c as u8 as char,
-                                _ => '?',
-                            })
-                            .collect();
-                        return Ok(chars);
-                    }
-                }
-            }
+
+// decodes a java.lang.String, its value array holds Latin-1 bytes or UTF-16 in native order
+pub fn extract_string(obj: &Arc<Object>) -> Result<String, String> {
+    let value = obj.get_field("value").map_err(|e| format!("{:?}", e))?;
+    let coder = obj.get_field("coder").map_err(|e| format!("{:?}", e))?;
+    let Value::Reference(Some(array)) = value else {
+        return Err("String has no value array".to_string());
+    };
+    let ObjectKind::ArrayInstance { elements, .. } = &array.kind else {
+        return Err(format!("Object is not an ArrayInstance: {:?}", array.kind));
+    };
+    let elements = elements.borrow();
+    match (&*elements, coder) {
+        (ArrayStorage::Byte(bytes), Value::Int(0)) => {
+            Ok(bytes.iter().map(|&b| b as u8 as char).collect())
         }
-        Err("No char array found in String object fields".to_string())
-    } else {
-        Err(format!("Object is not a ClassInstance: {:?}", obj.kind))
+        (ArrayStorage::Byte(bytes), Value::Int(1)) => {
+            let utf16 = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_ne_bytes([pair[0] as u8, pair[1] as u8]))
+                .collect::<Vec<_>>();
+            Ok(String::from_utf16_lossy(&utf16))
+        }
+        _ => Err("String value is not a byte array".to_string()),
     }
 }
 
-fn extract_from_char_array(obj: &Arc<Object>) -> Result<String, String> {
-    if let ObjectKind::ArrayInstance { elements, element_type, .. } = &obj.kind {
-        // Ensure the element type is "C" (char array)
-        if element_type == "C" {
-            // Map the elements (int values) to their corresponding chars and collect into a String
-            let chars: String = elements
-                .borrow()
-                .iter()
-                .map(|v| match v {
-                    Value::Int(c) => *c as u8 as char,
-                    _ => '?', // Handle any unexpected value types with a placeholder
-                })
-                .collect();
-            return Ok(chars);
-        } else {
-            Err("Array elements are not of type 'C'".to_string())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name_des(name: &str, des: &str) -> NameDes {
+        NameDes {
+            name: name.to_string(),
+            des: des.to_string(),
         }
-    } else {
-        Err(format!("Object is not an ArrayInstance: {:?}", obj.kind))
+    }
+
+    #[test]
+    fn short_symbols_escape_underscores_and_dollars() {
+        assert_eq!(native_symbol("pkg/Main", "run"), "Java_pkg_Main_run");
+        assert_eq!(
+            native_symbol("pkg/My_Class", "get_value"),
+            "Java_pkg_My_1Class_get_1value"
+        );
+        assert_eq!(
+            native_symbol("pkg/Outer$Inner", "do$it"),
+            "Java_pkg_Outer_00024Inner_do_00024it"
+        );
+    }
+
+    #[test]
+    fn short_symbols_escape_non_ascii_as_utf16_units() {
+        assert_eq!(native_symbol("Café", "naïve"), "Java_Caf_000e9_na_000efve");
+        // outside the Basic Multilingual Plane, one escape per surrogate
+        assert_eq!(native_symbol("A", "x\u{1F600}"), "Java_A_x_0d83d_0de00");
+    }
+
+    #[test]
+    fn long_symbols_append_the_mangled_arguments() {
+        assert_eq!(
+            native_long_symbol("A", &name_des("f", "(Ljava/lang/String;)V")),
+            "Java_A_f__Ljava_lang_String_2"
+        );
+        assert_eq!(
+            native_long_symbol("A", &name_des("f", "([I[[Ljava/lang/Object;)V")),
+            "Java_A_f___3I_3_3Ljava_lang_Object_2"
+        );
+        assert_eq!(native_long_symbol("A", &name_des("f", "()V")), "Java_A_f__");
+    }
+
+    #[test]
+    fn overloads_get_distinct_long_symbols() {
+        let overloads = [
+            name_des("add", "(II)I"),
+            name_des("add", "(JJ)J"),
+            name_des("add", "(Lpkg/My_Type;)I"),
+        ];
+        let symbols = overloads
+            .iter()
+            .map(|name_des| native_long_symbol("pkg/Calc", name_des))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            [
+                "Java_pkg_Calc_add__II",
+                "Java_pkg_Calc_add__JJ",
+                "Java_pkg_Calc_add__Lpkg_My_1Type_2",
+            ]
+        );
+        // the short symbol is the same for all of them
+        assert!(overloads
+            .iter()
+            .all(|name_des| native_symbol("pkg/Calc", &name_des.name) == "Java_pkg_Calc_add"));
     }
 }
@@ -7,6 +7,8 @@ use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 
 static OBJECT_ID: AtomicU32 = AtomicU32::new(0);
+// state of the xorshift generator behind identity hash codes
+static HASH_SEED: AtomicU32 = AtomicU32::new(0x2545_f491);
 
 #[derive(Debug)]
 pub struct ObjectHeader {
@@ -14,6 +16,8 @@ pub struct ObjectHeader {
     pub generation: u8,
     pub ref_count: u32,
     pub object_id: u32,
+    // assigned on the first identity hashCode request, stable for the object's lifetime
+    pub identity_hash: Option<i32>,
 }
 
 impl ObjectHeader {
@@ -25,6 +29,7 @@ impl ObjectHeader {
             generation: 0,
             ref_count: 1,
             object_id: id,
+            identity_hash: None,
         }
     }
 }
@@ -36,11 +41,211 @@ pub enum ObjectKind {
     },
     ArrayInstance {
         length: usize,
-        elements: RefCell<Vec<Value>>,
+        elements: RefCell<ArrayStorage>,
         element_type: String,
     },
 }
 
+// Backing storage of an array, specialised by element type.
+// boolean[] shares the byte representation like in HotSpot.
+#[derive(Debug, Clone)]
+pub enum ArrayStorage {
+    Byte(Vec<i8>),
+    Char(Vec<u16>),
+    Short(Vec<i16>),
+    Int(Vec<i32>),
+    Long(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Reference(Vec<Option<Arc<Object>>>),
+}
+
+impl ArrayStorage {
+    // every element starts with the default value of its type (JVMS 2.3, 2.4)
+    pub fn new(element_type: &str, length: usize) -> Self {
+        match element_type {
+            "Z" | "B" => ArrayStorage::Byte(vec![0; length]),
+            "C" => ArrayStorage::Char(vec![0; length]),
+            "S" => ArrayStorage::Short(vec![0; length]),
+            "I" => ArrayStorage::Int(vec![0; length]),
+            "J" => ArrayStorage::Long(vec![0; length]),
+            "F" => ArrayStorage::Float(vec![0.0; length]),
+            "D" => ArrayStorage::Double(vec![0.0; length]),
+            _ => ArrayStorage::Reference(vec![None; length]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            ArrayStorage::Byte(v) => v.len(),
+            ArrayStorage::Char(v) => v.len(),
+            ArrayStorage::Short(v) => v.len(),
+            ArrayStorage::Int(v) => v.len(),
+            ArrayStorage::Long(v) => v.len(),
+            ArrayStorage::Float(v) => v.len(),
+            ArrayStorage::Double(v) => v.len(),
+            ArrayStorage::Reference(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // byte, char and short elements are sign or zero extended to int on load
+    pub fn get(&self, index: usize) -> Option<Value> {
+        match self {
+            ArrayStorage::Byte(v) => v.get(index).map(|e| Value::Int(*e as i32)),
+            ArrayStorage::Char(v) => v.get(index).map(|e| Value::Int(*e as i32)),
+            ArrayStorage::Short(v) => v.get(index).map(|e| Value::Int(*e as i32)),
+            ArrayStorage::Int(v) => v.get(index).map(|e| Value::Int(*e)),
+            ArrayStorage::Long(v) => v.get(index).map(|e| Value::Long(*e)),
+            ArrayStorage::Float(v) => v.get(index).map(|e| Value::Float(*e)),
+            ArrayStorage::Double(v) => v.get(index).map(|e| Value::Double(*e)),
+            ArrayStorage::Reference(v) => v.get(index).map(|e| Value::Reference(e.clone())),
+        }
+    }
+
+    // int values stored into byte, char and short arrays are truncated (bastore, castore, sastore)
+    pub fn set(&mut self, index: usize, value: Value) -> Result<(), JVMError> {
+        let max = self.len();
+        if index >= max {
+            return Err(JVMError::IndexOutOfBounds { index, max });
+        }
+        match (self, value) {
+            (ArrayStorage::Byte(v), Value::Int(i)) => v[index] = i as i8,
+            (ArrayStorage::Char(v), Value::Int(i)) => v[index] = i as u16,
+            (ArrayStorage::Short(v), Value::Int(i)) => v[index] = i as i16,
+            (ArrayStorage::Int(v), Value::Int(i)) => v[index] = i,
+            (ArrayStorage::Long(v), Value::Long(l)) => v[index] = l,
+            (ArrayStorage::Float(v), Value::Float(f)) => v[index] = f,
+            (ArrayStorage::Double(v), Value::Double(d)) => v[index] = d,
+            (ArrayStorage::Reference(v), Value::Reference(r)) => v[index] = r,
+            (storage, value) => {
+                return Err(JVMError::TypeMismatch {
+                    expected: storage.type_name().to_string(),
+                    found: format!("{:?}", value),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ArrayStorage::Byte(_) => "B",
+            ArrayStorage::Char(_) => "C",
+            ArrayStorage::Short(_) => "S",
+            ArrayStorage::Int(_) => "I",
+            ArrayStorage::Long(_) => "J",
+            ArrayStorage::Float(_) => "F",
+            ArrayStorage::Double(_) => "D",
+            ArrayStorage::Reference(_) => "L",
+        }
+    }
+
+    // start of the elements of a primitive array, for native code
+    pub fn as_mut_ptr(&mut self) -> Result<*const std::ffi::c_void, String> {
+        Ok(match self {
+            ArrayStorage::Byte(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Char(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Short(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Int(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Long(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Float(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Double(v) => v.as_mut_ptr() as *const _,
+            ArrayStorage::Reference(_) => {
+                return Err("Arrays of references have no native representation".to_string())
+            }
+        })
+    }
+
+    // element size as seen through Unsafe, references count 4 bytes like compressed oops
+    pub fn element_size(&self) -> usize {
+        match self {
+            ArrayStorage::Byte(_) => 1,
+            ArrayStorage::Char(_) | ArrayStorage::Short(_) => 2,
+            ArrayStorage::Int(_) | ArrayStorage::Float(_) | ArrayStorage::Reference(_) => 4,
+            ArrayStorage::Long(_) | ArrayStorage::Double(_) => 8,
+        }
+    }
+
+    // raw read of a primitive array in native byte order, the range may span several elements
+    pub fn read_bytes(&self, offset: usize, len: usize) -> Option<Vec<u8>> {
+        let size = self.element_size();
+        if offset + len > self.len() * size {
+            return None;
+        }
+        let mut bytes = Vec::with_capacity(len + 2 * size);
+        for index in offset / size..(offset + len).div_ceil(size) {
+            bytes.extend(self.element_bytes(index)?);
+        }
+        let start = offset % size;
+        Some(bytes[start..start + len].to_vec())
+    }
+
+    pub fn write_bytes(&mut self, offset: usize, bytes: &[u8]) -> Result<(), JVMError> {
+        let size = self.element_size();
+        let first = offset / size;
+        let last = (offset + bytes.len()).div_ceil(size);
+        let mut raw = self
+            .read_bytes(first * size, (last - first) * size)
+            .ok_or(JVMError::IndexOutOfBounds {
+                index: offset,
+                max: self.len() * size,
+            })?;
+        let start = offset - first * size;
+        raw[start..start + bytes.len()].copy_from_slice(bytes);
+        for (i, chunk) in raw.chunks_exact(size).enumerate() {
+            let index = first + i;
+            match self {
+                ArrayStorage::Byte(v) => v[index] = chunk[0] as i8,
+                ArrayStorage::Char(v) => v[index] = u16::from_ne_bytes([chunk[0], chunk[1]]),
+                ArrayStorage::Short(v) => v[index] = i16::from_ne_bytes([chunk[0], chunk[1]]),
+                ArrayStorage::Int(v) => v[index] = i32::from_ne_bytes(chunk.try_into().unwrap()),
+                ArrayStorage::Long(v) => v[index] = i64::from_ne_bytes(chunk.try_into().unwrap()),
+                ArrayStorage::Float(v) => {
+                    v[index] = f32::from_ne_bytes(chunk.try_into().unwrap())
+                }
+                ArrayStorage::Double(v) => {
+                    v[index] = f64::from_ne_bytes(chunk.try_into().unwrap())
+                }
+                ArrayStorage::Reference(_) => {
+                    return Err(JVMError::TypeMismatch {
+                        expected: "primitive array".to_string(),
+                        found: "reference array".to_string(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn element_bytes(&self, index: usize) -> Option<Vec<u8>> {
+        match self {
+            ArrayStorage::Byte(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Char(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Short(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Int(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Long(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Float(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Double(v) => v.get(index).map(|e| e.to_ne_bytes().to_vec()),
+            ArrayStorage::Reference(_) => None,
+        }
+    }
+
+    pub fn references(&self) -> &[Option<Arc<Object>>] {
+        match self {
+            ArrayStorage::Reference(v) => v,
+            _ => &[],
+        }
+    }
+
+    pub fn to_values(&self) -> Vec<Value> {
+        (0..self.len()).filter_map(|i| self.get(i)).collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct Object {
     pub class: Option<Arc<LoadedClass>>,
@@ -85,33 +290,91 @@ impl Object {
         }
     }
 
+    // Marsaglia xorshift like HotSpot's default hashCode mode, never 0
+    pub fn identity_hash(&self) -> i32 {
+        let mut header = self.header.borrow_mut();
+        if let Some(hash) = header.identity_hash {
+            return hash;
+        }
+        let mut x = HASH_SEED.load(Ordering::Relaxed);
+        let hash = loop {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            let hash = (x & 0x7fff_ffff) as i32;
+            if hash != 0 {
+                break hash;
+            }
+        };
+        HASH_SEED.store(x, Ordering::Relaxed);
+        header.identity_hash = Some(hash);
+        hash
+    }
+
+    // field-by-field copy for Object.clone, the copy gets a fresh header and monitor
+    pub fn shallow_copy(&self) -> Self {
+        let kind = match &self.kind {
+            ObjectKind::ClassInstance { fields } => ObjectKind::ClassInstance {
+                fields: RefCell::new(fields.borrow().clone()),
+            },
+            ObjectKind::ArrayInstance {
+                length,
+                elements,
+                element_type,
+            } => ObjectKind::ArrayInstance {
+                length: *length,
+                elements: RefCell::new(elements.borrow().clone()),
+                element_type: element_type.clone(),
+            },
+        };
+        Object {
+            class: self.class.clone(),
+            header: RefCell::new(ObjectHeader::new()),
+            kind,
+            monitor: Arc::new(Mutex::new(())),
+        }
+    }
+
     pub fn get_field(&self, name: &str) -> Result<Value, JVMError> {
+        self.get_field_at(self.field_slot(name)?)
+    }
+
+    pub fn set_field(&self, name: &str, value: Value) -> Result<(), JVMError> {
+        self.set_field_at(self.field_slot(name)?, value)
+    }
+
+    fn field_slot(&self, name: &str) -> Result<usize, JVMError> {
+        let class = self
+            .class
+            .as_ref()
+            .ok_or_else(|| JVMError::Other("No class for instance".to_string()))?;
+        class
+            .instance_fields_indices
+            .get(name)
+            .copied()
+            .ok_or_else(|| JVMError::Other(format!("Instance field {} not found", name)))
+    }
+
+    pub fn get_field_at(&self, slot: usize) -> Result<Value, JVMError> {
         if let ObjectKind::ClassInstance { fields } = &self.kind {
-            let class = self
-                .class
-                .as_ref()
-                .ok_or_else(|| JVMError::Other("No class for instance".to_string()))?;
-            let index = class
-                .instance_fields_indices
-                .get(name)
-                .ok_or_else(|| JVMError::Other(format!("Instance field {} not found", name)))?;
-            Ok(fields.borrow()[*index].clone())
+            let fields = fields.borrow();
+            fields.get(slot).cloned().ok_or(JVMError::IndexOutOfBounds {
+                index: slot,
+                max: fields.len(),
+            })
         } else {
             Err(JVMError::Other("Field access on array object".to_string()))
         }
     }
 
-    pub fn set_field(&self, name: &str, value: Value) -> Result<(), JVMError> {
+    pub fn set_field_at(&self, slot: usize, value: Value) -> Result<(), JVMError> {
         if let ObjectKind::ClassInstance { fields } = &self.kind {
-            let class = self
-                .class
-                .as_ref()
-                .ok_or_else(|| JVMError::Other("No class for instance".to_string()))?;
-            let index = class
-                .instance_fields_indices
-                .get(name)
-                .ok_or_else(|| JVMError::Other(format!("Instance field {} not found", name)))?;
-            fields.borrow_mut()[*index] = value;
+            let mut fields = fields.borrow_mut();
+            let max = fields.len();
+            let field = fields
+                .get_mut(slot)
+                .ok_or(JVMError::IndexOutOfBounds { index: slot, max })?;
+            *field = value;
             Ok(())
         } else {
             Err(JVMError::Other("Field access on array object".to_string()))
@@ -119,12 +382,19 @@ impl Object {
     }
 
     pub fn new_array(class: Option<Arc<LoadedClass>>, length: usize, element_type: &str) -> Self {
-        let elements = vec![Value::Default; length];
+        Self::new_array_with(class, element_type, ArrayStorage::new(element_type, length))
+    }
+
+    pub fn new_array_with(
+        class: Option<Arc<LoadedClass>>,
+        element_type: &str,
+        elements: ArrayStorage,
+    ) -> Self {
         Object {
             class,
             header: RefCell::new(ObjectHeader::new()),
             kind: ObjectKind::ArrayInstance {
-                length,
+                length: elements.len(),
                 elements: RefCell::new(elements),
                 element_type: element_type.to_string(),
             },
@@ -137,14 +407,13 @@ impl Object {
             elements, length, ..
         } = &self.kind
         {
-            if index < *length {
-                Ok(elements.borrow()[index].clone())
-            } else {
-                Err(JVMError::IndexOutOfBounds {
+            elements
+                .borrow()
+                .get(index)
+                .ok_or(JVMError::IndexOutOfBounds {
                     index,
                     max: *length,
                 })
-            }
         } else {
             Err(JVMError::Other(
                 "Element access on class instance".to_string(),
@@ -153,19 +422,8 @@ impl Object {
     }
 
     pub fn set_element(&self, index: usize, value: Value) -> Result<(), JVMError> {
-        if let ObjectKind::ArrayInstance {
-            elements, length, ..
-        } = &self.kind
-        {
-            if index < *length {
-                elements.borrow_mut()[index] = value;
-                Ok(())
-            } else {
-                Err(JVMError::IndexOutOfBounds {
-                    index,
-                    max: *length,
-                })
-            }
+        if let ObjectKind::ArrayInstance { elements, .. } = &self.kind {
+            elements.borrow_mut().set(index, value)
         } else {
             Err(JVMError::Other(
                 "Element access on class instance".to_string(),
@@ -76,30 +76,39 @@ pub fn parse_descriptor(descriptor: &str) -> Result<ParsedDescriptor, String> {
                 _ => return Err(format!("Unexpected character in descriptor: {}", c)),
             }
         } else {
-            // After ')', parse return type
-            if chars.is_empty() {
-                return Ok(ParsedDescriptor {
-                    arg_types,
-                    return_type: c.to_string(),
-                });
-            } else {
-                return Err("Invalid descriptor: extra characters after return type".to_string());
+            // After ')', the rest is the return type
+            let return_type = std::iter::once(c).chain(chars.drain(..)).collect::<String>();
+            let valid = match return_type.as_bytes()[0] {
+                b'L' => return_type.ends_with(';'),
+                b'[' => !return_type.trim_start_matches('[').is_empty(),
+                _ => return_type.len() == 1,
+            };
+            if !valid {
+                return Err("Invalid descriptor: malformed return type".to_string());
             }
+            return Ok(ParsedDescriptor {
+                arg_types,
+                return_type,
+            });
         }
     }
 
     Err("Invalid descriptor: missing ')' or return type".to_string())
 }
 
-// Helper for parse_return_type (assuming it exists)
+// libffi type of a descriptor type as native code sees it, references are pointers
 pub fn parse_return_type(return_type: &str) -> Result<Type, String> {
-    match return_type {
-        "V" => Ok(Type::void()),
-        "I" | "B" | "S" | "C" | "Z" => Ok(Type::i32()),
-        "J" => Ok(Type::i64()),
-        "F" => Ok(Type::f32()),
-        "D" => Ok(Type::f64()),
-        "L" | "[" => Ok(Type::pointer()), // Simplified for references/arrays
+    match return_type.chars().next() {
+        Some('V') => Ok(Type::void()),
+        Some('Z') => Ok(Type::u8()),
+        Some('B') => Ok(Type::i8()),
+        Some('C') => Ok(Type::u16()),
+        Some('S') => Ok(Type::i16()),
+        Some('I') => Ok(Type::i32()),
+        Some('J') => Ok(Type::i64()),
+        Some('F') => Ok(Type::f32()),
+        Some('D') => Ok(Type::f64()),
+        Some('L') | Some('[') => Ok(Type::pointer()),
         _ => Err(format!("Unsupported return type: {}", return_type)),
     }
 }
@@ -1,6 +1,8 @@
 use super::class_loader::loaded_class::{LoadedClass, NameDes};
 use super::jvm_error::JVMError;
+use super::native::extract_string;
 use super::object::Object;
+use super::state::STACK_SIZE;
 use parser::attribute::Code;
 use parser::constant_pool::ConstantPool;
 use std::{collections::HashMap, sync::Arc};
@@ -17,6 +19,7 @@ pub enum Value {
 
 #[derive(Debug, Clone)]
 pub struct Frame {
+    pub class: Arc<LoadedClass>,
     pub constant_pool: Arc<ConstantPool>,
     pub method_name_des: NameDes,
     pub code: Arc<Code>,
@@ -30,6 +33,7 @@ impl Frame {
 //        println!("{:}",class.class_name);
         Frame {
             constant_pool: Arc::clone(&class.constant_pool),
+            class,
             method_name_des: name_des.clone(),
             code: Arc::clone(&code),
             pc: 0,
@@ -59,19 +63,75 @@ impl Frame {
     pub fn get_local(&mut self, index: usize) -> Option<&Value> {
         self.locals.get(index)
     }
+
+    // where the frame is, as a line of an exception's stack trace. There is no SourceFile
+    // attribute to read, so the file is named after the top level class as javac does.
+    pub fn trace_element(&self) -> String {
+        let class_name = self.class.class_name.replace('/', ".");
+        let location = match self.code.get_line_at_index(self.pc) {
+            Some(line) => {
+                let simple_name = class_name.rsplit('.').next().unwrap_or(&class_name);
+                let top_level = simple_name.split('$').next().unwrap_or(simple_name);
+                format!("{}.java:{}", top_level, line)
+            }
+            None => "Unknown Source".to_string(),
+        };
+        format!("{}.{}({})", class_name, self.method_name_des.name, location)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Stack {
     pub frames: Vec<Frame>,
     pub max_stack_size: usize,
+    // the frames an exception has left uncaught so far, innermost first
+    pub backtrace: Vec<String>,
+    // the object of the exception being thrown until a handler takes it, None for the
+    // exceptions the VM raises by class name
+    pub exception: Option<Arc<Object>>,
 }
 
 impl Stack {
     pub fn new() -> Self {
         Stack {
             frames: Vec::new(),
-            max_stack_size: 1024,
+            max_stack_size: *STACK_SIZE.lock().unwrap(),
+            backtrace: Vec::new(),
+            exception: None,
+        }
+    }
+
+    // the detail message of the exception of class_name being thrown, if it was thrown as an
+    // object that has one
+    pub fn exception_message(&self, class_name: &str) -> Option<String> {
+        let exception = self.exception.as_ref()?;
+        if exception.class.as_ref()?.class_name != class_name {
+            return None;
+        }
+        match exception.get_field("detailMessage") {
+            Ok(Value::Reference(Some(message))) => extract_string(&message).ok(),
+            _ => None,
+        }
+    }
+
+    // the object of the exception of class_name being thrown, exceptions the VM raised by name
+    // have none
+    pub fn take_exception(&mut self, class_name: &str) -> Option<Arc<Object>> {
+        self.exception.take().filter(|object| {
+            object.class.as_ref().map(|class| class.class_name.as_str()) == Some(class_name)
+        })
+    }
+
+    // reports an exception no frame caught as the default uncaught exception handler does
+    pub fn print_uncaught(&self, thread_name: &str, class_name: &str) {
+        // as Throwable.toString puts it
+        let description = match self.exception_message(class_name) {
+            Some(message) => format!("{}: {}", class_name.replace('/', "."), message),
+            None => class_name.replace('/', "."),
+        };
+        eprintln!("Exception in thread \"{}\" {}", thread_name, description);
+        for element in &self.backtrace {
+            eprintln!("\tat {}", element);
         }
     }
 
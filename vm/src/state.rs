@@ -19,10 +19,24 @@ pub static SERVER_STATE: Lazy<Arc<Mutex<VecDeque<MessageData>>>> =
 
 pub static GLOBAL_BOOL: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
-pub static MEMORY_SIZE: Lazy<Arc<Mutex<usize>>> = Lazy::new(|| Arc::new(Mutex::new(1024)));
+pub static MEMORY_SIZE: Lazy<Arc<Mutex<usize>>> = Lazy::new(|| Arc::new(Mutex::new(4096)));
 
 pub static MEMORY_SNAP: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
 
 pub static FILE_NAME: Lazy<Arc<Mutex<String>>> = Lazy::new(|| Arc::new(Mutex::new("dump.json".to_string())));
 
 pub static VIS_BOOL: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
+
+pub static IC_STATS: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
+
+pub static JIT_TRACE: Lazy<Arc<Mutex<bool>>> = Lazy::new(|| Arc::new(Mutex::new(false)));
+
+// the most frames a Stack holds before StackOverflowError, -Xss on the command line
+pub static STACK_SIZE: Lazy<Arc<Mutex<usize>>> = Lazy::new(|| Arc::new(Mutex::new(1024)));
+
+// a system property name and its value
+pub type Property = (String, String);
+
+// -D properties from the command line, in order, later ones win. VM::new takes them over.
+pub static SYSTEM_PROPERTIES: Lazy<Arc<Mutex<Vec<Property>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
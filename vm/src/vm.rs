@@ -1,31 +1,61 @@
 use crate::class_loader::class_loading_error::ClassLoadingError;
-use crate::class_loader::loaded_class::LoadedClass;
-use crate::state::{Header, MessageData, GLOBAL_BOOL, SERVER_STATE, VIS_BOOL};
+use crate::class_loader::java_home::JavaHome;
+use crate::class_loader::jimage::is_jimage;
+use crate::class_loader::loaded_class::{LoadedClass, ResolvedMethod};
+use crate::execute::execute::ExecutionResult;
+use crate::execute::execute_native::call_native;
+use crate::state::{
+    Header, MessageData, Property, GLOBAL_BOOL, SERVER_STATE, SYSTEM_PROPERTIES, VIS_BOOL,
+};
 use serde_json::json;
 //use crate::native::NativeMethodLoader;
-use super::native::NativeStack;
+use super::native::{NativeContext, NativeStack};
 use parser::instruction::*;
 
 use super::class_loader::class_loader::ClassLoader;
 use super::class_loader::loaded_class::NameDes;
 use super::heap::Heap;
+use super::inline_cache::InlineCacheStats;
+use super::intrinsics::intrinsics::{string_array, IntrinsicRegistry};
 use super::jvm_error::JVMError;
+use super::object::Object;
 use super::runtime::*;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use std::env;
-use std::path::PathBuf;
 
 pub struct VM {
     pub stack: Arc<RwLock<Stack>>,
     pub class_loader: ClassLoader,
     pub heap: Arc<RwLock<Heap>>,
     pub native_stack: NativeStack,
+    pub intrinsics: IntrinsicRegistry,
+    // None when the VM boots from the class library bundled with it
+    pub java_home: Option<JavaHome>,
+    // system properties set from outside, -D on the command line or the builder. They take
+    // the place of the defaults vmProperties and platformProperties report.
+    pub property_overrides: Vec<Property>,
+    #[cfg(feature = "jit")]
+    pub jit: std::sync::Mutex<crate::jit::jit::Jit>,
 }
 
 impl VM {
-    pub async fn new(heap_size: usize) -> Self {
+    // a VM on the runtime JAVA_HOME or the usual locations lead to, see JavaHome::find. An
+    // error when there is none.
+    pub async fn new(heap_size: usize) -> Result<Self, JVMError> {
+        let java_home = JavaHome::find(None).map_err(|e| JVMError::Other(e.to_string()))?;
+        let properties = SYSTEM_PROPERTIES.lock().unwrap().clone();
+        Self::with_config(heap_size, Some(java_home), properties).await
+    }
+
+    // a VM booting from the java.base of java_home, or the bundled class library without one,
+    // whose system properties are overridden by properties, later ones win. An error when the
+    // startup sequence fails, on a missing class or a heap too small for it.
+    pub async fn with_config(
+        heap_size: usize,
+        java_home: Option<JavaHome>,
+        properties: Vec<Property>,
+    ) -> Result<Self, JVMError> {
         let init_json = MessageData {
             header: Header::DATA,
             json: json!({"header": "init", "memory size": heap_size}).to_string(),
@@ -39,40 +69,184 @@ impl VM {
             class_loader: ClassLoader::new(),
             heap: Arc::new(RwLock::new(Heap::new(heap_size))),
             native_stack: NativeStack::new(),
+            intrinsics: IntrinsicRegistry::with_java_base(),
+            java_home,
+            property_overrides: properties,
+            #[cfg(feature = "jit")]
+            jit: std::sync::Mutex::new(crate::jit::jit::Jit::new()),
         };
         vm.preload_classes()
             .await
-            .expect("Failed to preload classes");
-        vm.register_native_methods();
-        vm
+            .map_err(|e| JVMError::Other(format!("failed to preload classes: {}", e)))?;
+        vm.initialize_system().await?;
+        Ok(vm)
+    }
+
+    // the startup sequence HotSpot runs before main: the system and main thread groups, the
+    // main thread, then System.initPhase1 which sets up the system properties and System.in,
+    // System.out and System.err
+    async fn initialize_system(&self) -> Result<(), JVMError> {
+        // the bundled class library has no threads, its System sets up the streams in <clinit>
+        if self.java_home.is_none() {
+            let system = self
+                .class_loader
+                .load_class("java/lang/System", self)
+                .await
+                .map_err(|e| JVMError::Other(e.to_string()))?;
+            return LoadedClass::initialize(system, self).await;
+        }
+        let stack = Stack::new();
+        let system_group = self.allocate_object(&stack, "java/lang/ThreadGroup").await?;
+        self.invoke_special(&system_group, "()V", vec![]).await?;
+        let main_group = self.allocate_object(&stack, "java/lang/ThreadGroup").await?;
+        let main_name = self.heap.write().await.allocate_string(&stack, self, "main").await?;
+        self.invoke_special(
+            &main_group,
+            "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V",
+            vec![system_group, main_name.clone()],
+        )
+        .await?;
+
+        // the constructor finds the thread being built through Thread.currentThread
+        let thread = match self.allocate_object(&stack, "java/lang/Thread").await? {
+            Value::Reference(Some(thread)) => thread,
+            _ => return Err(JVMError::NullReference),
+        };
+        self.heap.write().await.main_thread = Some(Arc::clone(&thread));
+        // up to JDK 18 the constructor copies the priority of the current thread, later
+        // versions keep it in Thread.FieldHolder and set it themselves
+        let _ = thread.set_field("priority", Value::Int(5));
+        self.invoke_special(
+            &Value::Reference(Some(thread)),
+            "(Ljava/lang/ThreadGroup;Ljava/lang/String;)V",
+            vec![main_group, main_name],
+        )
+        .await?;
+
+        let system = self
+            .class_loader
+            .load_class("java/lang/System", self)
+            .await
+            .map_err(|e| JVMError::Other(e.to_string()))?;
+        LoadedClass::initialize(Arc::clone(&system), self).await?;
+        let init_phase1 = NameDes {
+            name: "initPhase1".to_string(),
+            des: "()V".to_string(),
+        };
+        self.run_method(&system, &init_phase1, vec![]).await?;
+        Ok(())
+    }
+
+    // runs the constructor of the given descriptor on an object allocated by the VM
+    async fn invoke_special(
+        &self,
+        object: &Value,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> Result<(), JVMError> {
+        let class = match object {
+            Value::Reference(Some(object)) => object.class.clone(),
+            _ => None,
+        }
+        .ok_or(JVMError::NullReference)?;
+        let name_des = NameDes {
+            name: "<init>".to_string(),
+            des: descriptor.to_string(),
+        };
+        let mut locals = vec![object.clone()];
+        locals.extend(args);
+        self.run_method(&class, &name_des, locals).await?;
+        Ok(())
+    }
+
+    // runs a method to completion on a stack of its own, args include the receiver
+    async fn run_method(
+        &self,
+        class: &Arc<LoadedClass>,
+        name_des: &NameDes,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, JVMError> {
+        let method = LoadedClass::resolve_method(class, name_des)?;
+        self.call_method(&method, args).await
+    }
+
+    // runs a resolved method to completion on a stack of its own and returns its result, args
+    // include the receiver of instance methods. An exception it does not catch comes back as
+    // JVMError::UncaughtException.
+    pub async fn call_method(
+        &self,
+        method: &ResolvedMethod,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, JVMError> {
+        self.call_method_on(&mut Stack::new(), method, args).await
+    }
+
+    // call_method on an empty stack of the caller's, which holds the exception object of an
+    // uncaught exception afterwards
+    pub async fn call_method_on(
+        &self,
+        stack: &mut Stack,
+        method: &ResolvedMethod,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, JVMError> {
+        if method.is_native() {
+            return match call_native(method, args, stack, self).await? {
+                ExecutionResult::Throw(exception) => {
+                    stack.exception = self.native_stack.jni.take_thrown(&exception);
+                    Err(JVMError::UncaughtException(exception))
+                }
+                ExecutionResult::Return(value) => Ok(value),
+                _ => Ok(None),
+            };
+        }
+        let code = method.code()?;
+        let mut frame = Frame::new(Arc::clone(&method.class), &method.name_des, code);
+        let mut index = 0;
+        for arg in args {
+            let width = match arg {
+                Value::Long(_) | Value::Double(_) => 2,
+                _ => 1,
+            };
+            frame.set_local(index, arg);
+            index += width;
+        }
+        stack.push_frame(frame)?;
+        Box::pin(stack.execute_current_frame(self)).await
     }
 
     async fn preload_classes(&mut self) -> Result<(), ClassLoadingError> {
-        let classes = [
+        let mut classes = vec![
             "java/lang/Object",
             "java/lang/String",
             "java/io/Serializable",
             "java/lang/Comparable",
             "java/lang/CharSequence",
-            "java/lang/constant/Constable",
-            "java/lang/constant/ConstantDesc",
-            "java/lang/Class",
         ];
-        let _ = self.class_loader.add_jar_entry(BASE.to_string());
-        let exe_path = env::current_exe().expect("Failed to get current exe path");
-        let exe_dir = exe_path.parent().expect("Failed to get exe directory");
-        let lib_path = exe_dir
-            .join("../../IO/")
-            .to_str()
-            .expect("Failed to convert path to string")
-            .to_string();
-        let _ = self.class_loader.add_directory_entry(lib_path);
+        // interfaces of String the bundled library does without
+        if self.java_home.is_some() {
+            classes.extend(["java/lang/constant/Constable", "java/lang/constant/ConstantDesc"]);
+        }
+        classes.push("java/lang/Class");
+        self.add_java_base()?;
         for class_name in classes.iter() {
             self.class_loader.load_class(class_name, self).await?;
         }
         Ok(())
     }
 
+    // the boot class path, a jimage, a jmod or the bundled class library
+    fn add_java_base(&mut self) -> Result<(), ClassLoadingError> {
+        let Some(java_home) = &self.java_home else {
+            return self.class_loader.add_bundled_entry();
+        };
+        let java_base = java_home.java_base.to_string_lossy().into_owned();
+        if is_jimage(&java_home.java_base) {
+            self.class_loader.add_jimage_entry(java_base)
+        } else {
+            self.class_loader.add_jar_entry(java_base)
+        }
+    }
+
     async fn preinitialize_classes(&mut self) -> Result<(), ClassLoadingError> {
         let classes = [
             "java/lang/Object",
@@ -83,7 +257,7 @@ impl VM {
             "java/lang/constant/Constable",
             "java/lang/constant/ConstantDesc",
         ];
-        let _ = self.class_loader.add_jar_entry(BASE.to_string());
+        self.add_java_base()?;
         for class_name in classes.iter() {
             let class = self.class_loader.load_class(class_name, self).await?;
             LoadedClass::initialize(class, self).await.unwrap();
@@ -91,70 +265,48 @@ impl VM {
         Ok(())
     }
 
-    fn register_native_methods(&mut self) {
-        let exe_path = env::current_exe().expect("Failed to get current exe path");
-        let exe_dir = exe_path.parent().expect("Failed to get exe directory");
-        let lib_path = exe_dir.join("../../IO/libnative_io.so");
-        self.native_stack
-            .register_library("native_io", lib_path)
-            .expect("Failed to load libnative_io.so");
-        let prints_key = NameDes {
-            name: "prints".to_string(),
-            des: "(Ljava/lang/String;)V".to_string(),
-        };
-        self.native_stack
-            .register_method(prints_key, "native_io")
-            .expect("Failed to register Java_ioTer_prints");
-        let printca_key = NameDes {
-            name: "printca".to_string(),
-            des: "([C)V".to_string(),
-        };
-        self.native_stack
-            .register_method(printca_key, "native_io")
-            .expect("Failed to register Java_ioTer_printca");
-        let print_dec_key = NameDes {
-            name: "printd".to_string(),
-            des: "(D)V".to_string(),
-        };
-        self.native_stack
-            .register_method(print_dec_key, "native_io")
-            .expect("Failed to register Java_ioTer_printd");
-        let print_int_key = NameDes {
-            name: "printi".to_string(),
-            des: "(I)V".to_string(),
-        };
-        self.native_stack
-            .register_method(print_int_key, "native_io")
-            .expect("Failed to register Java_ioTer_printi");
-        let scan_int_key = NameDes {
-            name: "scani".to_string(),
-            des: "()I".to_string(),
-        };
-        self.native_stack
-            .register_method(scan_int_key, "native_io")
-            .expect("Failed to register Java_ioTer_scani");
-        let scan_double_key = NameDes {
-            name: "scand".to_string(),
-            des: "()D".to_string(),
-        };
-        self.native_stack
-            .register_method(scan_double_key, "native_io")
-            .expect("Failed to register Java_ioTer_scand");
-    }
-
-    pub async fn invoke_main(&self, class_name: &str) -> Result<(), JVMError> {
+    // runs the main method of a class with args as its String[]. As JEP 463 launches them,
+    // main(String[]) is preferred over main() and either may be static or an instance method
+    // of an object made with the no-arg constructor.
+    pub async fn invoke_main(&self, class_name: &str, args: &[String]) -> Result<(), JVMError> {
         let main_class = self
             .class_loader
             .load_class(class_name, self)
             .await
-            .map_err(|e| JVMError::Other(e.to_string()))
-            .unwrap();
-        let main_name_des = NameDes {
-            name: "main".to_string(),
-            des: "([Ljava/lang/String;)V".to_string(),
-        };
-        let (main_class, main_code) = Frame::lookup_method(&main_class, &main_name_des)?;
-        let main_frame = Frame::new(main_class, &main_name_des, main_code);
+            .map_err(|e| JVMError::Other(e.to_string()))?;
+        LoadedClass::initialize(Arc::clone(&main_class), self).await?;
+        let main = ["([Ljava/lang/String;)V", "()V"]
+            .into_iter()
+            .find_map(|des| {
+                let name_des = NameDes {
+                    name: "main".to_string(),
+                    des: des.to_string(),
+                };
+                LoadedClass::resolve_method(&main_class, &name_des)
+                    .ok()
+                    .filter(|method| !method.is_private() && !method.is_abstract())
+            })
+            .ok_or_else(|| JVMError::MethodNotFound {
+                class: class_name.to_string(),
+                name: "main".to_string(),
+                descriptor: "([Ljava/lang/String;)V".to_string(),
+            })?;
+
+        let setup = Stack::new();
+        let mut locals = Vec::new();
+        if !main.is_static() {
+            let receiver = self.allocate_object(&setup, class_name).await?;
+            self.invoke_special(&receiver, "()V", vec![]).await?;
+            locals.push(receiver);
+        }
+        if main.name_des.des != "()V" {
+            let args = args.iter().cloned().map(Some).collect::<Vec<_>>();
+            locals.push(string_array(self, &setup, &args).await?);
+        }
+        let mut main_frame = Frame::new(Arc::clone(&main.class), &main.name_des, main.code()?);
+        for (index, local) in locals.into_iter().enumerate() {
+            main_frame.set_local(index, local);
+        }
 
         let mut stack = self.stack.write().await;
         {
@@ -179,23 +331,152 @@ impl VM {
         Ok(())
     }
 
+    // the value a system property was given from outside, None if it keeps its default
+    pub fn property_override(&self, name: &str) -> Option<&str> {
+        self.property_overrides
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // what DestroyJavaVM does once main returns, runs the shutdown hooks
+    pub async fn shutdown(&self) -> Result<(), JVMError> {
+        let class = self
+            .class_loader
+            .load_class("java/lang/Shutdown", self)
+            .await
+            .map_err(|e| JVMError::Other(e.to_string()))?;
+        let name_des = NameDes {
+            name: "shutdown".to_string(),
+            des: "()V".to_string(),
+        };
+        self.run_method(&class, &name_des, vec![]).await?;
+        Ok(())
+    }
+
+    // runs main as the java launcher does and returns the exit status: that of System.exit or
+    // Runtime.halt, 1 when main throws, 0 otherwise
+    pub async fn run_main(&self, class_name: &str, args: &[String]) -> i32 {
+        let dotted_name = class_name.replace('/', ".");
+        if let Err(error) = self.class_loader.load_class(class_name, self).await {
+            eprintln!("Error: Could not find or load main class {}", dotted_name);
+            eprintln!("Caused by: {}", error);
+            return 1;
+        }
+        let status = match self.invoke_main(class_name, args).await {
+            Ok(()) => 0,
+            Err(JVMError::Exit(status)) => return status,
+            Err(JVMError::MethodNotFound { class, name, .. })
+                if class == class_name && name == "main" =>
+            {
+                eprintln!(
+                    "Error: Main method not found in class {}, please define the main method as:",
+                    dotted_name
+                );
+                eprintln!("   public static void main(String[] args)");
+                return 1;
+            }
+            Err(JVMError::UncaughtException(exception)) => {
+                self.stack.read().await.print_uncaught("main", &exception);
+                1
+            }
+            Err(error) => {
+                eprintln!("Error: {:?}", error);
+                1
+            }
+        };
+        match self.shutdown().await {
+            Ok(()) => status,
+            // a hook halted the VM
+            Err(JVMError::Exit(status)) => status,
+            Err(error) => {
+                eprintln!("Error: {:?}", error);
+                1
+            }
+        }
+    }
+
     pub async fn allocate_object(
         &self,
         stack: &Stack,
         class_name: &str,
     ) -> Result<Value, JVMError> {
+        // <clinit> may allocate, so it runs before the heap lock is taken
+        let class = Box::pin(self.class_loader.load_class(class_name, self))
+            .await
+            .map_err(|e| JVMError::Other(e.to_string()))?;
+        Box::pin(LoadedClass::initialize(class, self)).await?;
         let mut heap = self.heap.write().await;
         heap.allocate_object(stack, self, class_name).await
     }
 
+    // a Throwable of class_name built by its (Ljava/lang/String;)V constructor, or by its
+    // no-argument one when there is no message
+    pub async fn new_throwable(
+        &self,
+        stack: &Stack,
+        class_name: &str,
+        message: Option<&str>,
+    ) -> Result<Arc<Object>, JVMError> {
+        let throwable = self.allocate_object(stack, class_name).await?;
+        match message {
+            Some(message) => {
+                let message = {
+                    let mut heap = self.heap.write().await;
+                    heap.allocate_string(stack, self, message).await?
+                };
+                self.invoke_special(&throwable, "(Ljava/lang/String;)V", vec![message])
+                    .await?;
+            }
+            None => self.invoke_special(&throwable, "()V", Vec::new()).await?,
+        }
+        match throwable {
+            Value::Reference(Some(throwable)) => Ok(throwable),
+            _ => Err(JVMError::NullReference),
+        }
+    }
+
     pub async fn allocate_array(
         &self,
         stack: &Stack,
         element_type: &str,
         length: usize,
     ) -> Result<Value, JVMError> {
+        // the array class is loaded before taking the heap lock, loading may allocate
+        let array_class = Box::pin(
+            self.class_loader
+                .load_class(&format!("[{}", element_type), self),
+        )
+        .await
+        .map_err(|e| JVMError::Other(e.to_string()))?;
         let mut heap = self.heap.write().await;
-        heap.allocate_array(stack, self, element_type, length).await
+        heap.allocate_array(stack, self, array_class, length).await
+    }
+
+    // implements a native method of class_name with a Rust closure, which takes precedence
+    // over the native libraries
+    pub fn register_rust_native(
+        &self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+        function: impl Fn(&NativeContext, Vec<Value>) -> Result<ExecutionResult, JVMError>
+            + 'static,
+    ) {
+        self.native_stack
+            .register_rust_native(class_name, name, descriptor, function);
+    }
+
+    pub fn inline_cache_stats(&self) -> Vec<InlineCacheStats> {
+        let mut stats = self
+            .class_loader
+            .loaded_classes()
+            .iter()
+            .flat_map(|class| class.inline_cache_stats())
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+        stats
     }
 
     pub async fn memory_snap(&self) {
@@ -211,4 +492,3 @@ pub fn convert_instructions(instructions: Vec<Instruction>) -> Vec<Operation> {
         .collect()
 }
 
-const BASE: &str = "/usr/lib/jvm/java-24-openjdk/jmods/java.base.jmod";